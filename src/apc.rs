@@ -0,0 +1,86 @@
+// application program commands longer than this are dropped. kitty graphics
+// commands have to split payloads of more than 4096 bytes into chunks, so
+// this leaves plenty of room for a chunk and its control data.
+const MAX_APC_LEN: usize = 4 * 4096;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    Apc,
+}
+
+// vte recognizes application program commands (`\e_...\e\\`), but throws
+// away their contents, so we have to watch for them ourselves. this tracks
+// just enough of the parser state to know where they start and end, which is
+// simple because an escape character starts a new escape sequence no matter
+// what state the parser is in.
+#[derive(Clone, Debug)]
+pub struct Scanner {
+    state: State,
+    data: Vec<u8>,
+    overflowed: bool,
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            data: vec![],
+            overflowed: false,
+        }
+    }
+
+    // returns the position just after the end of the next complete command
+    // in bytes (the escape character which terminates it is not included),
+    // along with the contents of the command
+    pub fn scan(&mut self, bytes: &[u8]) -> Option<(usize, Vec<u8>)> {
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            match self.state {
+                State::Ground => {
+                    // fast path for the common case of not being in an
+                    // escape sequence at all
+                    match bytes[i..].iter().position(|&b| b == 0x1b) {
+                        Some(offset) => {
+                            i += offset;
+                            self.state = State::Escape;
+                        }
+                        None => return None,
+                    }
+                }
+                State::Escape => match b {
+                    b'_' => {
+                        self.state = State::Apc;
+                        self.data.clear();
+                        self.overflowed = false;
+                    }
+                    // control characters other than cancel and substitute
+                    // are executed without leaving the escape sequence
+                    0x1b | 0x00..=0x17 | 0x19 | 0x1c..=0x1f => {}
+                    _ => self.state = State::Ground,
+                },
+                State::Apc => match b {
+                    0x1b => {
+                        self.state = State::Escape;
+                        let data = std::mem::take(&mut self.data);
+                        if !self.overflowed {
+                            return Some((i, data));
+                        }
+                    }
+                    0x18 | 0x1a => self.state = State::Ground,
+                    _ => {
+                        if self.data.len() < MAX_APC_LEN {
+                            self.data.push(b);
+                        } else {
+                            self.overflowed = true;
+                        }
+                    }
+                },
+            }
+            i += 1;
+        }
+        None
+    }
+}
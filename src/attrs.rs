@@ -14,21 +14,84 @@ pub enum Color {
     Rgb(u8, u8, u8),
 }
 
-const TEXT_MODE_INTENSITY: u8 = 0b0000_0011;
-const TEXT_MODE_BOLD: u8 = 0b0000_0001;
-const TEXT_MODE_DIM: u8 = 0b0000_0010;
-const TEXT_MODE_ITALIC: u8 = 0b0000_0100;
-const TEXT_MODE_UNDERLINE: u8 = 0b0000_1000;
-const TEXT_MODE_INVERSE: u8 = 0b0001_0000;
+/// Represents the style of line used to underline cells.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Default)]
+pub enum UnderlineStyle {
+    /// The cell is not underlined.
+    #[default]
+    None,
+
+    /// A single straight line.
+    Single,
+
+    /// Two straight lines.
+    Double,
+
+    /// A wavy line (also known as undercurl).
+    Curly,
+
+    /// A dotted line.
+    Dotted,
+
+    /// A dashed line.
+    Dashed,
+}
+
+impl UnderlineStyle {
+    pub(crate) fn from_param(param: u16) -> Option<Self> {
+        match param {
+            0 => Some(Self::None),
+            1 => Some(Self::Single),
+            2 => Some(Self::Double),
+            3 => Some(Self::Curly),
+            4 => Some(Self::Dotted),
+            5 => Some(Self::Dashed),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn param(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Single => 1,
+            Self::Double => 2,
+            Self::Curly => 3,
+            Self::Dotted => 4,
+            Self::Dashed => 5,
+        }
+    }
+}
+
+const TEXT_MODE_INTENSITY: u16 = 0b0000_0000_0000_0011;
+const TEXT_MODE_BOLD: u16 = 0b0000_0000_0000_0001;
+const TEXT_MODE_DIM: u16 = 0b0000_0000_0000_0010;
+const TEXT_MODE_ITALIC: u16 = 0b0000_0000_0000_0100;
+const TEXT_MODE_UNDERLINE: u16 = 0b0000_0000_0011_1000;
+const TEXT_MODE_UNDERLINE_SHIFT: u32 = 3;
+const TEXT_MODE_INVERSE: u16 = 0b0000_0000_0100_0000;
+const TEXT_MODE_BLINK: u16 = 0b0000_0000_1000_0000;
+const TEXT_MODE_CONCEAL: u16 = 0b0000_0001_0000_0000;
+const TEXT_MODE_STRIKETHROUGH: u16 = 0b0000_0010_0000_0000;
+const TEXT_MODE_OVERLINE: u16 = 0b0000_0100_0000_0000;
 
 #[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
 pub struct Attrs {
     pub fgcolor: Color,
     pub bgcolor: Color,
-    pub mode: u8,
+    pub underline_color: Color,
+    pub mode: u16,
+    pub hyperlink: Option<std::num::NonZeroU16>,
 }
 
 impl Attrs {
+    // sgr 0 resets everything other than the hyperlink
+    pub fn clear_sgr(&mut self) {
+        *self = Self {
+            hyperlink: self.hyperlink,
+            ..Self::default()
+        };
+    }
+
     pub fn bold(&self) -> bool {
         self.mode & TEXT_MODE_BOLD != 0
     }
@@ -37,7 +100,7 @@ impl Attrs {
         self.mode & TEXT_MODE_DIM != 0
     }
 
-    fn intensity(&self) -> u8 {
+    fn intensity(&self) -> u16 {
         self.mode & TEXT_MODE_INTENSITY
     }
 
@@ -71,12 +134,17 @@ impl Attrs {
         self.mode & TEXT_MODE_UNDERLINE != 0
     }
 
-    pub fn set_underline(&mut self, underline: bool) {
-        if underline {
-            self.mode |= TEXT_MODE_UNDERLINE;
-        } else {
-            self.mode &= !TEXT_MODE_UNDERLINE;
-        }
+    pub fn underline_style(&self) -> UnderlineStyle {
+        UnderlineStyle::from_param(
+            (self.mode & TEXT_MODE_UNDERLINE) >> TEXT_MODE_UNDERLINE_SHIFT,
+        )
+        // the underline bits are only ever set from a valid style
+        .unwrap()
+    }
+
+    pub fn set_underline_style(&mut self, style: UnderlineStyle) {
+        self.mode &= !TEXT_MODE_UNDERLINE;
+        self.mode |= u16::from(style.param()) << TEXT_MODE_UNDERLINE_SHIFT;
     }
 
     pub fn inverse(&self) -> bool {
@@ -91,12 +159,80 @@ impl Attrs {
         }
     }
 
+    pub fn blink(&self) -> bool {
+        self.mode & TEXT_MODE_BLINK != 0
+    }
+
+    pub fn set_blink(&mut self, blink: bool) {
+        if blink {
+            self.mode |= TEXT_MODE_BLINK;
+        } else {
+            self.mode &= !TEXT_MODE_BLINK;
+        }
+    }
+
+    pub fn conceal(&self) -> bool {
+        self.mode & TEXT_MODE_CONCEAL != 0
+    }
+
+    pub fn set_conceal(&mut self, conceal: bool) {
+        if conceal {
+            self.mode |= TEXT_MODE_CONCEAL;
+        } else {
+            self.mode &= !TEXT_MODE_CONCEAL;
+        }
+    }
+
+    pub fn strikethrough(&self) -> bool {
+        self.mode & TEXT_MODE_STRIKETHROUGH != 0
+    }
+
+    pub fn set_strikethrough(&mut self, strikethrough: bool) {
+        if strikethrough {
+            self.mode |= TEXT_MODE_STRIKETHROUGH;
+        } else {
+            self.mode &= !TEXT_MODE_STRIKETHROUGH;
+        }
+    }
+
+    pub fn overline(&self) -> bool {
+        self.mode & TEXT_MODE_OVERLINE != 0
+    }
+
+    pub fn set_overline(&mut self, overline: bool) {
+        if overline {
+            self.mode |= TEXT_MODE_OVERLINE;
+        } else {
+            self.mode &= !TEXT_MODE_OVERLINE;
+        }
+    }
+
     pub fn write_escape_code_diff(
         &self,
         contents: &mut Vec<u8>,
         other: &Self,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) {
-        if self != other && self == &Self::default() {
+        if self.hyperlink != other.hyperlink {
+            crate::term::Hyperlink::new(
+                self.hyperlink.and_then(|id| hyperlinks.get(id)),
+            )
+            .write_buf(contents);
+        }
+
+        // the hyperlink isn't affected by sgr sequences, so it shouldn't
+        // affect how we write them either
+        let other = &Self {
+            hyperlink: self.hyperlink,
+            ..*other
+        };
+        if self != other
+            && self
+                == &(Self {
+                    hyperlink: self.hyperlink,
+                    ..Self::default()
+                })
+        {
             crate::term::ClearAttrs.write_buf(contents);
             return;
         }
@@ -128,16 +264,41 @@ impl Attrs {
         } else {
             attrs.italic(self.italic())
         };
-        let attrs = if self.underline() == other.underline() {
+        let attrs = if self.underline_style() == other.underline_style() {
+            attrs
+        } else {
+            attrs.underline(self.underline_style())
+        };
+        let attrs = if self.underline_color == other.underline_color {
             attrs
         } else {
-            attrs.underline(self.underline())
+            attrs.underline_color(self.underline_color)
         };
         let attrs = if self.inverse() == other.inverse() {
             attrs
         } else {
             attrs.inverse(self.inverse())
         };
+        let attrs = if self.blink() == other.blink() {
+            attrs
+        } else {
+            attrs.blink(self.blink())
+        };
+        let attrs = if self.conceal() == other.conceal() {
+            attrs
+        } else {
+            attrs.conceal(self.conceal())
+        };
+        let attrs = if self.strikethrough() == other.strikethrough() {
+            attrs
+        } else {
+            attrs.strikethrough(self.strikethrough())
+        };
+        let attrs = if self.overline() == other.overline() {
+            attrs
+        } else {
+            attrs.overline(self.overline())
+        };
 
         attrs.write_buf(contents);
     }
@@ -0,0 +1,34 @@
+// decodes standard base64, with or without padding. returns None if the
+// data contains anything other than base64 characters.
+pub fn decode(data: &[u8]) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some(u32::from(c - b'A')),
+            b'a'..=b'z' => Some(u32::from(c - b'a') + 26),
+            b'0'..=b'9' => Some(u32::from(c - b'0') + 52),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let data = match data {
+        [rest @ .., b'=', b'='] | [rest @ .., b'='] => rest,
+        _ => data,
+    };
+    let mut decoded = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    for chunk in data.chunks(4) {
+        let mut bits = 0;
+        for (i, c) in chunk.iter().enumerate() {
+            bits |= value(*c)? << (18 - 6 * i);
+        }
+        let bytes = bits.to_be_bytes();
+        match chunk.len() {
+            4 => decoded.extend_from_slice(&bytes[1..4]),
+            3 => decoded.extend_from_slice(&bytes[1..3]),
+            2 => decoded.push(bytes[1]),
+            _ => return None,
+        }
+    }
+    Some(decoded)
+}
@@ -34,6 +34,22 @@ pub trait Callbacks {
     /// This callback is called when the terminal requests data to be pasted
     /// from the system clipboard (typically with `\e]52;<ty>;?\a`).
     fn paste_from_clipboard(&mut self, _: &mut crate::Screen, _ty: &[u8]) {}
+    /// This callback is called when the terminal needs to send data back to
+    /// the application, for instance in response to a cursor position
+    /// request (`\e[6n`). The given bytes should be written to the
+    /// application's input.
+    fn reply(&mut self, _: &mut crate::Screen, _data: &[u8]) {}
+    /// This callback is called when the terminal requests a desktop
+    /// notification to be shown (typically with `\e]9;<body>\a`,
+    /// `\e]777;notify;<title>;<body>\a` or `\e]99;<metadata>;<payload>\a`).
+    /// Notifications which are sent in several chunks are only reported
+    /// once they are complete.
+    fn notify(
+        &mut self,
+        _: &mut crate::Screen,
+        _notification: &crate::Notification,
+    ) {
+    }
     /// This callback is called when the terminal receives an escape sequence
     /// which is otherwise not implemented.
     fn unhandled_char(&mut self, _: &mut crate::Screen, _c: char) {}
@@ -1,7 +1,10 @@
 use unicode_width::UnicodeWidthChar as _;
 
-// chosen to make the size of the cell struct 32 bytes
-const CONTENT_BYTES: usize = 22;
+// chosen to make the size of the cell struct 32 bytes. this used to be 22,
+// but the underline color, the extra text attributes and the hyperlink id
+// all have to be answerable from the cell itself, so they take up the
+// difference.
+const CONTENT_BYTES: usize = 15;
 
 const IS_WIDE: u8 = 0b1000_0000;
 const IS_WIDE_CONTINUATION: u8 = 0b0100_0000;
@@ -54,7 +57,15 @@ impl Cell {
 
     pub(crate) fn append(&mut self, c: char) {
         let len = self.len();
-        if len >= CONTENT_BYTES - 4 {
+        // combining characters with nothing to combine with are attached to
+        // a space
+        if len.max(1) + c.len_utf8() > CONTENT_BYTES {
+            // graphemes which are too long are truncated, but a trailing
+            // zero width joiner would join the cell to whatever is drawn
+            // after it
+            if self.contents().ends_with('\u{200d}') {
+                self.len -= 3;
+            }
             return;
         }
         if len == 0 {
@@ -67,7 +78,7 @@ impl Cell {
     }
 
     // Writes bytes representing c at start
-    // Requires caller to verify start <= CODEPOINTS_IN_CELL * 4
+    // Requires caller to verify start + c.len_utf8() <= CONTENT_BYTES
     fn append_char(&mut self, start: usize, c: char) {
         c.encode_utf8(&mut self.contents[start..]);
         self.len += u8::try_from(c.len_utf8()).unwrap();
@@ -75,7 +86,35 @@ impl Cell {
 
     pub(crate) fn clear(&mut self, attrs: crate::attrs::Attrs) {
         self.len = 0;
-        self.attrs = attrs;
+        // cells without any text can't be clicked on
+        self.attrs = crate::attrs::Attrs {
+            hyperlink: None,
+            ..attrs
+        };
+    }
+
+    // hyperlink ids are only meaningful within a single screen, so cells
+    // from different screens have to compare their links by value
+    pub(crate) fn same(
+        &self,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
+        other: &Self,
+        other_hyperlinks: &crate::hyperlink::Hyperlinks,
+    ) -> bool {
+        self.len == other.len
+            && self.contents() == other.contents()
+            && crate::attrs::Attrs {
+                hyperlink: None,
+                ..self.attrs
+            } == crate::attrs::Attrs {
+                hyperlink: None,
+                ..other.attrs
+            }
+            && self.attrs.hyperlink.and_then(|id| hyperlinks.get(id))
+                == other
+                    .attrs
+                    .hyperlink
+                    .and_then(|id| other_hyperlinks.get(id))
     }
 
     /// Returns the text contents of the cell.
@@ -170,10 +209,62 @@ impl Cell {
         self.attrs.underline()
     }
 
+    /// Returns the style of line that the cell should be underlined with.
+    #[must_use]
+    pub fn underline_style(&self) -> crate::UnderlineStyle {
+        self.attrs.underline_style()
+    }
+
+    /// Returns the color that the cell's underline should be drawn with.
+    /// [`Color::Default`](crate::Color::Default) means that the underline
+    /// should be drawn using the foreground color.
+    #[must_use]
+    pub fn underline_color(&self) -> crate::Color {
+        self.attrs.underline_color
+    }
+
     /// Returns whether the cell should be rendered with the inverse text
     /// attribute.
     #[must_use]
     pub fn inverse(&self) -> bool {
         self.attrs.inverse()
     }
+
+    /// Returns whether the cell should be rendered with the blinking text
+    /// attribute.
+    #[must_use]
+    pub fn blink(&self) -> bool {
+        self.attrs.blink()
+    }
+
+    /// Returns whether the cell should be rendered with the concealed text
+    /// attribute.
+    #[must_use]
+    pub fn conceal(&self) -> bool {
+        self.attrs.conceal()
+    }
+
+    /// Returns whether the cell should be rendered with the strikethrough text
+    /// attribute.
+    #[must_use]
+    pub fn strikethrough(&self) -> bool {
+        self.attrs.strikethrough()
+    }
+
+    /// Returns whether the cell should be rendered with the overlined text
+    /// attribute.
+    #[must_use]
+    pub fn overline(&self) -> bool {
+        self.attrs.overline()
+    }
+
+    /// Returns the id of the hyperlink that the cell is part of, if any. The
+    /// hyperlink itself can be retrieved with
+    /// [`Screen::hyperlink`](crate::Screen::hyperlink). Ids are reused once
+    /// their link is no longer on the screen, so they should only be looked
+    /// up on the screen that the cell came from.
+    #[must_use]
+    pub fn hyperlink(&self) -> Option<u16> {
+        self.attrs.hyperlink.map(std::num::NonZeroU16::get)
+    }
 }
@@ -0,0 +1,159 @@
+use crate::term::BufWrite as _;
+
+/// A character set which can be designated into one of the G0-G3 slots.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum Charset {
+    /// US ASCII (`\e(B`).
+    #[default]
+    Ascii,
+
+    /// The UK national character set, which replaces `#` with `£`
+    /// (`\e(A`).
+    Uk,
+
+    /// The DEC special graphics character set, which replaces the
+    /// characters from `_` through `~` with line drawing characters and
+    /// other symbols (`\e(0`).
+    DecSpecialGraphics,
+}
+
+impl Charset {
+    pub(crate) fn from_final(b: u8) -> Option<Self> {
+        match b {
+            b'B' => Some(Self::Ascii),
+            b'A' => Some(Self::Uk),
+            b'0' => Some(Self::DecSpecialGraphics),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn final_byte(self) -> u8 {
+        match self {
+            Self::Ascii => b'B',
+            Self::Uk => b'A',
+            Self::DecSpecialGraphics => b'0',
+        }
+    }
+
+    fn map(self, c: char) -> char {
+        match self {
+            Self::Ascii => c,
+            Self::Uk => {
+                if c == '#' {
+                    '£'
+                } else {
+                    c
+                }
+            }
+            Self::DecSpecialGraphics => match c {
+                '_' => ' ',
+                '`' => '◆',
+                'a' => '▒',
+                'b' => '␉',
+                'c' => '␌',
+                'd' => '␍',
+                'e' => '␊',
+                'f' => '°',
+                'g' => '±',
+                'h' => '␤',
+                'i' => '␋',
+                'j' => '┘',
+                'k' => '┐',
+                'l' => '┌',
+                'm' => '└',
+                'n' => '┼',
+                'o' => '⎺',
+                'p' => '⎻',
+                'q' => '─',
+                'r' => '⎼',
+                's' => '⎽',
+                't' => '├',
+                'u' => '┤',
+                'v' => '┴',
+                'w' => '┬',
+                'x' => '│',
+                'y' => '≤',
+                'z' => '≥',
+                '{' => 'π',
+                '|' => '≠',
+                '}' => '£',
+                '~' => '·',
+                _ => c,
+            },
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Charsets {
+    designations: [Charset; 4],
+    gl: u8,
+    single_shift: Option<u8>,
+}
+
+impl Charsets {
+    pub fn designations(self) -> [Charset; 4] {
+        self.designations
+    }
+
+    pub fn gl(self) -> u8 {
+        self.gl
+    }
+
+    pub fn designate(&mut self, g: u8, charset: Charset) {
+        self.designations[usize::from(g)] = charset;
+    }
+
+    pub fn locking_shift(&mut self, g: u8) {
+        self.gl = g;
+    }
+
+    pub fn single_shift(&mut self, g: u8) {
+        self.single_shift = Some(g);
+    }
+
+    pub fn map(&mut self, c: char) -> char {
+        let g = self.single_shift.take().unwrap_or(self.gl);
+        self.designations[usize::from(g)].map(c)
+    }
+
+    // returns a copy of this state where text written to the terminal will
+    // be drawn unmodified, so that the contents of the grid (which have
+    // already been translated) don't get translated a second time. a pending
+    // single shift can't be cancelled, so we instead make sure that the
+    // character set it points to is also harmless.
+    pub fn for_drawing(self) -> Self {
+        let mut charsets = self;
+        charsets.designations[usize::from(charsets.gl)] = Charset::Ascii;
+        if let Some(g) = charsets.single_shift.take() {
+            charsets.designations[usize::from(g)] = Charset::Ascii;
+        }
+        charsets
+    }
+
+    pub fn write_escape_code_diff(self, contents: &mut Vec<u8>, other: Self) {
+        for (g, (charset, other_charset)) in self
+            .designations
+            .iter()
+            .zip(other.designations.iter())
+            .enumerate()
+        {
+            if charset != other_charset {
+                // designations always has exactly four entries
+                crate::term::DesignateCharset::new(
+                    g.try_into().unwrap(),
+                    *charset,
+                )
+                .write_buf(contents);
+            }
+        }
+        if self.gl != other.gl {
+            crate::term::LockingShift::new(self.gl).write_buf(contents);
+        }
+        if let Some(g) = self.single_shift {
+            if other.single_shift != self.single_shift {
+                crate::term::SingleShift::new(g).write_buf(contents);
+            }
+        }
+    }
+}
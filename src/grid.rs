@@ -8,8 +8,12 @@ pub struct Grid {
     rows: Vec<crate::row::Row>,
     scroll_top: u16,
     scroll_bottom: u16,
+    scroll_left: u16,
+    scroll_right: u16,
+    tabs: Vec<bool>,
     origin_mode: bool,
     saved_origin_mode: bool,
+    keyboard_flags: crate::keyboard::KeyboardFlags,
     scrollback: std::collections::VecDeque<crate::row::Row>,
     scrollback_len: usize,
     scrollback_offset: usize,
@@ -24,14 +28,40 @@ impl Grid {
             rows: vec![],
             scroll_top: 0,
             scroll_bottom: size.rows - 1,
+            scroll_left: 0,
+            scroll_right: size.cols - 1,
+            tabs: default_tabs(0, size.cols).collect(),
             origin_mode: false,
             saved_origin_mode: false,
+            keyboard_flags: crate::keyboard::KeyboardFlags::default(),
             scrollback: std::collections::VecDeque::new(),
             scrollback_len,
             scrollback_offset: 0,
         }
     }
 
+    // a copy of the grid as it is currently displayed, without any of the
+    // scrollback
+    pub fn snapshot(&self) -> Self {
+        Self {
+            size: self.size,
+            pos: self.pos,
+            saved_pos: self.saved_pos,
+            rows: self.visible_rows().cloned().collect(),
+            scroll_top: self.scroll_top,
+            scroll_bottom: self.scroll_bottom,
+            scroll_left: self.scroll_left,
+            scroll_right: self.scroll_right,
+            tabs: self.tabs.clone(),
+            origin_mode: self.origin_mode,
+            saved_origin_mode: self.saved_origin_mode,
+            keyboard_flags: self.keyboard_flags.clone(),
+            scrollback: std::collections::VecDeque::new(),
+            scrollback_len: 0,
+            scrollback_offset: 0,
+        }
+    }
+
     pub fn allocate_rows(&mut self) {
         if self.rows.is_empty() {
             self.rows.extend(
@@ -50,11 +80,16 @@ impl Grid {
     pub fn clear(&mut self) {
         self.pos = Pos::default();
         self.saved_pos = Pos::default();
+        // clearing a row keeps its prompt markers, but nothing on a reset
+        // screen should be kept
+        let cols = self.size.cols;
         for row in self.drawing_rows_mut() {
-            row.clear(crate::attrs::Attrs::default());
+            *row = crate::row::Row::new(cols);
         }
         self.scroll_top = 0;
         self.scroll_bottom = self.size.rows - 1;
+        self.scroll_left = 0;
+        self.scroll_right = self.size.cols - 1;
         self.origin_mode = false;
         self.saved_origin_mode = false;
     }
@@ -73,6 +108,18 @@ impl Grid {
         if self.scroll_bottom == self.size.rows - 1 {
             self.scroll_bottom = size.rows - 1;
         }
+        if self.scroll_right == self.size.cols - 1 {
+            self.scroll_right = size.cols - 1;
+        }
+
+        // keep any tab stops that were explicitly set, and use the default
+        // tab stops for any newly added columns
+        let old_cols = self.size.cols;
+        if size.cols > old_cols {
+            self.tabs.extend(default_tabs(old_cols, size.cols));
+        } else {
+            self.tabs.truncate(usize::from(size.cols));
+        }
 
         self.size = size;
         for row in &mut self.rows {
@@ -86,6 +133,13 @@ impl Grid {
         if self.scroll_bottom < self.scroll_top {
             self.scroll_top = 0;
         }
+        if self.scroll_right >= size.cols {
+            self.scroll_right = size.cols - 1;
+        }
+        if self.scroll_right <= self.scroll_left {
+            self.scroll_left = 0;
+            self.scroll_right = size.cols - 1;
+        }
 
         self.row_clamp_top(false);
         self.row_clamp_bottom(false);
@@ -106,13 +160,30 @@ impl Grid {
     pub fn set_pos(&mut self, mut pos: Pos) {
         if self.origin_mode {
             pos.row = pos.row.saturating_add(self.scroll_top);
+            pos.col = pos.col.saturating_add(self.scroll_left);
         }
         self.pos = pos;
         self.row_clamp_top(self.origin_mode);
         self.row_clamp_bottom(self.origin_mode);
+        if self.origin_mode && self.pos.col > self.scroll_right {
+            self.pos.col = self.scroll_right;
+        }
         self.col_clamp();
     }
 
+    // the cursor position as reported to the application, which is relative
+    // to the margins in origin mode. a cursor which is just past the end of
+    // a row is reported as being in the last column.
+    pub fn report_pos(&self) -> (u16, u16) {
+        let mut row = self.pos.row;
+        let mut col = self.pos.col.min(self.size.cols - 1);
+        if self.origin_mode {
+            row = row.saturating_sub(self.scroll_top);
+            col = col.saturating_sub(self.scroll_left);
+        }
+        (row, col)
+    }
+
     pub fn save_cursor(&mut self) {
         self.saved_pos = self.pos;
         self.saved_origin_mode = self.origin_mode;
@@ -150,6 +221,29 @@ impl Grid {
         self.scrollback.iter().chain(self.rows.iter())
     }
 
+    pub fn hyperlinks(
+        &self,
+    ) -> impl Iterator<Item = std::num::NonZeroU16> + '_ {
+        self.all_rows().flat_map(crate::row::Row::hyperlinks)
+    }
+
+    pub fn all_rows_mut(
+        &mut self,
+    ) -> impl Iterator<Item = &mut crate::row::Row> {
+        self.scrollback.iter_mut().chain(self.rows.iter_mut())
+    }
+
+    pub fn commands(&self) -> Vec<crate::Command> {
+        crate::prompt::commands(
+            self.all_rows(),
+            (self.scrollback.len() + self.rows.len(), 0),
+        )
+    }
+
+    pub fn images(&self) -> Vec<crate::ImagePlacement> {
+        crate::image::placements(self.visible_rows())
+    }
+
     pub fn drawing_rows(&self) -> impl Iterator<Item = &crate::row::Row> {
         self.rows.iter()
     }
@@ -244,6 +338,7 @@ impl Grid {
     pub fn write_contents_formatted_full(
         &self,
         contents: &mut Vec<u8>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs {
         crate::term::ClearAttrs.write_buf(contents);
 
@@ -260,6 +355,7 @@ impl Grid {
                 0,
                 self.size.cols,
                 prev_attrs,
+                hyperlinks,
             );
             wrapping = row.wrapped();
         }
@@ -270,8 +366,14 @@ impl Grid {
     pub fn write_contents_formatted(
         &self,
         contents: &mut Vec<u8>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs {
         crate::term::ClearAttrs.write_buf(contents);
+        if !self.has_default_tabs() {
+            // clearing the screen moves the cursor home anyway, so we don't
+            // need to worry about where this leaves the cursor
+            self.write_tabs_formatted(contents);
+        }
         crate::term::ClearScreen.write_buf(contents);
 
         let mut prev_attrs = crate::attrs::Attrs::default();
@@ -289,6 +391,7 @@ impl Grid {
                 wrapping,
                 Some(prev_pos),
                 Some(prev_attrs),
+                hyperlinks,
             );
             prev_pos = new_pos;
             prev_attrs = new_attrs;
@@ -299,6 +402,7 @@ impl Grid {
             contents,
             Some(prev_pos),
             Some(prev_attrs),
+            hyperlinks,
         );
 
         prev_attrs
@@ -309,8 +413,15 @@ impl Grid {
         contents: &mut Vec<u8>,
         prev: &Self,
         mut prev_attrs: crate::attrs::Attrs,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
+        prev_hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs {
         let mut prev_pos = prev.pos;
+        if self.tabs != prev.tabs {
+            if let Some(col) = self.write_tabs_formatted(contents) {
+                prev_pos.col = col;
+            }
+        }
         let mut wrapping = false;
         let mut prev_wrapping = false;
         for (i, (row, prev_row)) in
@@ -329,6 +440,8 @@ impl Grid {
                 prev_wrapping,
                 prev_pos,
                 prev_attrs,
+                hyperlinks,
+                prev_hyperlinks,
             );
             prev_pos = new_pos;
             prev_attrs = new_attrs;
@@ -340,16 +453,34 @@ impl Grid {
             contents,
             Some(prev_pos),
             Some(prev_attrs),
+            hyperlinks,
         );
 
         prev_attrs
     }
 
+    // moves the cursor to the last tab stop, returning its column (or None
+    // if there are no tab stops, and so the cursor was not moved)
+    pub fn write_tabs_formatted(
+        &self,
+        contents: &mut Vec<u8>,
+    ) -> Option<u16> {
+        crate::term::ClearTabStops.write_buf(contents);
+        let mut last = None;
+        for col in self.tab_stops() {
+            crate::term::MoveToCol::new(col).write_buf(contents);
+            crate::term::SetTabStop.write_buf(contents);
+            last = Some(col);
+        }
+        last
+    }
+
     pub fn write_cursor_position_formatted(
         &self,
         contents: &mut Vec<u8>,
         prev_pos: Option<Pos>,
         prev_attrs: Option<crate::attrs::Attrs>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) {
         let prev_attrs = prev_attrs.unwrap_or_default();
         // writing a character to the last column of a row doesn't wrap the
@@ -386,9 +517,17 @@ impl Grid {
                 } else {
                     crate::term::MoveTo::new(pos).write_buf(contents);
                 }
-                cell.attrs().write_escape_code_diff(contents, &prev_attrs);
+                cell.attrs().write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 contents.extend(cell.contents().as_bytes());
-                prev_attrs.write_escape_code_diff(contents, cell.attrs());
+                prev_attrs.write_escape_code_diff(
+                    contents,
+                    cell.attrs(),
+                    hyperlinks,
+                );
             } else {
                 // if the cell doesn't have contents, we can't have gotten
                 // here by drawing a character in the last column. this means
@@ -433,11 +572,13 @@ impl Grid {
                                 cell.attrs().write_escape_code_diff(
                                     contents,
                                     &prev_attrs,
+                                    hyperlinks,
                                 );
                                 contents.extend(cell.contents().as_bytes());
                                 prev_attrs.write_escape_code_diff(
                                     contents,
                                     cell.attrs(),
+                                    hyperlinks,
                                 );
                             }
                         } else {
@@ -445,11 +586,13 @@ impl Grid {
                             cell.attrs().write_escape_code_diff(
                                 contents,
                                 &prev_attrs,
+                                hyperlinks,
                             );
                             contents.extend(cell.contents().as_bytes());
                             prev_attrs.write_escape_code_diff(
                                 contents,
                                 cell.attrs(),
+                                hyperlinks,
                             );
                         }
                         contents.extend(
@@ -486,15 +629,20 @@ impl Grid {
                         // we assume self.pos.row is always valid, and
                         // self.size.cols - 1 is always a valid column
                         .unwrap();
-                    end_cell
-                        .attrs()
-                        .write_escape_code_diff(contents, &prev_attrs);
+                    end_cell.attrs().write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     crate::term::SaveCursor.write_buf(contents);
                     crate::term::Backspace.write_buf(contents);
                     crate::term::EraseChar::new(1).write_buf(contents);
                     crate::term::RestoreCursor.write_buf(contents);
-                    prev_attrs
-                        .write_escape_code_diff(contents, end_cell.attrs());
+                    prev_attrs.write_escape_code_diff(
+                        contents,
+                        end_cell.attrs(),
+                        hyperlinks,
+                    );
                 }
             }
         } else if let Some(prev_pos) = prev_pos {
@@ -552,6 +700,20 @@ impl Grid {
     }
 
     pub fn insert_cells(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            if self.in_horizontal_margins()
+                && self.pos.col <= self.scroll_right
+            {
+                let pos = self.pos;
+                let right = self.scroll_right;
+                let row = self.current_row_mut();
+                for _ in 0..count.min(right - pos.col + 1) {
+                    row.insert_within(pos.col, right, crate::Cell::new());
+                }
+            }
+            return;
+        }
+
         let size = self.size;
         let pos = self.pos;
         let wide = pos.col < size.cols
@@ -576,6 +738,20 @@ impl Grid {
     }
 
     pub fn delete_cells(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            if self.in_horizontal_margins()
+                && self.pos.col <= self.scroll_right
+            {
+                let pos = self.pos;
+                let right = self.scroll_right;
+                let row = self.current_row_mut();
+                for _ in 0..count.min(right - pos.col + 1) {
+                    row.remove_within(pos.col, right, crate::Cell::new());
+                }
+            }
+            return;
+        }
+
         let size = self.size;
         let pos = self.pos;
         let row = self.current_row_mut();
@@ -595,6 +771,20 @@ impl Grid {
     }
 
     pub fn insert_lines(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            if self.in_scroll_region()
+                && self.in_horizontal_margins()
+                && self.pos.col <= self.scroll_right
+            {
+                self.scroll_band_down(
+                    self.pos.row,
+                    self.scroll_bottom,
+                    count,
+                );
+            }
+            return;
+        }
+
         for _ in 0..count {
             self.rows.remove(usize::from(self.scroll_bottom));
             self.rows.insert(usize::from(self.pos.row), self.new_row());
@@ -604,6 +794,16 @@ impl Grid {
     }
 
     pub fn delete_lines(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            if self.in_scroll_region()
+                && self.in_horizontal_margins()
+                && self.pos.col <= self.scroll_right
+            {
+                self.scroll_band_up(self.pos.row, self.scroll_bottom, count);
+            }
+            return;
+        }
+
         for _ in 0..(count.min(self.size.rows - self.pos.row)) {
             self.rows
                 .insert(usize::from(self.scroll_bottom) + 1, self.new_row());
@@ -612,6 +812,11 @@ impl Grid {
     }
 
     pub fn scroll_up(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            self.scroll_band_up(self.scroll_top, self.scroll_bottom, count);
+            return;
+        }
+
         for _ in 0..(count.min(self.size.rows - self.scroll_top)) {
             self.rows
                 .insert(usize::from(self.scroll_bottom) + 1, self.new_row());
@@ -630,6 +835,11 @@ impl Grid {
     }
 
     pub fn scroll_down(&mut self, count: u16) {
+        if self.horizontal_margins_active() {
+            self.scroll_band_down(self.scroll_top, self.scroll_bottom, count);
+            return;
+        }
+
         for _ in 0..count {
             self.rows.remove(usize::from(self.scroll_bottom));
             self.rows
@@ -652,12 +862,106 @@ impl Grid {
         self.pos.col = 0;
     }
 
+    // moves the cells between the left and right margins in the rows from
+    // top to bottom (inclusive) up by count rows, leaving the cells outside
+    // of the margins alone
+    fn scroll_band_up(&mut self, top: u16, bottom: u16, count: u16) {
+        let left = self.scroll_left;
+        let right = self.scroll_right;
+        let count = count.min(bottom - top + 1);
+        if count == 0 {
+            return;
+        }
+        for row in top..=bottom {
+            if row + count <= bottom {
+                let (dst, src) =
+                    self.rows.split_at_mut(usize::from(row + count));
+                dst[usize::from(row)].copy_from(&src[0], left, right);
+            } else {
+                let row = &mut self.rows[usize::from(row)];
+                for col in left..=right {
+                    row.erase(col, crate::attrs::Attrs::default());
+                }
+                row.remove_markers(left, right);
+            }
+        }
+    }
+
+    // moves the cells between the left and right margins in the rows from
+    // top to bottom (inclusive) down by count rows, leaving the cells
+    // outside of the margins alone
+    fn scroll_band_down(&mut self, top: u16, bottom: u16, count: u16) {
+        let left = self.scroll_left;
+        let right = self.scroll_right;
+        let count = count.min(bottom - top + 1);
+        if count == 0 {
+            return;
+        }
+        for row in (top..=bottom).rev() {
+            if row >= top + count {
+                let (src, dst) = self.rows.split_at_mut(usize::from(row));
+                dst[0].copy_from(&src[usize::from(row - count)], left, right);
+            } else {
+                let row = &mut self.rows[usize::from(row)];
+                for col in left..=right {
+                    row.erase(col, crate::attrs::Attrs::default());
+                }
+                row.remove_markers(left, right);
+            }
+        }
+    }
+
+    pub fn set_horizontal_margins(&mut self, left: u16, right: u16) {
+        let right = right.min(self.size().cols - 1);
+        if left < right {
+            self.scroll_left = left;
+            self.scroll_right = right;
+        } else {
+            self.clear_horizontal_margins();
+        }
+        self.set_pos(Pos { row: 0, col: 0 });
+    }
+
+    pub fn clear_horizontal_margins(&mut self) {
+        self.scroll_left = 0;
+        self.scroll_right = self.size.cols - 1;
+    }
+
+    fn horizontal_margins_active(&self) -> bool {
+        self.scroll_left != 0 || self.scroll_right != self.size.cols - 1
+    }
+
+    // this includes the column just past the right margin, since that is
+    // where the cursor ends up after drawing a character at the right margin
+    // (the same way that the cursor can be just past the end of a row)
+    fn in_horizontal_margins(&self) -> bool {
+        !self.horizontal_margins_active()
+            || (self.pos.col >= self.scroll_left
+                && self.pos.col <= self.scroll_right + 1)
+    }
+
+    // the column that text wraps after: the right margin when the cursor is
+    // within the left and right margins, and the end of the row otherwise
+    pub fn wrap_col(&self) -> u16 {
+        if self.in_horizontal_margins() {
+            self.scroll_right
+        } else {
+            self.size.cols - 1
+        }
+    }
+
     fn in_scroll_region(&self) -> bool {
         self.pos.row >= self.scroll_top && self.pos.row <= self.scroll_bottom
     }
 
     fn scroll_region_active(&self) -> bool {
-        self.scroll_top != 0 || self.scroll_bottom != self.size.rows - 1
+        self.scroll_top != 0
+            || self.scroll_bottom != self.size.rows - 1
+            || self.horizontal_margins_active()
+    }
+
+    pub fn origin_mode(&self) -> bool {
+        self.origin_mode
     }
 
     pub fn set_origin_mode(&mut self, mode: bool) {
@@ -665,6 +969,16 @@ impl Grid {
         self.set_pos(Pos { row: 0, col: 0 });
     }
 
+    pub fn keyboard_flags(&self) -> &crate::keyboard::KeyboardFlags {
+        &self.keyboard_flags
+    }
+
+    pub fn keyboard_flags_mut(
+        &mut self,
+    ) -> &mut crate::keyboard::KeyboardFlags {
+        &mut self.keyboard_flags
+    }
+
     pub fn row_inc_clamp(&mut self, count: u16) {
         let in_scroll_region = self.in_scroll_region();
         self.pos.row = self.pos.row.saturating_add(count);
@@ -675,7 +989,7 @@ impl Grid {
         let in_scroll_region = self.in_scroll_region();
         self.pos.row = self.pos.row.saturating_add(count);
         let lines = self.row_clamp_bottom(in_scroll_region);
-        if in_scroll_region {
+        if in_scroll_region && self.in_horizontal_margins() {
             self.scroll_up(lines);
             lines
         } else {
@@ -696,7 +1010,9 @@ impl Grid {
         let extra_lines = count.saturating_sub(self.pos.row);
         self.pos.row = self.pos.row.saturating_sub(count);
         let lines = self.row_clamp_top(in_scroll_region);
-        self.scroll_down(lines + extra_lines);
+        if self.in_horizontal_margins() {
+            self.scroll_down(lines + extra_lines);
+        }
     }
 
     pub fn row_set(&mut self, i: u16) {
@@ -709,29 +1025,139 @@ impl Grid {
     }
 
     pub fn col_inc_clamp(&mut self, count: u16) {
+        let in_horizontal_margins = self.in_horizontal_margins();
         self.pos.col = self.pos.col.saturating_add(count);
+        if in_horizontal_margins && self.pos.col > self.scroll_right {
+            self.pos.col = self.scroll_right;
+        }
         self.col_clamp();
     }
 
     pub fn col_dec(&mut self, count: u16) {
-        self.pos.col = self.pos.col.saturating_sub(count);
+        let left = if self.pos.col >= self.scroll_left {
+            self.scroll_left
+        } else {
+            0
+        };
+        self.pos.col = self.pos.col.saturating_sub(count).max(left);
+    }
+
+    // moves to the left margin, or to the start of the row if the cursor is
+    // already to the left of the left margin
+    pub fn col_left_margin(&mut self) {
+        if self.origin_mode || self.pos.col >= self.scroll_left {
+            self.pos.col = self.scroll_left;
+        } else {
+            self.pos.col = 0;
+        }
     }
 
-    pub fn col_tab(&mut self) {
-        self.pos.col -= self.pos.col % 8;
-        self.pos.col += 8;
+    // moves backwards through the screen as a whole rather than stopping at
+    // the start of the row, for reverse wraparound mode
+    // (or through the area between the left and right margins, if the
+    // cursor is within them)
+    pub fn col_dec_wrap(&mut self, count: u16) {
+        let right = self.wrap_col();
+        let left = if right == self.scroll_right {
+            self.scroll_left
+        } else {
+            0
+        };
+        let cols = u32::from(right - left + 1);
+        let offset = (u32::from(self.pos.row) * cols
+            + u32::from(self.pos.col - left))
+        .saturating_sub(u32::from(count));
+        // the offset is at most rows * cols, so dividing by cols must fit
+        // back into a u16 (and the remainder is always less than cols)
+        self.pos.row = (offset / cols).try_into().unwrap();
+        self.pos.col = left + u16::try_from(offset % cols).unwrap();
+    }
+
+    pub fn col_tab(&mut self, count: u16) {
+        let right = if self.in_horizontal_margins() {
+            self.scroll_right
+        } else {
+            self.size.cols - 1
+        };
+        for _ in 0..count {
+            let next = self.tab_stops().find(|col| *col > self.pos.col);
+            match next {
+                Some(col) if col <= right => self.pos.col = col,
+                _ => {
+                    self.pos.col = right;
+                    break;
+                }
+            }
+        }
         self.col_clamp();
     }
 
+    pub fn col_back_tab(&mut self, count: u16) {
+        self.col_clamp();
+        let left = if self.pos.col >= self.scroll_left {
+            self.scroll_left
+        } else {
+            0
+        };
+        for _ in 0..count {
+            let prev = self.tab_stops().rev().find(|col| *col < self.pos.col);
+            match prev {
+                Some(col) if col >= left => self.pos.col = col,
+                _ => {
+                    self.pos.col = left;
+                    break;
+                }
+            }
+        }
+    }
+
+    pub fn tab_stops(&self) -> impl DoubleEndedIterator<Item = u16> + '_ {
+        self.tabs.iter().enumerate().filter(|(_, set)| **set).map(
+            // we limit the number of cols to a u16 (see Size)
+            |(col, _)| col.try_into().unwrap(),
+        )
+    }
+
+    pub fn has_default_tabs(&self) -> bool {
+        self.tabs
+            .iter()
+            .copied()
+            .eq(default_tabs(0, self.size.cols))
+    }
+
+    pub fn set_tab(&mut self) {
+        if let Some(tab) = self.tabs.get_mut(usize::from(self.pos.col)) {
+            *tab = true;
+        }
+    }
+
+    pub fn clear_tab(&mut self) {
+        if let Some(tab) = self.tabs.get_mut(usize::from(self.pos.col)) {
+            *tab = false;
+        }
+    }
+
+    pub fn clear_all_tabs(&mut self) {
+        self.tabs.fill(false);
+    }
+
     pub fn col_set(&mut self, i: u16) {
         self.pos.col = i;
         self.col_clamp();
     }
 
     pub fn col_wrap(&mut self, width: u16, wrap: bool) {
-        if self.pos.col > self.size.cols - width {
+        let wrap_col = self.wrap_col();
+        if self.pos.col + width > wrap_col + 1 {
+            // wrapping within the left and right margins doesn't join the
+            // rows together into a single line
+            let wrap = wrap && wrap_col == self.size.cols - 1;
             let mut prev_pos = self.pos;
-            self.pos.col = 0;
+            self.pos.col = if wrap_col == self.scroll_right {
+                self.scroll_left
+            } else {
+                0
+            };
             let scrolled = self.row_inc_scroll(1);
             prev_pos.row -= scrolled;
             let new_pos = self.pos;
@@ -744,6 +1170,15 @@ impl Grid {
         }
     }
 
+    // when autowrap is disabled, text drawn past the end of the row
+    // overwrites the end of the row instead
+    pub fn col_clamp_width(&mut self, width: u16) {
+        let wrap_col = self.wrap_col();
+        if self.pos.col + width > wrap_col + 1 {
+            self.pos.col = wrap_col + 1 - width;
+        }
+    }
+
     fn row_clamp_top(&mut self, limit_to_scroll_region: bool) -> u16 {
         if limit_to_scroll_region && self.pos.row < self.scroll_top {
             let rows = self.scroll_top - self.pos.row;
@@ -782,6 +1217,10 @@ impl Grid {
     }
 }
 
+fn default_tabs(from: u16, to: u16) -> impl Iterator<Item = bool> {
+    (from..to).map(|col| col > 0 && col % 8 == 0)
+}
+
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Size {
     pub rows: u16,
@@ -0,0 +1,127 @@
+// longer uris are ignored, the same as in vte
+pub const MAX_URI_LEN: usize = 2083;
+
+/// A hyperlink which has been attached to cells with OSC 8 (`\e]8;;<uri>`).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Hyperlink {
+    id: Option<String>,
+    uri: String,
+}
+
+impl Hyperlink {
+    // params are a colon separated list of key=value pairs. only the id key
+    // is defined by the spec, so we ignore anything else.
+    pub(crate) fn new(params: &str, uri: &str) -> Self {
+        let id = params
+            .split(':')
+            .find_map(|param| param.strip_prefix("id="))
+            .filter(|id| !id.is_empty())
+            .map(str::to_string);
+        Self {
+            id,
+            uri: uri.to_string(),
+        }
+    }
+
+    /// Returns the id given to this hyperlink by the application, if any.
+    /// Cells which share an id (and uri) are meant to be treated as a
+    /// single link even if they are not contiguous.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the uri that this hyperlink points to.
+    #[must_use]
+    pub fn uri(&self) -> &str {
+        &self.uri
+    }
+}
+
+// ids are reused once no cell refers to their link any more, so an id is
+// only meaningful within a single screen. comparing cells from different
+// screens (in contents_diff, for instance) has to compare the links
+// themselves.
+#[derive(Clone, Debug, Default)]
+pub struct Hyperlinks {
+    links: Vec<Option<Hyperlink>>,
+    ids: std::collections::HashMap<Hyperlink, std::num::NonZeroU16>,
+    free: Vec<std::num::NonZeroU16>,
+}
+
+impl Hyperlinks {
+    // returns None if the table is full (see reclaim)
+    pub fn intern(
+        &mut self,
+        link: Hyperlink,
+    ) -> Option<std::num::NonZeroU16> {
+        if let Some(id) = self.ids.get(&link) {
+            return Some(*id);
+        }
+        let id = if let Some(id) = self.free.pop() {
+            self.links[usize::from(id.get() - 1)] = Some(link.clone());
+            id
+        } else {
+            let id = std::num::NonZeroU16::new(
+                u16::try_from(self.links.len() + 1).ok()?,
+            )?;
+            self.links.push(Some(link.clone()));
+            id
+        };
+        self.ids.insert(link, id);
+        Some(id)
+    }
+
+    pub fn get(&self, id: std::num::NonZeroU16) -> Option<&Hyperlink> {
+        self.links.get(usize::from(id.get() - 1))?.as_ref()
+    }
+
+    pub fn find(&self, link: &Hyperlink) -> Option<std::num::NonZeroU16> {
+        self.ids.get(link).copied()
+    }
+
+    // a copy of the table with only the given links, which keep their ids
+    pub fn snapshot(
+        &self,
+        ids: impl IntoIterator<Item = std::num::NonZeroU16>,
+    ) -> Self {
+        let mut snapshot = Self::default();
+        for id in ids {
+            let i = usize::from(id.get() - 1);
+            let Some(link) = self.links.get(i).cloned().flatten() else {
+                continue;
+            };
+            if snapshot.links.len() <= i {
+                snapshot.links.resize(i + 1, None);
+            }
+            snapshot.ids.insert(link.clone(), id);
+            snapshot.links[i] = Some(link);
+        }
+        snapshot
+    }
+
+    // frees every id which isn't in use, so that it can be given to a new
+    // link
+    pub fn reclaim(
+        &mut self,
+        in_use: impl IntoIterator<Item = std::num::NonZeroU16>,
+    ) {
+        let mut used = vec![false; self.links.len()];
+        for id in in_use {
+            used[usize::from(id.get() - 1)] = true;
+        }
+        for (i, link) in self.links.iter_mut().enumerate() {
+            if used[i] {
+                continue;
+            }
+            if let Some(link) = link.take() {
+                self.ids.remove(&link);
+                // the table never has more than u16::MAX entries
+                self.free.push(
+                    std::num::NonZeroU16::new(u16::try_from(i + 1).unwrap())
+                        .unwrap(),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+/// Describes how the terminal identifies itself in response to device
+/// attribute requests from the application.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TerminalIdentity {
+    /// The parameters reported in response to a primary device attributes
+    /// request (`\e[c`). The first value is the conformance level, and the
+    /// remaining values are the supported extensions. The default of
+    /// `[62, 22]` describes a VT220-level terminal with ANSI color.
+    pub primary_attributes: Vec<u16>,
+
+    /// The terminal type reported in response to a secondary device
+    /// attributes request (`\e[>c`).
+    pub terminal_type: u16,
+
+    /// The firmware version reported in response to a secondary device
+    /// attributes request (`\e[>c`).
+    pub firmware_version: u16,
+
+    /// The unit id reported in response to a tertiary device attributes
+    /// request (`\e[=c`). This is reported as eight hexadecimal digits.
+    pub unit_id: u32,
+}
+
+impl Default for TerminalIdentity {
+    fn default() -> Self {
+        Self {
+            primary_attributes: vec![62, 22],
+            terminal_type: 1,
+            firmware_version: 0,
+            unit_id: 0,
+        }
+    }
+}
+
+impl TerminalIdentity {
+    pub(crate) fn primary_attributes_report(&self) -> Vec<u8> {
+        let params: Vec<_> = self
+            .primary_attributes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        format!("\x1b[?{}c", params.join(";")).into_bytes()
+    }
+
+    pub(crate) fn secondary_attributes_report(&self) -> Vec<u8> {
+        format!("\x1b[>{};{};0c", self.terminal_type, self.firmware_version)
+            .into_bytes()
+    }
+
+    pub(crate) fn tertiary_attributes_report(&self) -> Vec<u8> {
+        format!("\x1bP!|{:08X}\x1b\\", self.unit_id).into_bytes()
+    }
+}
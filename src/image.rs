@@ -0,0 +1,374 @@
+/// An image, as sent by the application with sixel graphics, the kitty
+/// graphics protocol, or iTerm2's inline image protocol.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+    file: Option<Vec<u8>>,
+}
+
+impl Image {
+    pub(crate) fn new(width: u32, height: u32, rgba: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            rgba,
+            file: None,
+        }
+    }
+
+    // an image file which hasn't been decoded
+    pub(crate) fn encoded(width: u32, height: u32, file: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            rgba: vec![],
+            file: Some(file),
+        }
+    }
+
+    /// Returns the width of the image in pixels.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the image in pixels.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the pixel data of the image, as four bytes (red, green, blue
+    /// and alpha) per pixel, a row at a time starting from the top left.
+    /// This is empty for images which were sent as an image file (see
+    /// [`file`](Self::file)).
+    #[must_use]
+    pub fn rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// Returns the contents of the image file, for images sent with
+    /// iTerm2's inline image protocol (`\e]1337;File=...`). These are not
+    /// decoded, since they can be in any format (usually PNG, GIF or JPEG).
+    #[must_use]
+    pub fn file(&self) -> Option<&[u8]> {
+        self.file.as_deref()
+    }
+}
+
+/// The part of an image which is drawn on a rectangle of cells of the
+/// visible screen, as returned by [`Screen::images`](crate::Screen::images).
+///
+/// Images are anchored to the cells they were drawn on, so they scroll
+/// along with the text. Cells of a sixel or iTerm2 image which are
+/// overwritten by text or erased are removed from the image, so an image may
+/// be split into several placements.
+#[derive(Clone, Debug)]
+pub struct ImagePlacement {
+    placement: std::sync::Arc<Placement>,
+    pos: (u16, u16),
+    size: (u16, u16),
+    offset: (u16, u16),
+}
+
+impl ImagePlacement {
+    /// Returns the image being drawn.
+    #[must_use]
+    pub fn image(&self) -> &Image {
+        &self.placement.image
+    }
+
+    /// Returns the (row, col) of the top left cell that the image is drawn
+    /// on.
+    #[must_use]
+    pub fn position(&self) -> (u16, u16) {
+        self.pos
+    }
+
+    /// Returns the size of the area that the image is drawn on, as (rows,
+    /// cols).
+    #[must_use]
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Returns the part of the image which is drawn on these cells, as (x,
+    /// y, width, height) in pixels of the image.
+    #[must_use]
+    pub fn source_rect(&self) -> (u32, u32, u32, u32) {
+        self.rects().0
+    }
+
+    /// Returns where on these cells the part of the image given by
+    /// [`source_rect`](Self::source_rect) is drawn, as (x, y, width,
+    /// height) in pixels relative to the top left corner of the cell at
+    /// [`position`](Self::position). If the sizes of the two rectangles are
+    /// different, the image is meant to be scaled.
+    ///
+    /// This is based on the cell size that was set when the image was drawn
+    /// (see [`Screen::set_cell_size`](crate::Screen::set_cell_size)).
+    #[must_use]
+    pub fn dest_rect(&self) -> (u32, u32, u32, u32) {
+        self.rects().1
+    }
+
+    /// Returns the z-index of the image. Images with a negative z-index are
+    /// meant to be drawn below the text. This is always 0 for sixel images.
+    #[must_use]
+    pub fn z_index(&self) -> i32 {
+        self.placement.z_index
+    }
+
+    /// Returns the image id and placement id given to the image by the
+    /// application, for images drawn with the kitty graphics protocol.
+    #[must_use]
+    pub fn kitty_id(&self) -> Option<(u32, u32)> {
+        self.placement.kitty_id
+    }
+
+    fn rects(&self) -> ((u32, u32, u32, u32), (u32, u32, u32, u32)) {
+        let placement = &self.placement;
+        let (cell_width, cell_height) = (
+            u32::from(placement.cell_size.0),
+            u32::from(placement.cell_size.1),
+        );
+        let cells = (
+            u32::from(self.offset.1) * cell_width,
+            u32::from(self.offset.0) * cell_height,
+            u32::from(self.size.1) * cell_width,
+            u32::from(self.size.0) * cell_height,
+        );
+        let (source_x, dest_x) = visible_span(
+            (placement.source.0, placement.source.2),
+            (placement.dest.0, placement.dest.2),
+            (cells.0, cells.2),
+        );
+        let (source_y, dest_y) = visible_span(
+            (placement.source.1, placement.source.3),
+            (placement.dest.1, placement.dest.3),
+            (cells.1, cells.3),
+        );
+        (
+            (source_x.0, source_y.0, source_x.1, source_y.1),
+            (dest_x.0 - cells.0, dest_y.0 - cells.1, dest_x.1, dest_y.1),
+        )
+    }
+}
+
+// maps the part of the destination span which is within the visible span
+// back to the source span, returning the visible parts of the source and
+// destination spans. all spans are (start, length).
+fn visible_span(
+    source: (u32, u32),
+    dest: (u32, u32),
+    visible: (u32, u32),
+) -> ((u32, u32), (u32, u32)) {
+    let start = dest.0.max(visible.0);
+    let end = (dest.0 + dest.1).min(visible.0 + visible.1).max(start);
+    let scale = |pixels: u32| {
+        let scaled = u64::from(pixels) * u64::from(source.1)
+            / u64::from(dest.1.max(1));
+        // scaled is at most source.1
+        u32::try_from(scaled).unwrap()
+    };
+    let source_start = scale(start - dest.0);
+    let source_end = scale(end - dest.0);
+    (
+        (source.0 + source_start, source_end - source_start),
+        (start, end - start),
+    )
+}
+
+// how an image is drawn onto the screen. this is shared between all of the
+// fragments that the image is split into.
+#[derive(Debug)]
+pub struct Placement {
+    image: std::sync::Arc<Image>,
+    cell_size: (u16, u16),
+    // the part of the image to draw, as (x, y, width, height) in pixels of
+    // the image
+    source: (u32, u32, u32, u32),
+    // where to draw it, as (x, y, width, height) in pixels relative to the
+    // top left corner of the first cell of the placement
+    dest: (u32, u32, u32, u32),
+    z_index: i32,
+    kitty_id: Option<(u32, u32)>,
+}
+
+impl Placement {
+    // draws the whole image at its natural size
+    pub fn new(image: std::sync::Arc<Image>, cell_size: (u16, u16)) -> Self {
+        let size = (image.width(), image.height());
+        Self::scaled(image, cell_size, size)
+    }
+
+    // draws the whole image scaled to the given (width, height) in pixels
+    pub fn scaled(
+        image: std::sync::Arc<Image>,
+        cell_size: (u16, u16),
+        size: (u32, u32),
+    ) -> Self {
+        let (width, height) = (image.width(), image.height());
+        Self {
+            image,
+            cell_size,
+            source: (0, 0, width, height),
+            dest: (0, 0, size.0, size.1),
+            z_index: 0,
+            kitty_id: None,
+        }
+    }
+
+    pub fn kitty(
+        image: std::sync::Arc<Image>,
+        cell_size: (u16, u16),
+        source: (u32, u32, u32, u32),
+        dest: (u32, u32, u32, u32),
+        z_index: i32,
+        kitty_id: (u32, u32),
+    ) -> Self {
+        Self {
+            image,
+            cell_size,
+            source,
+            dest,
+            z_index,
+            kitty_id: Some(kitty_id),
+        }
+    }
+
+    pub fn kitty_id(&self) -> Option<(u32, u32)> {
+        self.kitty_id
+    }
+
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    // the number of (rows, cols) that the placement covers
+    pub fn cells(&self) -> (u16, u16) {
+        let cells = |pixels: u32, cell_pixels: u16| -> u16 {
+            let cell_pixels = u32::from(cell_pixels);
+            ((pixels + cell_pixels - 1) / cell_pixels)
+                .try_into()
+                // images are limited to a few thousand pixels in each
+                // direction
+                .unwrap_or(u16::MAX)
+        };
+        (
+            cells(self.dest.1 + self.dest.3, self.cell_size.1),
+            cells(self.dest.0 + self.dest.2, self.cell_size.0),
+        )
+    }
+
+    // sixel and iterm2 images are replaced by whatever is drawn over them,
+    // but images from the kitty graphics protocol are drawn on a separate
+    // layer from the text
+    fn overwritable(&self) -> bool {
+        self.kitty_id.is_none()
+    }
+}
+
+// the part of an image which covers a horizontal run of cells in a single
+// row. these are stored on the rows themselves, so that images scroll along
+// with the text.
+#[derive(Clone, Debug)]
+pub struct Fragment {
+    placement: std::sync::Arc<Placement>,
+    col: u16,
+    cols: u16,
+    // the (row, col) of the cell within the placement which is drawn at col
+    offset: (u16, u16),
+}
+
+impl Fragment {
+    pub fn new(
+        placement: std::sync::Arc<Placement>,
+        col: u16,
+        cols: u16,
+        placement_row: u16,
+    ) -> Self {
+        Self {
+            placement,
+            col,
+            cols,
+            offset: (placement_row, 0),
+        }
+    }
+
+    pub fn placement(&self) -> &std::sync::Arc<Placement> {
+        &self.placement
+    }
+
+    pub fn col(&self) -> u16 {
+        self.col
+    }
+
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+
+    pub fn overwritable(&self) -> bool {
+        self.placement.overwritable()
+    }
+
+    pub fn covers(&self, col: u16) -> bool {
+        (self.col..self.end()).contains(&col)
+    }
+
+    fn end(&self) -> u16 {
+        self.col + self.cols
+    }
+
+    // returns the part of this fragment between the columns start
+    // (inclusive) and end (exclusive), if any
+    pub fn intersect(&self, start: u16, end: u16) -> Option<Self> {
+        let start = start.max(self.col);
+        let end = end.min(self.end());
+        (start < end).then(|| Self {
+            placement: self.placement.clone(),
+            col: start,
+            cols: end - start,
+            offset: (self.offset.0, self.offset.1 + (start - self.col)),
+        })
+    }
+}
+
+// merges the fragments on each row with the matching fragments on the row
+// above, so that an image which hasn't been clipped is returned as a single
+// placement
+pub fn placements<'a>(
+    rows: impl Iterator<Item = &'a crate::row::Row>,
+) -> Vec<ImagePlacement> {
+    let mut placements: Vec<ImagePlacement> = vec![];
+    for (row, fragments) in rows.map(crate::row::Row::images).enumerate() {
+        // we limit the number of rows to a u16 (see Size)
+        let row: u16 = row.try_into().unwrap();
+        for fragment in fragments {
+            let above = placements.iter_mut().find(|placement| {
+                std::sync::Arc::ptr_eq(
+                    &placement.placement,
+                    &fragment.placement,
+                ) && placement.pos.0 + placement.size.0 == row
+                    && placement.pos.1 == fragment.col
+                    && placement.size.1 == fragment.cols
+                    && placement.offset.0 + placement.size.0
+                        == fragment.offset.0
+                    && placement.offset.1 == fragment.offset.1
+            });
+            if let Some(placement) = above {
+                placement.size.0 += 1;
+            } else {
+                placements.push(ImagePlacement {
+                    placement: fragment.placement.clone(),
+                    pos: (row, fragment.col),
+                    size: (1, fragment.cols),
+                    offset: fragment.offset,
+                });
+            }
+        }
+    }
+    placements
+}
@@ -0,0 +1,357 @@
+// files larger than this are ignored, so that a malicious or broken
+// application can't make us allocate arbitrary amounts of memory
+const MAX_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+// commands longer than this are dropped. this leaves room for the arguments
+// along with the base64 encoded contents of the largest file we accept.
+const MAX_COMMAND_LEN: usize = MAX_FILE_SIZE / 3 * 4 + 4096;
+
+const PREFIX: &[u8] = b"1337;";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    // how much of the prefix has been seen
+    Prefix(usize),
+    Command,
+}
+
+// vte buffers the whole of an operating system command before dispatching
+// it, with no limit on its size, and inline images are sent as a single
+// (potentially very large) OSC 1337. this watches for them in the same way
+// as apc::Scanner, and takes their contents out of the byte stream, so that
+// vte only ever sees `\e]1337;` followed by the terminator.
+#[derive(Clone, Debug)]
+pub struct Scanner {
+    state: State,
+    data: Vec<u8>,
+    // whether the end of the file arguments has been seen
+    args_checked: bool,
+    overflowed: bool,
+}
+
+impl Scanner {
+    pub fn new() -> Self {
+        Self {
+            state: State::Ground,
+            data: vec![],
+            args_checked: false,
+            overflowed: false,
+        }
+    }
+
+    // returns the number of bytes which should be passed through to vte,
+    // followed by the number of bytes after that which are part of a command
+    // and should be skipped. if the command ended, its contents are returned
+    // too, and the terminator is the first byte after the skipped bytes.
+    pub fn scan(&mut self, bytes: &[u8]) -> (usize, usize, Option<Vec<u8>>) {
+        let mut i = 0;
+        while self.state != State::Command {
+            let Some(&b) = bytes.get(i) else {
+                return (bytes.len(), 0, None);
+            };
+            match self.state {
+                State::Ground => {
+                    match bytes[i..].iter().position(|&b| b == 0x1b) {
+                        Some(offset) => {
+                            i += offset;
+                            self.state = State::Escape;
+                        }
+                        None => return (bytes.len(), 0, None),
+                    }
+                }
+                State::Escape => match b {
+                    b']' => self.state = State::Prefix(0),
+                    // control characters other than cancel and substitute
+                    // are executed without leaving the escape sequence
+                    0x1b | 0x00..=0x17 | 0x19 | 0x1c..=0x1f => {}
+                    _ => self.state = State::Ground,
+                },
+                State::Prefix(len) => {
+                    if b == PREFIX[len] {
+                        self.state = if len + 1 == PREFIX.len() {
+                            self.data.clear();
+                            self.args_checked = false;
+                            self.overflowed = false;
+                            State::Command
+                        } else {
+                            State::Prefix(len + 1)
+                        };
+                    } else {
+                        // this byte could start another escape sequence
+                        self.state = State::Ground;
+                        continue;
+                    }
+                }
+                State::Command => unreachable!(),
+            }
+            i += 1;
+        }
+
+        let rest = &bytes[i..];
+        let Some(end) = rest
+            .iter()
+            .position(|b| matches!(b, 0x07 | 0x18 | 0x1a | 0x1b))
+        else {
+            self.push(rest);
+            return (i, rest.len(), None);
+        };
+        self.push(&rest[..end]);
+        self.state = State::Ground;
+        let data = std::mem::take(&mut self.data);
+        // cancel and substitute abort the command
+        let complete = matches!(rest[end], 0x07 | 0x1b) && !self.overflowed;
+        (i, end, complete.then_some(data))
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        let start = self.data.len();
+        // vte ignores other control characters in operating system
+        // commands
+        self.data.extend(bytes.iter().filter(|&&b| b >= 0x20));
+        if self.data.len() > MAX_COMMAND_LEN {
+            self.overflow();
+            return;
+        }
+
+        // files which are declared to be too large can be dropped as soon
+        // as their arguments have arrived
+        if !self.args_checked {
+            if let Some(end) =
+                self.data[start..].iter().position(|&c| c == b':')
+            {
+                self.args_checked = true;
+                if self.data[..start + end]
+                    .strip_prefix(b"File=")
+                    .and_then(declared_size)
+                    .is_some_and(|size| size > MAX_FILE_SIZE)
+                {
+                    self.overflow();
+                }
+            }
+        }
+    }
+
+    fn overflow(&mut self) {
+        self.overflowed = true;
+        self.data = vec![];
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Dimension {
+    Auto,
+    Cells(u32),
+    Pixels(u32),
+    Percent(u32),
+}
+
+impl Dimension {
+    fn parse(value: &[u8]) -> Option<Self> {
+        let number = |digits: &[u8]| -> Option<u32> {
+            std::str::from_utf8(digits).ok()?.parse().ok()
+        };
+        match value {
+            b"auto" => Some(Self::Auto),
+            [digits @ .., b'p', b'x'] => number(digits).map(Self::Pixels),
+            [digits @ .., b'%'] => number(digits).map(Self::Percent),
+            _ => number(value).map(Self::Cells),
+        }
+    }
+
+    // returns the size in pixels, or None for auto
+    fn pixels(self, cell: u16, screen: u32) -> Option<u32> {
+        match self {
+            Self::Auto => None,
+            Self::Cells(cells) => Some(cells.saturating_mul(u32::from(cell))),
+            Self::Pixels(pixels) => Some(pixels),
+            Self::Percent(percent) => Some(scale(screen, 100, percent)),
+        }
+    }
+}
+
+// the arguments and contents of an iterm2 inline image sequence, which looks
+// like `\e]1337;File=key=value;key=value:<base64 contents>\a`
+#[derive(Clone, Debug)]
+pub struct File {
+    width: Dimension,
+    height: Dimension,
+    preserve_aspect_ratio: bool,
+    inline: bool,
+    contents: Vec<u8>,
+}
+
+impl File {
+    pub fn parse(command: &[u8]) -> Option<Self> {
+        let command = command.strip_prefix(b"File=")?;
+        let separator = command.iter().position(|&c| c == b':')?;
+        let (args, contents) =
+            (&command[..separator], &command[separator + 1..]);
+
+        let mut file = Self {
+            width: Dimension::Auto,
+            height: Dimension::Auto,
+            preserve_aspect_ratio: true,
+            inline: false,
+            contents: vec![],
+        };
+        for arg in args.split(|&c| c == b';') {
+            let mut parts = arg.splitn(2, |&c| c == b'=');
+            let key = parts.next().unwrap_or_default();
+            let value = parts.next().unwrap_or_default();
+            match key {
+                b"width" => file.width = Dimension::parse(value)?,
+                b"height" => file.height = Dimension::parse(value)?,
+                b"preserveAspectRatio" => {
+                    file.preserve_aspect_ratio = value != b"0";
+                }
+                b"inline" => file.inline = value == b"1",
+                b"size" if parse_size(value)? > MAX_FILE_SIZE => {
+                    return None;
+                }
+                // the file name is only used for downloads, which we don't
+                // support, and other keys are ignored
+                _ => {}
+            }
+        }
+
+        // checked before decoding, to avoid allocating the decoded data
+        if contents.len() / 4 * 3 > MAX_FILE_SIZE {
+            return None;
+        }
+        file.contents = crate::base64::decode(contents)?;
+        Some(file)
+    }
+
+    // files which aren't inline are meant to be downloaded rather than
+    // displayed
+    pub fn inline(&self) -> bool {
+        self.inline
+    }
+
+    // returns the image along with the size to draw it at in pixels, or
+    // None if the file isn't in a format that we know how to find the
+    // dimensions of. screen is the size of the screen in pixels, which
+    // images are scaled down to fit into.
+    pub fn into_image(
+        self,
+        cell_size: (u16, u16),
+        screen: (u32, u32),
+    ) -> Option<(crate::Image, (u32, u32))> {
+        let (width, height) = image_size(&self.contents)?;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let dest_width = self.width.pixels(cell_size.0, screen.0);
+        let dest_height = self.height.pixels(cell_size.1, screen.1);
+        let size = match (dest_width, dest_height) {
+            (None, None) => (width, height),
+            (Some(dest_width), None) => {
+                (dest_width, scale(height, width, dest_width))
+            }
+            (None, Some(dest_height)) => {
+                (scale(width, height, dest_height), dest_height)
+            }
+            (Some(dest_width), Some(dest_height)) => {
+                if self.preserve_aspect_ratio {
+                    fit((width, height), (dest_width, dest_height))
+                } else {
+                    (dest_width, dest_height)
+                }
+            }
+        };
+        // images which are too large for the screen are scaled down
+        let size = if size.0 > screen.0 || size.1 > screen.1 {
+            fit(size, screen)
+        } else {
+            size
+        };
+
+        Some((
+            crate::Image::encoded(width, height, self.contents),
+            (size.0.max(1), size.1.max(1)),
+        ))
+    }
+}
+
+fn scale(size: u32, from: u32, to: u32) -> u32 {
+    let scaled = u64::from(size) * u64::from(to) / u64::from(from.max(1));
+    u32::try_from(scaled).unwrap_or(u32::MAX)
+}
+
+// scales size up or down to fit within bounds, keeping the aspect ratio
+fn fit(size: (u32, u32), bounds: (u32, u32)) -> (u32, u32) {
+    if u64::from(size.0) * u64::from(bounds.1)
+        > u64::from(size.1) * u64::from(bounds.0)
+    {
+        (bounds.0, scale(size.1, size.0, bounds.0))
+    } else {
+        (scale(size.0, size.1, bounds.1), bounds.1)
+    }
+}
+
+// we don't decode image files, but we need to know how large they are in
+// order to know how many cells they cover. this reads the dimensions from
+// the headers of png, gif and jpeg files.
+fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |i: usize| -> Option<u32> {
+        let bytes = data.get(i..i + 2)?.try_into().ok()?;
+        Some(u32::from(u16::from_be_bytes(bytes)))
+    };
+    let be32 = |i: usize| -> Option<u32> {
+        Some(u32::from_be_bytes(data.get(i..i + 4)?.try_into().ok()?))
+    };
+    let le16 = |i: usize| -> Option<u32> {
+        let bytes = data.get(i..i + 2)?.try_into().ok()?;
+        Some(u32::from(u16::from_le_bytes(bytes)))
+    };
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // the first chunk is always the header
+        if data.get(12..16) != Some(b"IHDR") {
+            return None;
+        }
+        return Some((be32(16)?, be32(20)?));
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some((le16(6)?, le16(8)?));
+    }
+    if data.starts_with(b"\xff\xd8") {
+        let mut i = 2;
+        loop {
+            if *data.get(i)? != 0xff {
+                return None;
+            }
+            let marker = *data.get(i + 1)?;
+            match marker {
+                // padding
+                0xff => i += 1,
+                // markers without a length
+                0x01 | 0xd0..=0xd7 => i += 2,
+                // start of frame markers (c4, c8 and cc are other kinds of
+                // segments which share the same range)
+                0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                    return Some((be16(i + 7)?, be16(i + 5)?));
+                }
+                _ => i += 2 + usize::try_from(be16(i + 2)?).ok()?,
+            }
+        }
+    }
+    None
+}
+
+fn parse_size(value: &[u8]) -> Option<usize> {
+    std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+// the size given in the arguments of a file, if there is a valid one
+fn declared_size(args: &[u8]) -> Option<usize> {
+    args.split(|&c| c == b';')
+        .find_map(|arg| arg.strip_prefix(b"size="))
+        .and_then(parse_size)
+}
@@ -0,0 +1,601 @@
+/// A key on the keyboard, as used in a [`KeyEvent`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Key {
+    /// A key which types a character. This should be the character that the
+    /// key types without any modifiers (`a` rather than `A` for shift+a),
+    /// and the text typed with the modifiers should be given with
+    /// [`KeyEvent::with_text`].
+    Char(char),
+
+    /// The enter (or return) key.
+    Enter,
+
+    /// The tab key.
+    Tab,
+
+    /// The backspace key.
+    Backspace,
+
+    /// The escape key.
+    Escape,
+
+    /// The up arrow key.
+    Up,
+
+    /// The down arrow key.
+    Down,
+
+    /// The right arrow key.
+    Right,
+
+    /// The left arrow key.
+    Left,
+
+    /// The home key.
+    Home,
+
+    /// The end key.
+    End,
+
+    /// The page up key.
+    PageUp,
+
+    /// The page down key.
+    PageDown,
+
+    /// The insert key.
+    Insert,
+
+    /// The delete key.
+    Delete,
+
+    /// A function key, from 1 to 24.
+    F(u8),
+
+    /// A key on the numeric keypad, other than enter. This is one of the
+    /// digits `0` to `9`, or one of `.`, `+`, `-`, `*`, `/` and `=`.
+    Keypad(char),
+
+    /// The enter key on the numeric keypad.
+    KeypadEnter,
+}
+
+/// The modifier keys which are held down during a [`KeyEvent`].
+///
+/// Modifiers can be combined with `|`, for instance
+/// `vt100::Modifiers::CTRL | vt100::Modifiers::SHIFT`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    /// No modifiers.
+    pub const NONE: Self = Self(0);
+
+    /// The shift key.
+    pub const SHIFT: Self = Self(0b0000_0001);
+
+    /// The alt (or option) key.
+    pub const ALT: Self = Self(0b0000_0010);
+
+    /// The control key.
+    pub const CTRL: Self = Self(0b0000_0100);
+
+    /// The meta key. Like xterm, this is sent as the same modifier bit that
+    /// other terminals use for the super key.
+    pub const META: Self = Self(0b0000_1000);
+
+    /// Returns whether all of the modifiers in `other` are also in this
+    /// set.
+    #[must_use]
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether no modifiers are set.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // the modifier parameter used in xterm's escape sequences (and the
+    // kitty keyboard protocol, which uses the same bits)
+    fn param(self) -> u8 {
+        self.0 + 1
+    }
+
+    fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Whether a [`KeyEvent`] is for a key being pressed, repeated, or
+/// released.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum KeyEventKind {
+    /// The key was pressed.
+    #[default]
+    Press,
+
+    /// The key is being held down, and is repeating.
+    Repeat,
+
+    /// The key was released. Releases are only sent to the application if
+    /// it asked for them with the kitty keyboard protocol.
+    Release,
+}
+
+impl KeyEventKind {
+    fn param(self) -> u8 {
+        match self {
+            Self::Press => 1,
+            Self::Repeat => 2,
+            Self::Release => 3,
+        }
+    }
+}
+
+/// A key press, which can be turned into the bytes to send to the
+/// application with [`Screen::encode_key`](crate::Screen::encode_key).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyEvent {
+    key: Key,
+    modifiers: Modifiers,
+    kind: KeyEventKind,
+    text: Option<String>,
+}
+
+impl KeyEvent {
+    /// Creates a new key event, for a key press.
+    #[must_use]
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            modifiers,
+            kind: KeyEventKind::Press,
+            text: None,
+        }
+    }
+
+    /// Sets whether the key was pressed, repeated or released.
+    #[must_use]
+    pub fn with_kind(mut self, kind: KeyEventKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Sets the text typed by the key press, taking the modifiers into
+    /// account (so `A` for shift+a). By default, keys typing a character
+    /// type the character given by [`Key::Char`].
+    #[must_use]
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    /// Returns the key which was pressed.
+    #[must_use]
+    pub fn key(&self) -> Key {
+        self.key
+    }
+
+    /// Returns the modifier keys which were held down.
+    #[must_use]
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns whether the key was pressed, repeated or released.
+    #[must_use]
+    pub fn kind(&self) -> KeyEventKind {
+        self.kind
+    }
+
+    /// Returns the text typed by the key press, if any.
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    pub(crate) fn encode(&self, screen: &crate::Screen) -> Vec<u8> {
+        let flags = screen.keyboard_flags();
+        if flags != 0 {
+            return self.encode_kitty(screen, flags);
+        }
+        if self.kind == KeyEventKind::Release {
+            return vec![];
+        }
+        self.encode_legacy(screen)
+    }
+
+    // the encoding used by the kitty keyboard protocol, which depends on
+    // which of its enhancements the application asked for
+    fn encode_kitty(&self, screen: &crate::Screen, flags: u8) -> Vec<u8> {
+        let modifiers = self.modifiers;
+        let all_keys = flags & crate::keyboard::REPORT_ALL_KEYS != 0;
+        if self.kind == KeyEventKind::Release
+            && flags & crate::keyboard::REPORT_EVENT_TYPES == 0
+        {
+            return vec![];
+        }
+
+        let (code, c) = match self.key {
+            Key::Char(c) => (u32::from(c), b'u'),
+            Key::Enter => (13, b'u'),
+            Key::Tab => (9, b'u'),
+            Key::Backspace => (127, b'u'),
+            Key::Escape => (27, b'u'),
+            Key::Up => (1, b'A'),
+            Key::Down => (1, b'B'),
+            Key::Right => (1, b'C'),
+            Key::Left => (1, b'D'),
+            Key::Home => (1, b'H'),
+            Key::End => (1, b'F'),
+            Key::Insert => (2, b'~'),
+            Key::Delete => (3, b'~'),
+            Key::PageUp => (5, b'~'),
+            Key::PageDown => (6, b'~'),
+            Key::F(n) => match n {
+                1 => (1, b'P'),
+                2 => (1, b'Q'),
+                // CSI R would be ambiguous with a cursor position report
+                3 => (13, b'~'),
+                4 => (1, b'S'),
+                5..=12 => (
+                    [15, 17, 18, 19, 20, 21, 23, 24][usize::from(n - 5)],
+                    b'~',
+                ),
+                13..=35 => (57376 + u32::from(n - 13), b'u'),
+                _ => return vec![],
+            },
+            Key::Keypad(c) => {
+                let Some(offset) = "0123456789./*-+"
+                    .find(c)
+                    .or_else(|| (c == '=').then_some(16))
+                else {
+                    return vec![];
+                };
+                // offset is less than 17
+                (57399 + u32::try_from(offset).unwrap(), b'u')
+            }
+            Key::KeypadEnter => (57414, b'u'),
+        };
+
+        // keys are sent the same way as without the kitty keyboard protocol
+        // unless that would be ambiguous, or the application asked for all
+        // keys to be sent as escape codes
+        if !all_keys {
+            let disambiguate = flags & crate::keyboard::DISAMBIGUATE != 0;
+            let legacy = match self.key {
+                Key::Char(_) | Key::Keypad(_) => {
+                    !disambiguate
+                        || modifiers.without(Modifiers::SHIFT).is_empty()
+                }
+                Key::Enter | Key::Tab | Key::Backspace | Key::KeypadEnter => {
+                    !disambiguate || modifiers.is_empty()
+                }
+                Key::Escape => !disambiguate,
+                // function keys past f12 only have kitty specific codes
+                Key::F(n) if n > 12 => false,
+                _ => self.kind == KeyEventKind::Press && modifiers.is_empty(),
+            };
+            if legacy {
+                // releases of keys without escape codes aren't sent
+                if self.kind == KeyEventKind::Release {
+                    return vec![];
+                }
+                return self.encode_legacy(screen);
+            }
+        }
+
+        let mut key = if code == 1 {
+            String::new()
+        } else {
+            code.to_string()
+        };
+        if flags & crate::keyboard::REPORT_ALTERNATE_KEYS != 0
+            && modifiers.contains(Modifiers::SHIFT)
+        {
+            if let (Key::Char(c), Some(shifted)) =
+                (self.key, self.single_char())
+            {
+                if shifted != c {
+                    key = format!("{key}:{}", u32::from(shifted));
+                }
+            }
+        }
+        let params = if self.kind != KeyEventKind::Press {
+            format!("{}:{}", modifiers.param(), self.kind.param())
+        } else if modifiers.is_empty() {
+            String::new()
+        } else {
+            modifiers.param().to_string()
+        };
+        let text = self
+            .text_codepoints()
+            .filter(|_| {
+                all_keys
+                    && flags & crate::keyboard::REPORT_ASSOCIATED_TEXT != 0
+                    && self.kind != KeyEventKind::Release
+            })
+            .map(|text| format!(";{text}"))
+            .unwrap_or_default();
+        if !params.is_empty() || !text.is_empty() {
+            if key.is_empty() {
+                key.push('1');
+            }
+            key = format!("{key};{params}");
+        }
+        let mut bytes = format!("\x1b[{key}{text}").into_bytes();
+        bytes.push(c);
+        bytes
+    }
+
+    // the legacy (xterm compatible) encoding of the key press
+    fn encode_legacy(&self, screen: &crate::Screen) -> Vec<u8> {
+        let modifiers = self.modifiers;
+        if let Some(bytes) =
+            self.modify_other_keys(screen.modify_other_keys())
+        {
+            return bytes;
+        }
+
+        let mut bytes = vec![];
+        // alt is sent as an escape prefix for keys which don't have a
+        // modifier parameter
+        let alt_prefix = |bytes: &mut Vec<u8>| {
+            if modifiers.contains(Modifiers::ALT) {
+                bytes.push(b'\x1b');
+            }
+        };
+        match self.key {
+            Key::Char(c) => {
+                alt_prefix(&mut bytes);
+                let control = if modifiers.contains(Modifiers::CTRL) {
+                    control_char(c)
+                } else {
+                    None
+                };
+                if let Some(control) = control {
+                    bytes.push(control);
+                } else {
+                    bytes.extend_from_slice(self.text_or(c).as_bytes());
+                }
+            }
+            Key::Enter => {
+                alt_prefix(&mut bytes);
+                if screen.newline_mode() {
+                    bytes.extend_from_slice(b"\r\n");
+                } else {
+                    bytes.push(b'\r');
+                }
+            }
+            Key::Tab => {
+                if modifiers.contains(Modifiers::SHIFT) {
+                    bytes.extend_from_slice(b"\x1b[Z");
+                } else {
+                    alt_prefix(&mut bytes);
+                    bytes.push(b'\t');
+                }
+            }
+            Key::Backspace => {
+                alt_prefix(&mut bytes);
+                // control inverts which of the two codes is sent, so that
+                // both are always available
+                if screen.backarrow_key_mode()
+                    == modifiers.contains(Modifiers::CTRL)
+                {
+                    bytes.push(b'\x7f');
+                } else {
+                    bytes.push(b'\x08');
+                }
+            }
+            Key::Escape => {
+                alt_prefix(&mut bytes);
+                bytes.push(b'\x1b');
+            }
+            Key::Up => self.cursor_key(&mut bytes, screen, b'A'),
+            Key::Down => self.cursor_key(&mut bytes, screen, b'B'),
+            Key::Right => self.cursor_key(&mut bytes, screen, b'C'),
+            Key::Left => self.cursor_key(&mut bytes, screen, b'D'),
+            Key::Home => self.cursor_key(&mut bytes, screen, b'H'),
+            Key::End => self.cursor_key(&mut bytes, screen, b'F'),
+            Key::Insert => tilde(&mut bytes, 2, modifiers),
+            Key::Delete => tilde(&mut bytes, 3, modifiers),
+            Key::PageUp => tilde(&mut bytes, 5, modifiers),
+            Key::PageDown => tilde(&mut bytes, 6, modifiers),
+            Key::F(n) => {
+                // like xterm, f13 through f24 are sent as shifted f1
+                // through f12
+                let (n, modifiers) = if n > 12 {
+                    (n - 12, modifiers | Modifiers::SHIFT)
+                } else {
+                    (n, modifiers)
+                };
+                match n {
+                    1..=4 => {
+                        let c = b"PQRS"[usize::from(n - 1)];
+                        if modifiers.is_empty() {
+                            bytes.extend_from_slice(&[b'\x1b', b'O', c]);
+                        } else {
+                            csi(&mut bytes, 1, modifiers, c);
+                        }
+                    }
+                    5..=12 => {
+                        let code = [15, 17, 18, 19, 20, 21, 23, 24]
+                            [usize::from(n - 5)];
+                        tilde(&mut bytes, code, modifiers);
+                    }
+                    // there are no other function keys
+                    _ => {}
+                }
+            }
+            Key::Keypad(c) => {
+                alt_prefix(&mut bytes);
+                let application = match c {
+                    '0'..='9' => {
+                        // c is an ascii digit
+                        Some(b'p' + u8::try_from(c).unwrap() - b'0')
+                    }
+                    '.' => Some(b'n'),
+                    '+' => Some(b'k'),
+                    '-' => Some(b'm'),
+                    '*' => Some(b'j'),
+                    '/' => Some(b'o'),
+                    '=' => Some(b'X'),
+                    _ => None,
+                };
+                match application {
+                    Some(c) if screen.application_keypad() => {
+                        bytes.extend_from_slice(&[b'\x1b', b'O', c]);
+                    }
+                    _ => bytes.extend_from_slice(self.text_or(c).as_bytes()),
+                }
+            }
+            Key::KeypadEnter => {
+                alt_prefix(&mut bytes);
+                if screen.application_keypad() {
+                    bytes.extend_from_slice(b"\x1bOM");
+                } else if screen.newline_mode() {
+                    bytes.extend_from_slice(b"\r\n");
+                } else {
+                    bytes.push(b'\r');
+                }
+            }
+        }
+        bytes
+    }
+
+    // with xterm's modifyOtherKeys, keys which would otherwise lose their
+    // modifiers are sent as `CSI 27 ; modifiers ; code ~`. at level 1 this
+    // is only done for key combinations which have no other encoding, and
+    // at level 2 it is done for all modified keys other than shifted
+    // characters.
+    fn modify_other_keys(&self, level: u8) -> Option<Vec<u8>> {
+        let modifiers = self.modifiers;
+        if level == 0 || modifiers.is_empty() {
+            return None;
+        }
+        let code = match self.key {
+            Key::Char(c) => {
+                if modifiers == Modifiers::SHIFT {
+                    return None;
+                }
+                if level == 1
+                    && !(modifiers.contains(Modifiers::CTRL)
+                        && control_char(c).is_none())
+                {
+                    return None;
+                }
+                // the code is the character typed, if there is just one
+                u32::from(self.single_char().unwrap_or(c))
+            }
+            Key::Enter | Key::Tab | Key::Backspace | Key::Escape => {
+                let code = match self.key {
+                    Key::Enter => 13,
+                    Key::Tab => 9,
+                    Key::Backspace => 127,
+                    _ => 27,
+                };
+                // these keys already have encodings for alt, shift+tab and
+                // control+backspace
+                if level == 1
+                    && (modifiers == Modifiers::ALT
+                        || (self.key == Key::Tab
+                            && modifiers == Modifiers::SHIFT)
+                        || (self.key == Key::Backspace
+                            && modifiers == Modifiers::CTRL))
+                {
+                    return None;
+                }
+                code
+            }
+            _ => return None,
+        };
+        Some(format!("\x1b[27;{};{code}~", modifiers.param()).into_bytes())
+    }
+
+    fn cursor_key(&self, bytes: &mut Vec<u8>, screen: &crate::Screen, c: u8) {
+        if !self.modifiers.is_empty() {
+            csi(bytes, 1, self.modifiers, c);
+        } else if screen.application_cursor() {
+            bytes.extend_from_slice(&[b'\x1b', b'O', c]);
+        } else {
+            bytes.extend_from_slice(&[b'\x1b', b'[', c]);
+        }
+    }
+
+    fn text_or(&self, c: char) -> String {
+        self.text.clone().unwrap_or_else(|| c.to_string())
+    }
+
+    // the character typed by the key, if it types exactly one
+    fn single_char(&self) -> Option<char> {
+        let text = match self.key {
+            Key::Char(c) | Key::Keypad(c) => self.text_or(c),
+            _ => return None,
+        };
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+
+    // the text typed by the key as colon separated codepoints, for the
+    // kitty keyboard protocol. control characters aren't included.
+    fn text_codepoints(&self) -> Option<String> {
+        let text = match self.key {
+            Key::Char(c) | Key::Keypad(c) => self.text_or(c),
+            _ => return None,
+        };
+        let codepoints: Vec<_> = text
+            .chars()
+            .filter(|c| !c.is_control())
+            .map(|c| u32::from(c).to_string())
+            .collect();
+        (!codepoints.is_empty()).then(|| codepoints.join(":"))
+    }
+}
+
+// `CSI code ; modifiers c`
+fn csi(bytes: &mut Vec<u8>, code: u8, modifiers: Modifiers, c: u8) {
+    bytes.extend_from_slice(
+        format!("\x1b[{code};{}", modifiers.param()).as_bytes(),
+    );
+    bytes.push(c);
+}
+
+// `CSI code ~` or `CSI code ; modifiers ~`
+fn tilde(bytes: &mut Vec<u8>, code: u8, modifiers: Modifiers) {
+    if modifiers.is_empty() {
+        bytes.extend_from_slice(format!("\x1b[{code}~").as_bytes());
+    } else {
+        csi(bytes, code, modifiers, b'~');
+    }
+}
+
+// the control character sent when the key for c is pressed while holding
+// control, following xterm
+fn control_char(c: char) -> Option<u8> {
+    let c = u8::try_from(c).ok()?;
+    match c {
+        b'@' | b' ' | b'2' => Some(0x00),
+        b'a'..=b'z' => Some(c - b'a' + 1),
+        b'A'..=b'Z' => Some(c - b'A' + 1),
+        b'[' | b'3' => Some(0x1b),
+        b'\\' | b'4' => Some(0x1c),
+        b']' | b'5' => Some(0x1d),
+        b'^' | b'6' | b'~' => Some(0x1e),
+        b'_' | b'7' | b'/' | b'-' => Some(0x1f),
+        b'8' | b'?' => Some(0x7f),
+        _ => None,
+    }
+}
@@ -0,0 +1,57 @@
+// the progressive enhancements of the kitty keyboard protocol
+pub const DISAMBIGUATE: u8 = 0b0_0001;
+pub const REPORT_EVENT_TYPES: u8 = 0b0_0010;
+pub const REPORT_ALTERNATE_KEYS: u8 = 0b0_0100;
+pub const REPORT_ALL_KEYS: u8 = 0b0_1000;
+pub const REPORT_ASSOCIATED_TEXT: u8 = 0b1_0000;
+const ALL_FLAGS: u8 = 0b1_1111;
+
+// when more entries than this are pushed, the oldest ones are discarded, so
+// that applications which push without popping can't use unbounded memory
+const MAX_STACK_LEN: usize = 16;
+
+// the stack of kitty keyboard protocol flags. each screen has its own
+// stack, and the flags on top of it are the ones currently in effect.
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardFlags {
+    stack: Vec<u8>,
+}
+
+impl KeyboardFlags {
+    pub fn current(&self) -> u8 {
+        self.stack.last().copied().unwrap_or(0)
+    }
+
+    pub fn stack(&self) -> &[u8] {
+        &self.stack
+    }
+
+    pub fn push(&mut self, flags: u8) {
+        if self.stack.len() >= MAX_STACK_LEN {
+            self.stack.remove(0);
+        }
+        self.stack.push(flags & ALL_FLAGS);
+    }
+
+    pub fn pop(&mut self, count: usize) {
+        self.stack.truncate(self.stack.len().saturating_sub(count));
+    }
+
+    // mode 1 replaces the current flags, 2 sets the given flags, and 3
+    // clears them
+    pub fn set(&mut self, flags: u8, mode: u16) -> bool {
+        let current = self.current();
+        let flags = match mode {
+            1 => flags,
+            2 => current | flags,
+            3 => current & !flags,
+            _ => return false,
+        } & ALL_FLAGS;
+        if let Some(top) = self.stack.last_mut() {
+            *top = flags;
+        } else {
+            self.stack.push(flags);
+        }
+        true
+    }
+}
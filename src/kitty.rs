@@ -0,0 +1,394 @@
+// images larger than this are rejected, so that a malicious or broken
+// application can't make us allocate arbitrary amounts of memory
+const MAX_SIZE: u32 = 4096;
+// the largest amount of (base64 encoded) data that a chunked transmission
+// can contain, which is enough for an rgba image of the maximum size
+const MAX_DATA_LEN: usize = (4096 * 4096 * 4 + 2) / 3 * 4;
+// when the stored images take up more than this many bytes, the oldest ones
+// are discarded
+const MAX_STORAGE: usize = 256 * 1024 * 1024;
+
+// the control data of a graphics command, which is a comma separated list
+// of key=value pairs where the keys are single characters and the values
+// are either single characters or integers
+#[derive(Clone, Debug)]
+pub struct Command {
+    pub action: u8,
+    pub quiet: u32,
+    pub format: u32,
+    pub medium: u8,
+    pub compression: Option<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub image_id: u32,
+    pub image_number: u32,
+    pub placement_id: u32,
+    pub more: bool,
+    pub source: (u32, u32, u32, u32),
+    pub offset: (u32, u32),
+    pub cols: u32,
+    pub rows: u32,
+    pub move_cursor: bool,
+    pub z_index: i32,
+    pub delete: u8,
+    pub virtual_placement: bool,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: b't',
+            quiet: 0,
+            format: 32,
+            medium: b'd',
+            compression: None,
+            width: 0,
+            height: 0,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            more: false,
+            source: (0, 0, 0, 0),
+            offset: (0, 0),
+            cols: 0,
+            rows: 0,
+            move_cursor: true,
+            z_index: 0,
+            delete: b'a',
+            virtual_placement: false,
+        }
+    }
+}
+
+impl Command {
+    pub fn parse(control: &[u8]) -> Option<Self> {
+        let mut command = Self::default();
+        for pair in control.split(|&c| c == b',') {
+            if pair.is_empty() {
+                continue;
+            }
+            let [key, b'=', value @ ..] = pair else {
+                return None;
+            };
+            let number = || -> Option<i64> {
+                std::str::from_utf8(value).ok()?.parse().ok()
+            };
+            let unsigned = || u32::try_from(number()?).ok();
+            let character = || match value {
+                [c] => Some(*c),
+                _ => None,
+            };
+            match key {
+                b'a' => command.action = character()?,
+                b'q' => command.quiet = unsigned()?,
+                b'f' => command.format = unsigned()?,
+                b't' => command.medium = character()?,
+                b'o' => command.compression = Some(character()?),
+                b's' => command.width = unsigned()?,
+                b'v' => command.height = unsigned()?,
+                b'i' => command.image_id = unsigned()?,
+                b'I' => command.image_number = unsigned()?,
+                b'p' => command.placement_id = unsigned()?,
+                b'm' => command.more = unsigned()? == 1,
+                b'x' => command.source.0 = unsigned()?,
+                b'y' => command.source.1 = unsigned()?,
+                b'w' => command.source.2 = unsigned()?,
+                b'h' => command.source.3 = unsigned()?,
+                b'X' => command.offset.0 = unsigned()?,
+                b'Y' => command.offset.1 = unsigned()?,
+                b'c' => command.cols = unsigned()?,
+                b'r' => command.rows = unsigned()?,
+                b'C' => command.move_cursor = unsigned()? == 0,
+                b'z' => command.z_index = i32::try_from(number()?).ok()?,
+                b'd' => command.delete = character()?,
+                b'U' => command.virtual_placement = unsigned()? == 1,
+                // other keys (for animation, relative placements, and
+                // reading from files) aren't supported, and are ignored
+                _ => {}
+            }
+        }
+        Some(command)
+    }
+
+    // the response to a command is only sent if the application gave the
+    // image an id or a number, and can be suppressed with the q key
+    pub fn response(
+        &self,
+        image_id: u32,
+        result: Result<(), Error>,
+    ) -> Option<Vec<u8>> {
+        if self.image_id == 0 && self.image_number == 0 {
+            return None;
+        }
+        let message = match result {
+            Ok(()) if self.quiet == 0 => "OK".to_string(),
+            Err(Error(code, message)) if self.quiet <= 1 => {
+                format!("{code}:{message}")
+            }
+            _ => return None,
+        };
+        let number = if self.image_number == 0 {
+            String::new()
+        } else {
+            format!(",I={}", self.image_number)
+        };
+        let placement = if self.placement_id == 0 {
+            String::new()
+        } else {
+            format!(",p={}", self.placement_id)
+        };
+        Some(
+            format!("\x1b_Gi={image_id}{number}{placement};{message}\x1b\\")
+                .into_bytes(),
+        )
+    }
+
+    // the most (base64 encoded) data that the command can need, which is
+    // known exactly for raw pixel data of a given size
+    fn max_data_len(&self) -> usize {
+        let bytes_per_pixel = match self.format {
+            24 => 3,
+            32 => 4,
+            _ => return MAX_DATA_LEN,
+        };
+        if self.compression.is_some()
+            || self.width == 0
+            || self.height == 0
+            || self.width > MAX_SIZE
+            || self.height > MAX_SIZE
+        {
+            return MAX_DATA_LEN;
+        }
+        // width and height are limited to MAX_SIZE
+        let pixels = usize::try_from(self.width * self.height).unwrap();
+        (pixels * bytes_per_pixel + 2) / 3 * 4
+    }
+
+    // decodes the image data sent with a transmit command
+    pub fn decode(&self, data: &[u8]) -> Result<crate::Image, Error> {
+        if self.medium != b'd' {
+            return Err(Error::new(
+                "EINVAL",
+                "only direct transmission is supported",
+            ));
+        }
+        if self.compression.is_some() {
+            return Err(Error::new("EINVAL", "compression is not supported"));
+        }
+        let bytes_per_pixel = match self.format {
+            24 => 3,
+            32 => 4,
+            100 => {
+                return Err(Error::new("EINVAL", "PNG is not supported"));
+            }
+            _ => return Err(Error::new("EINVAL", "unknown format")),
+        };
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::new("EINVAL", "image size not specified"));
+        }
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(Error::new("EFBIG", "image too large"));
+        }
+        let Some(data) = crate::base64::decode(data) else {
+            return Err(Error::new("EINVAL", "invalid base64 data"));
+        };
+        // width and height are limited to MAX_SIZE
+        let pixels = usize::try_from(self.width * self.height).unwrap();
+        if data.len() < pixels * bytes_per_pixel {
+            return Err(Error::new("ENODATA", "insufficient image data"));
+        }
+        let rgba = if bytes_per_pixel == 4 {
+            data[..pixels * 4].to_vec()
+        } else {
+            data.chunks(3)
+                .take(pixels)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect()
+        };
+        Ok(crate::Image::new(self.width, self.height, rgba))
+    }
+
+    // works out where to draw an image, returning the part of the image to
+    // draw and the area to draw it in, relative to the top left corner of
+    // the cursor cell
+    pub fn placement_rects(
+        &self,
+        image: &crate::Image,
+        cell_size: (u16, u16),
+    ) -> ((u32, u32, u32, u32), (u32, u32, u32, u32)) {
+        let x = self.source.0.min(image.width());
+        let y = self.source.1.min(image.height());
+        let clamp = |size: u32, max: u32| {
+            if size == 0 {
+                max
+            } else {
+                size.min(max)
+            }
+        };
+        let width = clamp(self.source.2, image.width() - x);
+        let height = clamp(self.source.3, image.height() - y);
+
+        // images are scaled to fit the given number of rows and columns,
+        // keeping the aspect ratio if only one of them is given
+        let scale = |size: u32, from: u32, to: u32| {
+            let scaled =
+                u64::from(size) * u64::from(to) / u64::from(from.max(1));
+            u32::try_from(scaled).unwrap_or(u32::MAX)
+        };
+        let cols_width = self.cols.saturating_mul(u32::from(cell_size.0));
+        let rows_height = self.rows.saturating_mul(u32::from(cell_size.1));
+        let (dest_width, dest_height) = match (self.cols, self.rows) {
+            (0, 0) => (width, height),
+            (_, 0) => (cols_width, scale(height, width, cols_width)),
+            (0, _) => (scale(width, height, rows_height), rows_height),
+            _ => (cols_width, rows_height),
+        };
+        (
+            (x, y, width, height),
+            (
+                self.offset.0.min(u32::from(cell_size.0) - 1),
+                self.offset.1.min(u32::from(cell_size.1) - 1),
+                dest_width.min(MAX_SIZE * 4),
+                dest_height.min(MAX_SIZE * 4),
+            ),
+        )
+    }
+}
+
+pub struct Error(&'static str, String);
+
+impl Error {
+    pub fn new(code: &'static str, message: &str) -> Self {
+        Self(code, message.to_string())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StoredImage {
+    id: u32,
+    number: u32,
+    image: std::sync::Arc<crate::Image>,
+}
+
+#[derive(Clone, Debug)]
+struct Transmission {
+    command: Command,
+    data: Vec<u8>,
+    too_large: bool,
+}
+
+// the images which have been transmitted by the application, and which can
+// be displayed by referring to their id or number
+#[derive(Clone, Debug, Default)]
+pub struct Images {
+    // oldest first
+    stored: Vec<StoredImage>,
+    next_id: u32,
+    transmission: Option<Transmission>,
+}
+
+impl Images {
+    // returns the command and the full data for the command once all of the
+    // chunks have been received
+    pub fn receive(
+        &mut self,
+        command: Command,
+        data: &[u8],
+    ) -> Option<(Command, Result<Vec<u8>, Error>)> {
+        if let Some(transmission) = &mut self.transmission {
+            // chunks after the first only contain the m key (and possibly
+            // q), so the rest of the command comes from the first chunk.
+            // once the data is too large, what was received so far is
+            // thrown away rather than held on to until the last chunk.
+            if transmission.too_large
+                || transmission.data.len() + data.len()
+                    > transmission.command.max_data_len()
+            {
+                transmission.too_large = true;
+                transmission.data = vec![];
+            } else {
+                transmission.data.extend_from_slice(data);
+            }
+            if command.more {
+                return None;
+            }
+            let transmission = self.transmission.take()?;
+            let data = if transmission.too_large {
+                Err(Error::new("EFBIG", "image too large"))
+            } else {
+                Ok(transmission.data)
+            };
+            return Some((transmission.command, data));
+        }
+        if command.more && matches!(command.action, b't' | b'T' | b'q') {
+            let too_large = data.len() > command.max_data_len();
+            self.transmission = Some(Transmission {
+                command,
+                data: if too_large { vec![] } else { data.to_vec() },
+                too_large,
+            });
+            return None;
+        }
+        Some((command, Ok(data.to_vec())))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&std::sync::Arc<crate::Image>> {
+        self.stored
+            .iter()
+            .find(|image| image.id == id)
+            .map(|image| &image.image)
+    }
+
+    // returns the id of the newest image with the given number
+    pub fn id_for_number(&self, number: u32) -> Option<u32> {
+        self.stored
+            .iter()
+            .rev()
+            .find(|image| image.number == number)
+            .map(|image| image.id)
+    }
+
+    // stores an image, replacing any existing image with the same id. if
+    // the id is 0, a new id is assigned.
+    pub fn insert(
+        &mut self,
+        id: u32,
+        number: u32,
+        image: crate::Image,
+    ) -> u32 {
+        let id = if id == 0 { self.unused_id() } else { id };
+        self.remove(id);
+        self.stored.push(StoredImage {
+            id,
+            number,
+            image: std::sync::Arc::new(image),
+        });
+
+        let mut total: usize = self
+            .stored
+            .iter()
+            .map(|image| image.image.rgba().len())
+            .sum();
+        while total > MAX_STORAGE && self.stored.len() > 1 {
+            total -= self.stored.remove(0).image.rgba().len();
+        }
+        id
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.stored.retain(|image| image.id != id);
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.stored.iter().map(|image| image.id)
+    }
+
+    fn unused_id(&mut self) -> u32 {
+        loop {
+            self.next_id = self.next_id.wrapping_add(1).max(1);
+            if self.get(self.next_id).is_none() {
+                return self.next_id;
+            }
+        }
+    }
+}
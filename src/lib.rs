@@ -47,18 +47,47 @@
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::type_complexity)]
 
+mod apc;
 mod attrs;
+mod base64;
 mod callbacks;
 mod cell;
+mod charset;
 mod grid;
+mod hyperlink;
+mod identity;
+mod image;
+mod iterm;
+mod key;
+mod keyboard;
+mod kitty;
+mod locator;
+mod mouse;
+mod notification;
+mod palette;
 mod parser;
+mod paste;
 mod perform;
+mod prompt;
 mod row;
 mod screen;
+mod sixel;
 mod term;
 
-pub use attrs::Color;
+pub use attrs::{Color, UnderlineStyle};
 pub use callbacks::Callbacks;
 pub use cell::Cell;
+pub use charset::Charset;
+pub use hyperlink::Hyperlink;
+pub use identity::TerminalIdentity;
+pub use image::{Image, ImagePlacement};
+pub use key::{Key, KeyEvent, KeyEventKind, Modifiers};
+pub use mouse::{MouseButton, MouseEvent, MouseEventKind};
+pub use notification::{Notification, Urgency};
+pub use palette::DefaultColors;
 pub use parser::Parser;
-pub use screen::{MouseProtocolEncoding, MouseProtocolMode, Screen};
+pub use paste::PasteControls;
+pub use prompt::Command;
+pub use screen::{
+    CursorStyle, MouseProtocolEncoding, MouseProtocolMode, Screen,
+};
@@ -0,0 +1,166 @@
+// a rectangle (top, left, bottom, right), in the locator's units
+type Rect = (u32, u32, u32, u32);
+
+// the state of DEC locator mode, which reports the mouse position as
+// `CSI Pe ; Pb ; Pr ; Pc ; Pp & w` (DECLRP), either on request or when
+// buttons are pressed or the mouse leaves a rectangle. whether it is
+// enabled is tracked by the mouse protocol mode.
+#[derive(Clone, Debug, Default)]
+pub struct Locator {
+    one_shot: bool,
+    pixels: bool,
+    report_press: bool,
+    report_release: bool,
+    filter: Option<Rect>,
+    // the (row, col) and (y, x) of the last mouse event, starting from 1
+    position: Option<((u32, u32), (u32, u32))>,
+    buttons: u8,
+}
+
+impl Locator {
+    pub fn enable(&mut self, one_shot: bool, pixels: bool) {
+        self.one_shot = one_shot;
+        self.pixels = pixels;
+    }
+
+    pub fn disable(&mut self) {
+        self.one_shot = false;
+        self.pixels = false;
+        self.filter = None;
+    }
+
+    // once a one shot locator has sent a report, it should be disabled
+    pub fn one_shot(&self) -> bool {
+        self.one_shot
+    }
+
+    pub fn pixels(&self) -> bool {
+        self.pixels
+    }
+
+    pub fn report_press(&self) -> bool {
+        self.report_press
+    }
+
+    pub fn report_release(&self) -> bool {
+        self.report_release
+    }
+
+    // DECSLE
+    pub fn select_events(&mut self, params: &[u16]) -> bool {
+        for param in params {
+            match param {
+                0 => {
+                    self.report_press = false;
+                    self.report_release = false;
+                    self.filter = None;
+                }
+                1 => self.report_press = true,
+                2 => self.report_press = false,
+                3 => self.report_release = true,
+                4 => self.report_release = false,
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    // DECEFR. parameters which are omitted (or 0) default to the current
+    // position, and if the mouse is already outside of the rectangle, the
+    // report is sent immediately.
+    pub fn set_filter(&mut self, params: [u16; 4]) -> Option<Vec<u8>> {
+        let current = self.current();
+        let edge = |param: u16, current: Option<u32>| {
+            if param == 0 {
+                current
+            } else {
+                Some(u32::from(param))
+            }
+        };
+        let row = current.map(|(row, _)| row);
+        let col = current.map(|(_, col)| col);
+        self.filter = (|| {
+            Some((
+                edge(params[0], row)?,
+                edge(params[1], col)?,
+                edge(params[2], row)?,
+                edge(params[3], col)?,
+            ))
+        })();
+        self.check_filter()
+    }
+
+    // DECRQLP
+    pub fn position_report(&self) -> Vec<u8> {
+        self.current().map_or_else(
+            || b"\x1b[0&w".to_vec(),
+            |(row, col)| {
+                format!("\x1b[1;{};{row};{col};1&w", self.buttons)
+                    .into_bytes()
+            },
+        )
+    }
+
+    // updates the locator with a mouse event, returning the reports that
+    // should be sent if locator mode is enabled
+    pub fn mouse_event(
+        &mut self,
+        event: &crate::MouseEvent,
+        cell_size: (u16, u16),
+    ) -> Vec<u8> {
+        let (row, col) = event.position();
+        let (x, y) = event.pixel_position(cell_size);
+        self.position = Some((
+            (u32::from(row) + 1, u32::from(col) + 1),
+            (y.saturating_add(1), x.saturating_add(1)),
+        ));
+
+        let mut reports = self.check_filter().unwrap_or_default();
+        let (button, pressed) = match event.kind() {
+            crate::MouseEventKind::Press(button) => (button, true),
+            crate::MouseEventKind::Release(button) => (button, false),
+            crate::MouseEventKind::Motion(_) => return reports,
+        };
+        let (mask, event) = match button {
+            crate::MouseButton::Right => (1, 6),
+            crate::MouseButton::Middle => (2, 4),
+            crate::MouseButton::Left => (4, 2),
+            crate::MouseButton::Back => (8, 8),
+            // the locator only has four buttons
+            _ => return reports,
+        };
+        if pressed {
+            self.buttons |= mask;
+            if self.report_press {
+                reports.extend(self.report(event));
+            }
+        } else {
+            self.buttons &= !mask;
+            if self.report_release {
+                reports.extend(self.report(event + 1));
+            }
+        }
+        reports
+    }
+
+    fn current(&self) -> Option<(u32, u32)> {
+        self.position
+            .map(|(cells, pixels)| if self.pixels { pixels } else { cells })
+    }
+
+    // the filter rectangle only applies once
+    fn check_filter(&mut self) -> Option<Vec<u8>> {
+        let (top, left, bottom, right) = self.filter?;
+        let (row, col) = self.current()?;
+        if (top..=bottom).contains(&row) && (left..=right).contains(&col) {
+            return None;
+        }
+        self.filter = None;
+        Some(self.report(10))
+    }
+
+    fn report(&self, event: u8) -> Vec<u8> {
+        let (row, col) = self.current().unwrap_or((1, 1));
+        format!("\x1b[{event};{};{row};{col};1&w", self.buttons).into_bytes()
+    }
+}
@@ -0,0 +1,343 @@
+/// A mouse button, as used in a [`MouseEvent`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MouseButton {
+    /// The left mouse button.
+    Left,
+
+    /// The middle mouse button.
+    Middle,
+
+    /// The right mouse button.
+    Right,
+
+    /// Scrolling the mouse wheel up.
+    WheelUp,
+
+    /// Scrolling the mouse wheel down.
+    WheelDown,
+
+    /// Scrolling the mouse wheel left.
+    WheelLeft,
+
+    /// Scrolling the mouse wheel right.
+    WheelRight,
+
+    /// The back button (sometimes called button 8).
+    Back,
+
+    /// The forward button (sometimes called button 9).
+    Forward,
+}
+
+impl MouseButton {
+    fn code(self) -> u16 {
+        match self {
+            Self::Left => 0,
+            Self::Middle => 1,
+            Self::Right => 2,
+            Self::WheelUp => 64,
+            Self::WheelDown => 65,
+            Self::WheelLeft => 66,
+            Self::WheelRight => 67,
+            Self::Back => 128,
+            Self::Forward => 129,
+        }
+    }
+
+    fn is_wheel(self) -> bool {
+        matches!(
+            self,
+            Self::WheelUp
+                | Self::WheelDown
+                | Self::WheelLeft
+                | Self::WheelRight
+        )
+    }
+}
+
+/// What happened in a [`MouseEvent`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MouseEventKind {
+    /// A button was pressed (or the wheel was scrolled).
+    Press(MouseButton),
+
+    /// A button was released. Releases of the mouse wheel are never
+    /// reported.
+    Release(MouseButton),
+
+    /// The mouse moved, optionally while a button was held down.
+    Motion(Option<MouseButton>),
+}
+
+/// A mouse event, which can be turned into the bytes to send to the
+/// application with [`Screen::encode_mouse`](crate::Screen::encode_mouse).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MouseEvent {
+    kind: MouseEventKind,
+    pos: (u16, u16),
+    pixel_pos: Option<(u32, u32)>,
+    modifiers: crate::Modifiers,
+}
+
+impl MouseEvent {
+    /// Creates a new mouse event, at the given (row, col) of the visible
+    /// screen (starting from 0).
+    #[must_use]
+    pub fn new(kind: MouseEventKind, row: u16, col: u16) -> Self {
+        Self {
+            kind,
+            pos: (row, col),
+            pixel_pos: None,
+            modifiers: crate::Modifiers::NONE,
+        }
+    }
+
+    /// Sets the modifier keys which were held down. Only shift, alt (or
+    /// meta) and control can be reported.
+    #[must_use]
+    pub fn with_modifiers(mut self, modifiers: crate::Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Sets the position of the mouse in pixels, as (x, y) starting from
+    /// 0. This is used by the SGR-pixels encoding. If it isn't set, the
+    /// position of the top left corner of the cell is used, based on
+    /// [`Screen::cell_size`](crate::Screen::cell_size).
+    #[must_use]
+    pub fn with_pixel_position(mut self, x: u32, y: u32) -> Self {
+        self.pixel_pos = Some((x, y));
+        self
+    }
+
+    /// Returns what happened in the event.
+    #[must_use]
+    pub fn kind(&self) -> MouseEventKind {
+        self.kind
+    }
+
+    /// Returns the (row, col) of the cell that the event happened in.
+    #[must_use]
+    pub fn position(&self) -> (u16, u16) {
+        self.pos
+    }
+
+    // the (x, y) position in pixels, which defaults to the top left corner
+    // of the cell
+    pub(crate) fn pixel_position(&self, cell_size: (u16, u16)) -> (u32, u32) {
+        self.pixel_pos.unwrap_or_else(|| {
+            (
+                u32::from(self.pos.1) * u32::from(cell_size.0),
+                u32::from(self.pos.0) * u32::from(cell_size.1),
+            )
+        })
+    }
+
+    pub(crate) fn encode(
+        &self,
+        mode: crate::MouseProtocolMode,
+        encoding: crate::MouseProtocolEncoding,
+        cell_size: (u16, u16),
+        state: &mut MouseState,
+    ) -> Vec<u8> {
+        let reported = match self.kind {
+            // the dec locator has its own reports
+            MouseEventKind::Press(_) => !matches!(
+                mode,
+                crate::MouseProtocolMode::None
+                    | crate::MouseProtocolMode::DecLocator
+            ),
+            MouseEventKind::Release(button) => {
+                !button.is_wheel()
+                    && matches!(
+                        mode,
+                        crate::MouseProtocolMode::PressRelease
+                            | crate::MouseProtocolMode::Highlight
+                            | crate::MouseProtocolMode::ButtonMotion
+                            | crate::MouseProtocolMode::AnyMotion
+                    )
+            }
+            // only dragging is reported in button motion mode
+            MouseEventKind::Motion(button) => match mode {
+                crate::MouseProtocolMode::ButtonMotion => button.is_some(),
+                crate::MouseProtocolMode::AnyMotion => true,
+                _ => false,
+            },
+        };
+        if !reported {
+            return vec![];
+        }
+
+        // coordinates are reported starting from 1
+        let (x, y) = if encoding == crate::MouseProtocolEncoding::SgrPixels {
+            let (x, y) = self.pixel_position(cell_size);
+            (x.saturating_add(1), y.saturating_add(1))
+        } else {
+            (u32::from(self.pos.1) + 1, u32::from(self.pos.0) + 1)
+        };
+
+        // motion is only reported when the mouse moves to a different cell
+        // (or pixel, for SGR-pixels)
+        if let MouseEventKind::Motion(_) = self.kind {
+            if state.last_pos == Some((x, y)) {
+                return vec![];
+            }
+        }
+        state.last_pos = Some((x, y));
+
+        if mode == crate::MouseProtocolMode::Highlight
+            && self.kind == MouseEventKind::Release(MouseButton::Left)
+        {
+            if let Some(highlight) = state.highlight.take() {
+                return highlight.report((x, y), encoding);
+            }
+        }
+
+        let sgr = matches!(
+            encoding,
+            crate::MouseProtocolEncoding::Sgr
+                | crate::MouseProtocolEncoding::SgrPixels
+        );
+        let mut code = match self.kind {
+            MouseEventKind::Press(button) => button.code(),
+            // only the sgr encodings report which button was released
+            MouseEventKind::Release(button) => {
+                if sgr {
+                    button.code()
+                } else {
+                    3
+                }
+            }
+            MouseEventKind::Motion(button) => {
+                button.map_or(3, MouseButton::code) + 32
+            }
+        };
+        // x10 mode doesn't report modifiers
+        if mode != crate::MouseProtocolMode::Press {
+            let modifiers = self.modifiers;
+            if modifiers.contains(crate::Modifiers::SHIFT) {
+                code += 4;
+            }
+            if modifiers.contains(crate::Modifiers::ALT)
+                || modifiers.contains(crate::Modifiers::META)
+            {
+                code += 8;
+            }
+            if modifiers.contains(crate::Modifiers::CTRL) {
+                code += 16;
+            }
+        }
+
+        match encoding {
+            crate::MouseProtocolEncoding::Default
+            | crate::MouseProtocolEncoding::Utf8 => {
+                let mut bytes = b"\x1b[M".to_vec();
+                if push_coords(&mut bytes, &[u32::from(code), x, y], encoding)
+                {
+                    bytes
+                } else {
+                    vec![]
+                }
+            }
+            crate::MouseProtocolEncoding::Urxvt => {
+                format!("\x1b[{};{x};{y}M", code + 32).into_bytes()
+            }
+            crate::MouseProtocolEncoding::Sgr
+            | crate::MouseProtocolEncoding::SgrPixels => {
+                let c = if let MouseEventKind::Release(_) = self.kind {
+                    'm'
+                } else {
+                    'M'
+                };
+                format!("\x1b[<{code};{x};{y}{c}").into_bytes()
+            }
+        }
+    }
+}
+
+// the state needed to encode mouse events, other than the mouse modes
+#[derive(Clone, Debug, Default)]
+pub struct MouseState {
+    // the coordinates of the last reported event
+    last_pos: Option<(u32, u32)>,
+    highlight: Option<Highlight>,
+}
+
+impl MouseState {
+    // XTHIMOUSE (`CSI func ; startx ; starty ; firstrow ; lastrow T`), sent
+    // by the application in response to a button press in highlight
+    // tracking mode
+    pub fn highlight(&mut self, params: &[u16]) {
+        self.highlight = match params {
+            [0, ..] => None,
+            &[_, start_x, start_y, first_row, last_row] => Some(Highlight {
+                start: (u32::from(start_x), u32::from(start_y)),
+                first_row: u32::from(first_row),
+                last_row: u32::from(last_row),
+            }),
+            _ => return,
+        };
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Highlight {
+    start: (u32, u32),
+    first_row: u32,
+    // the row after the last row that the highlight can end in
+    last_row: u32,
+}
+
+impl Highlight {
+    // when the button is released, the application is told where the
+    // highlighted text ended instead of getting a normal release event
+    fn report(
+        &self,
+        mouse: (u32, u32),
+        encoding: crate::MouseProtocolEncoding,
+    ) -> Vec<u8> {
+        let last_row = self.last_row.saturating_sub(1).max(self.first_row);
+        let end = (mouse.0, mouse.1.clamp(self.first_row, last_row));
+        let mut bytes = vec![];
+        let coords = if end == self.start {
+            bytes.extend_from_slice(b"\x1b[t");
+            vec![mouse.0, mouse.1]
+        } else {
+            bytes.extend_from_slice(b"\x1b[T");
+            vec![self.start.0, self.start.1, end.0, end.1, mouse.0, mouse.1]
+        };
+        if push_coords(&mut bytes, &coords, encoding) {
+            bytes
+        } else {
+            vec![]
+        }
+    }
+}
+
+// the legacy encodings send each value as a single character offset by 32,
+// which limits the coordinates that can be reported. returns false if any
+// of the values can't be encoded.
+fn push_coords(
+    bytes: &mut Vec<u8>,
+    values: &[u32],
+    encoding: crate::MouseProtocolEncoding,
+) -> bool {
+    for value in values {
+        let value = value.saturating_add(32);
+        if encoding == crate::MouseProtocolEncoding::Utf8 {
+            // the utf8 encoding is limited to two byte characters
+            let Some(c) = char::from_u32(value).filter(|_| value < 0x800)
+            else {
+                return false;
+            };
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+        } else {
+            let Ok(value) = u8::try_from(value) else {
+                return false;
+            };
+            bytes.push(value);
+        }
+    }
+    true
+}
@@ -0,0 +1,174 @@
+// notifications larger than this are truncated, to avoid an application
+// being able to use up arbitrary amounts of memory with unfinished chunked
+// notifications
+const MAX_NOTIFICATION_LEN: usize = 64 * 1024;
+
+/// How urgent a desktop notification is.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum Urgency {
+    /// A low urgency notification.
+    Low,
+
+    /// A normal urgency notification.
+    #[default]
+    Normal,
+
+    /// A critical notification.
+    Critical,
+}
+
+/// A desktop notification requested by the application, with `\e]9;<body>`,
+/// `\e]777;notify;<title>;<body>` or `\e]99;<metadata>;<payload>`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Notification {
+    id: Option<String>,
+    title: String,
+    body: String,
+    urgency: Urgency,
+}
+
+impl Notification {
+    pub(crate) fn new(title: &[u8], body: &[u8]) -> Self {
+        Self {
+            title: String::from_utf8_lossy(title).into_owned(),
+            body: String::from_utf8_lossy(body).into_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the identifier given to the notification by the application,
+    /// if any. Only `\e]99` notifications can have an identifier.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns the title of the notification. This is empty for `\e]9`
+    /// notifications, which only have a body.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns the body of the notification.
+    #[must_use]
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Returns the urgency of the notification. Only `\e]99` notifications
+    /// can specify an urgency, so other notifications are always
+    /// [`Urgency::Normal`].
+    #[must_use]
+    pub fn urgency(&self) -> Urgency {
+        self.urgency
+    }
+}
+
+// an OSC 99 notification which may be split across several sequences. the
+// payload is kept as bytes until the notification is complete, since a
+// chunk boundary can fall in the middle of a utf8 character.
+#[derive(Clone, Debug, Default)]
+pub struct PendingNotification {
+    id: Option<String>,
+    title: Vec<u8>,
+    body: Vec<u8>,
+    urgency: Urgency,
+}
+
+impl PendingNotification {
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    pub fn finish(self) -> Notification {
+        Notification {
+            id: self.id,
+            title: String::from_utf8_lossy(&self.title).into_owned(),
+            body: String::from_utf8_lossy(&self.body).into_owned(),
+            urgency: self.urgency,
+        }
+    }
+}
+
+pub enum PayloadType {
+    Title,
+    Body,
+}
+
+// the parsed metadata section of an OSC 99 sequence, which is a colon
+// separated list of key=value pairs. unknown keys are ignored, as required
+// by the spec.
+pub struct Metadata {
+    pub id: Option<String>,
+    pub done: bool,
+    pub payload_type: PayloadType,
+    pub base64: bool,
+    pub urgency: Option<Urgency>,
+}
+
+impl Metadata {
+    pub fn parse(metadata: &[u8]) -> Option<Self> {
+        let mut parsed = Self {
+            id: None,
+            done: true,
+            payload_type: PayloadType::Title,
+            base64: false,
+            urgency: None,
+        };
+        for pair in metadata.split(|&c| c == b':') {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = match pair {
+                [key, b'=', value @ ..] => (*key, value),
+                _ => return None,
+            };
+            match (key, value) {
+                (b'i', id) => {
+                    if !id.iter().all(|&c| {
+                        c.is_ascii_alphanumeric() || b"-_+.".contains(&c)
+                    }) {
+                        return None;
+                    }
+                    // we just checked that this is ascii
+                    parsed.id =
+                        Some(std::str::from_utf8(id).unwrap().to_string());
+                }
+                (b'd', b"0") => parsed.done = false,
+                (b'd', b"1") => parsed.done = true,
+                (b'p', b"title") => parsed.payload_type = PayloadType::Title,
+                (b'p', b"body") => parsed.payload_type = PayloadType::Body,
+                (b'e', b"0") => parsed.base64 = false,
+                (b'e', b"1") => parsed.base64 = true,
+                (b'u', b"0") => parsed.urgency = Some(Urgency::Low),
+                (b'u', b"1") => parsed.urgency = Some(Urgency::Normal),
+                (b'u', b"2") => parsed.urgency = Some(Urgency::Critical),
+                (b'd' | b'p' | b'e' | b'u', _) => return None,
+                _ => {}
+            }
+        }
+        Some(parsed)
+    }
+
+    pub fn start(&self) -> PendingNotification {
+        PendingNotification {
+            id: self.id.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn apply(&self, pending: &mut PendingNotification, payload: &[u8]) {
+        if let Some(urgency) = self.urgency {
+            pending.urgency = urgency;
+        }
+        let buf = match self.payload_type {
+            PayloadType::Title => &mut pending.title,
+            PayloadType::Body => &mut pending.body,
+        };
+        let len = payload
+            .len()
+            .min(MAX_NOTIFICATION_LEN.saturating_sub(buf.len()));
+        buf.extend_from_slice(&payload[..len]);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::term::BufWrite as _;
+
+/// The colors that the terminal uses when the application hasn't overridden
+/// them.
+///
+/// These are the values reported back when the application queries a color
+/// (with `\e]4;<n>;?`, `\e]10;?`, etc) that it hasn't set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DefaultColors {
+    /// The default foreground color, as (red, green, blue).
+    pub foreground: (u8, u8, u8),
+
+    /// The default background color, as (red, green, blue).
+    pub background: (u8, u8, u8),
+
+    /// The default text cursor color, as (red, green, blue).
+    pub cursor: (u8, u8, u8),
+
+    /// The sixteen ANSI colors, as (red, green, blue). The remaining
+    /// indexed colors are the standard xterm 6x6x6 color cube and
+    /// grayscale ramp.
+    pub ansi: [(u8, u8, u8); 16],
+}
+
+impl Default for DefaultColors {
+    fn default() -> Self {
+        Self {
+            foreground: (229, 229, 229),
+            background: (0, 0, 0),
+            cursor: (229, 229, 229),
+            ansi: [
+                (0, 0, 0),
+                (205, 0, 0),
+                (0, 205, 0),
+                (205, 205, 0),
+                (0, 0, 238),
+                (205, 0, 205),
+                (0, 205, 205),
+                (229, 229, 229),
+                (127, 127, 127),
+                (255, 0, 0),
+                (0, 255, 0),
+                (255, 255, 0),
+                (92, 92, 255),
+                (255, 0, 255),
+                (0, 255, 255),
+                (255, 255, 255),
+            ],
+        }
+    }
+}
+
+impl DefaultColors {
+    pub(crate) fn indexed(&self, idx: u8) -> (u8, u8, u8) {
+        const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+        match idx {
+            0..=15 => self.ansi[usize::from(idx)],
+            16..=231 => {
+                let idx = usize::from(idx - 16);
+                (
+                    CUBE_LEVELS[idx / 36],
+                    CUBE_LEVELS[(idx / 6) % 6],
+                    CUBE_LEVELS[idx % 6],
+                )
+            }
+            232..=255 => {
+                let level = 8 + (idx - 232) * 10;
+                (level, level, level)
+            }
+        }
+    }
+
+    pub(crate) fn dynamic(&self, color: DynamicColor) -> (u8, u8, u8) {
+        match color {
+            DynamicColor::Foreground => self.foreground,
+            DynamicColor::Background => self.background,
+            DynamicColor::Cursor => self.cursor,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DynamicColor {
+    Foreground,
+    Background,
+    Cursor,
+}
+
+impl DynamicColor {
+    const ALL: [Self; 3] = [Self::Foreground, Self::Background, Self::Cursor];
+
+    // the osc number used to set this color (10, 11 or 12). resetting uses
+    // the same number plus 100.
+    pub fn from_osc(ps: u16) -> Option<Self> {
+        match ps {
+            10 => Some(Self::Foreground),
+            11 => Some(Self::Background),
+            12 => Some(Self::Cursor),
+            _ => None,
+        }
+    }
+
+    pub fn osc(self) -> u16 {
+        match self {
+            Self::Foreground => 10,
+            Self::Background => 11,
+            Self::Cursor => 12,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Foreground => 0,
+            Self::Background => 1,
+            Self::Cursor => 2,
+        }
+    }
+
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Self::Foreground => Some(Self::Background),
+            Self::Background => Some(Self::Cursor),
+            Self::Cursor => None,
+        }
+    }
+}
+
+// only colors which have been explicitly set by the application are stored
+// here, so that we can tell the difference between a color being reset and a
+// color being set to the same value as the default
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Palette {
+    indexed: [Option<(u8, u8, u8)>; 256],
+    dynamic: [Option<(u8, u8, u8)>; 3],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            indexed: [None; 256],
+            dynamic: [None; 3],
+        }
+    }
+}
+
+impl Palette {
+    pub fn indexed(&self, idx: u8) -> Option<(u8, u8, u8)> {
+        self.indexed[usize::from(idx)]
+    }
+
+    pub fn set_indexed(&mut self, idx: u8, rgb: Option<(u8, u8, u8)>) {
+        self.indexed[usize::from(idx)] = rgb;
+    }
+
+    pub fn reset_all_indexed(&mut self) {
+        self.indexed = [None; 256];
+    }
+
+    pub fn dynamic(&self, color: DynamicColor) -> Option<(u8, u8, u8)> {
+        self.dynamic[color.index()]
+    }
+
+    pub fn set_dynamic(
+        &mut self,
+        color: DynamicColor,
+        rgb: Option<(u8, u8, u8)>,
+    ) {
+        self.dynamic[color.index()] = rgb;
+    }
+
+    pub fn write_escape_code_diff(
+        &self,
+        contents: &mut Vec<u8>,
+        other: &Self,
+    ) {
+        for (idx, (rgb, other_rgb)) in
+            self.indexed.iter().zip(other.indexed.iter()).enumerate()
+        {
+            if rgb != other_rgb {
+                // indexed always has exactly 256 entries
+                let idx = idx.try_into().unwrap();
+                if let Some(rgb) = rgb {
+                    crate::term::SetPaletteColor::new(idx, *rgb)
+                        .write_buf(contents);
+                } else {
+                    crate::term::ResetPaletteColor::new(idx)
+                        .write_buf(contents);
+                }
+            }
+        }
+        for color in DynamicColor::ALL {
+            let rgb = self.dynamic(color);
+            if rgb != other.dynamic(color) {
+                if let Some(rgb) = rgb {
+                    crate::term::SetDynamicColor::new(color.osc(), rgb)
+                        .write_buf(contents);
+                } else {
+                    crate::term::ResetDynamicColor::new(color.osc())
+                        .write_buf(contents);
+                }
+            }
+        }
+    }
+}
+
+// parses the color specifications understood by XParseColor, other than
+// color names: rgb:<r>/<g>/<b> with one to four hex digits per component,
+// and #<rgb> with one to four hex digits per component.
+pub fn parse_color(spec: &[u8]) -> Option<(u8, u8, u8)> {
+    fn hex(digits: &[u8]) -> Option<u16> {
+        if digits.is_empty()
+            || digits.len() > 4
+            || !digits.iter().all(u8::is_ascii_hexdigit)
+        {
+            return None;
+        }
+        u16::from_str_radix(std::str::from_utf8(digits).ok()?, 16).ok()
+    }
+
+    if let Some(rgb) = spec.strip_prefix(b"rgb:") {
+        let mut components = rgb.split(|&c| c == b'/').map(|digits| {
+            // scale to the full range, so that (for instance) f and ffff
+            // both mean 255
+            let value = u32::from(hex(digits)?);
+            let max = (1u32 << (4 * digits.len())) - 1;
+            u8::try_from(value * 255 / max).ok()
+        });
+        let r = components.next()??;
+        let g = components.next()??;
+        let b = components.next()??;
+        if components.next().is_some() {
+            return None;
+        }
+        Some((r, g, b))
+    } else if let Some(rgb) = spec.strip_prefix(b"#") {
+        if rgb.is_empty() || rgb.len() % 3 != 0 {
+            return None;
+        }
+        let len = rgb.len() / 3;
+        let mut components = rgb.chunks(len).map(|digits| {
+            // the digits given are the most significant bits of the value
+            let value = hex(digits)? << (4 * (4 - digits.len()));
+            u8::try_from(value >> 8).ok()
+        });
+        let r = components.next()??;
+        let g = components.next()??;
+        let b = components.next()??;
+        Some((r, g, b))
+    } else {
+        None
+    }
+}
@@ -1,7 +1,9 @@
 /// A parser for terminal output which produces an in-memory representation of
 /// the terminal contents.
 pub struct Parser<CB: crate::callbacks::Callbacks = ()> {
-    parser: vte::Parser,
+    vte: vte::Parser,
+    apc: crate::apc::Scanner,
+    iterm: crate::iterm::Scanner,
     screen: crate::perform::WrappedScreen<CB>,
 }
 
@@ -11,7 +13,9 @@ impl Parser {
     #[must_use]
     pub fn new(rows: u16, cols: u16, scrollback_len: usize) -> Self {
         Self {
-            parser: vte::Parser::new(),
+            vte: vte::Parser::new(),
+            apc: crate::apc::Scanner::new(),
+            iterm: crate::iterm::Scanner::new(),
             screen: crate::perform::WrappedScreen::new(
                 rows,
                 cols,
@@ -33,7 +37,9 @@ impl<CB: crate::callbacks::Callbacks> Parser<CB> {
         callbacks: CB,
     ) -> Self {
         Self {
-            parser: vte::Parser::new(),
+            vte: vte::Parser::new(),
+            apc: crate::apc::Scanner::new(),
+            iterm: crate::iterm::Scanner::new(),
             screen: crate::perform::WrappedScreen::new_with_callbacks(
                 rows,
                 cols,
@@ -46,7 +52,34 @@ impl<CB: crate::callbacks::Callbacks> Parser<CB> {
     /// Processes the contents of the given byte string, and updates the
     /// in-memory terminal state.
     pub fn process(&mut self, bytes: &[u8]) {
-        self.parser.advance(&mut self.screen, bytes);
+        // the contents of iterm2 inline images are kept away from vte, which
+        // would otherwise buffer them without any limit
+        let mut start = 0;
+        while start < bytes.len() {
+            let (len, skip, command) = self.iterm.scan(&bytes[start..]);
+            self.advance(&bytes[start..start + len]);
+            if let Some(command) = command {
+                self.screen.osc1337_dispatch(&command);
+            }
+            start += len + skip;
+        }
+        self.screen.screen.synchronized_bytes(bytes.len());
+    }
+
+    fn advance(&mut self, bytes: &[u8]) {
+        // the bytes making up an application program command are still
+        // passed through to vte (which ignores them), so that it stays in
+        // sync with where escape sequences start and end
+        let mut start = 0;
+        let mut scanned = 0;
+        while let Some((end, apc)) = self.apc.scan(&bytes[scanned..]) {
+            let end = scanned + end;
+            self.vte.advance(&mut self.screen, &bytes[start..end]);
+            self.screen.apc_dispatch(&apc);
+            start = end;
+            scanned = end + 1;
+        }
+        self.vte.advance(&mut self.screen, &bytes[start..]);
     }
 
     /// Returns a reference to a [`Screen`](crate::Screen) object containing
@@ -0,0 +1,70 @@
+/// What to do with control characters in pasted text. Tabs and newlines are
+/// always kept, and the bracketed paste markers are always removed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum PasteControls {
+    /// Control characters are removed.
+    #[default]
+    Strip,
+
+    /// Control characters are replaced with visible symbols (from the
+    /// Control Pictures block for C0 controls and delete, and U+FFFD for C1
+    /// controls), so that they can't be interpreted by the application.
+    Replace,
+
+    /// Control characters are sent to the application unchanged.
+    Allow,
+}
+
+pub fn encode(
+    data: &[u8],
+    bracketed: bool,
+    controls: PasteControls,
+) -> Vec<u8> {
+    // the end marker would let the pasted text end the paste early and have
+    // the rest of it interpreted as typed input, so the markers are always
+    // removed. the end of the text is checked after each character is
+    // copied, which also catches markers that are put together by removing
+    // another one.
+    let mut text = String::with_capacity(data.len());
+    for c in String::from_utf8_lossy(data).chars() {
+        text.push(c);
+        if text.ends_with("\x1b[200~") || text.ends_with("\x1b[201~") {
+            text.truncate(text.len() - 6);
+        }
+    }
+
+    let mut bytes = vec![];
+    if bracketed {
+        bytes.extend_from_slice(b"\x1b[200~");
+    }
+    let mut buf = [0; 4];
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            // newlines are sent as carriage returns, like the enter key
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                '\r'
+            }
+            '\n' => '\r',
+            '\t' => '\t',
+            c if c.is_control() => match controls {
+                PasteControls::Strip => continue,
+                PasteControls::Replace => match c {
+                    '\x7f' => '\u{2421}',
+                    '\0'..='\x1f' => {
+                        char::from_u32(0x2400 + u32::from(c)).unwrap()
+                    }
+                    _ => '\u{fffd}',
+                },
+                PasteControls::Allow => c,
+            },
+            c => c,
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    }
+    if bracketed {
+        bytes.extend_from_slice(b"\x1b[201~");
+    }
+    bytes
+}
@@ -5,6 +5,13 @@ const CLIPBOARD_SELECTOR: &[u8] = b"cpqs01234567";
 pub struct WrappedScreen<CB: crate::callbacks::Callbacks = ()> {
     pub screen: crate::screen::Screen,
     pub callbacks: CB,
+    dcs: Option<Dcs>,
+}
+
+// the state of the device control string currently being received, if it
+// is one that we handle
+enum Dcs {
+    Sixel(crate::sixel::Decoder),
 }
 
 impl WrappedScreen<()> {
@@ -26,10 +33,35 @@ impl<CB: crate::callbacks::Callbacks> WrappedScreen<CB> {
                 scrollback_len,
             ),
             callbacks,
+            dcs: None,
         }
     }
 }
 
+impl<CB: crate::callbacks::Callbacks> WrappedScreen<CB> {
+    // vte doesn't handle application program commands, so these are
+    // dispatched separately (see Parser::process)
+    pub fn apc_dispatch(&mut self, data: &[u8]) {
+        if let [b'G', data @ ..] = data {
+            if let Some(reply) = self.screen.kitty_graphics(data) {
+                self.callbacks.reply(&mut self.screen, &reply);
+            }
+        }
+    }
+
+    // OSC 1337, which is dispatched separately to avoid vte buffering
+    // arbitrarily large inline images (see Parser::process)
+    pub fn osc1337_dispatch(&mut self, data: &[u8]) {
+        let callbacks = &mut self.callbacks;
+        self.screen.osc1337(data, |screen| {
+            let params: Vec<_> = std::iter::once(&b"1337"[..])
+                .chain(data.split(|&c| c == b';'))
+                .collect();
+            callbacks.unhandled_osc(screen, &params);
+        });
+    }
+}
+
 impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
     fn print(&mut self, c: char) {
         if c == '\u{fffd}' || ('\u{80}'..'\u{a0}').contains(&c) {
@@ -48,15 +80,25 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
             11 => self.screen.vt(),
             12 => self.screen.ff(),
             13 => self.screen.cr(),
-            // we don't implement shift in/out alternate character sets, but
-            // it shouldn't count as an "error"
-            14 | 15 => {}
+            14 => self.screen.so(),
+            15 => self.screen.si(),
             _ => self.callbacks.unhandled_control(&mut self.screen, b),
         }
     }
 
     fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, b: u8) {
-        if let Some(i) = intermediates.first() {
+        let designate = match intermediates {
+            [b'('] => Some(0),
+            [b')'] => Some(1),
+            [b'*'] => Some(2),
+            [b'+'] => Some(3),
+            _ => None,
+        };
+        if let (Some(g), Some(charset)) =
+            (designate, crate::Charset::from_final(b))
+        {
+            self.screen.scs(g, charset);
+        } else if let Some(i) = intermediates.first() {
             self.callbacks.unhandled_escape(
                 &mut self.screen,
                 Some(*i),
@@ -69,9 +111,14 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                 b'8' => self.screen.decrc(),
                 b'=' => self.screen.deckpam(),
                 b'>' => self.screen.deckpnm(),
+                b'H' => self.screen.hts(),
                 b'M' => self.screen.ri(),
+                b'N' => self.screen.ss2(),
+                b'O' => self.screen.ss3(),
                 b'c' => self.screen.ris(),
                 b'g' => self.callbacks.visual_bell(&mut self.screen),
+                b'n' => self.screen.ls2(),
+                b'o' => self.screen.ls3(),
                 _ => {
                     self.callbacks.unhandled_escape(
                         &mut self.screen,
@@ -111,6 +158,7 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                 'F' => self.screen.cpl(canonicalize_params_1(params, 1)),
                 'G' => self.screen.cha(canonicalize_params_1(params, 1)),
                 'H' => self.screen.cup(canonicalize_params_2(params, 1, 1)),
+                'I' => self.screen.cht(canonicalize_params_1(params, 1)),
                 'J' => self
                     .screen
                     .ed(canonicalize_params_1(params, 0), unhandled),
@@ -121,14 +169,47 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                 'M' => self.screen.dl(canonicalize_params_1(params, 1)),
                 'P' => self.screen.dch(canonicalize_params_1(params, 1)),
                 'S' => self.screen.su(canonicalize_params_1(params, 1)),
+                'T' if params.len() == 5 => self.screen.xthimouse(params),
                 'T' => self.screen.sd(canonicalize_params_1(params, 1)),
                 'X' => self.screen.ech(canonicalize_params_1(params, 1)),
+                'Z' => self.screen.cbt(canonicalize_params_1(params, 1)),
+                'c' => {
+                    if let Some(reply) = self
+                        .screen
+                        .da1(canonicalize_params_1(params, 0), unhandled)
+                    {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
                 'd' => self.screen.vpa(canonicalize_params_1(params, 1)),
+                'g' => self
+                    .screen
+                    .tbc(canonicalize_params_1(params, 0), unhandled),
+                'h' => self.screen.sm(params, unhandled),
+                'l' => self.screen.rm(params, unhandled),
                 'm' => self.screen.sgr(params, unhandled),
+                'n' => {
+                    if let Some(reply) = self
+                        .screen
+                        .dsr(canonicalize_params_1(params, 0), unhandled)
+                    {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
                 'r' => self.screen.decstbm(canonicalize_params_decstbm(
                     params,
                     self.screen.grid().size(),
                 )),
+                's' => {
+                    if self.screen.left_right_margin_mode() {
+                        self.screen.decslrm(canonicalize_params_decslrm(
+                            params,
+                            self.screen.grid().size(),
+                        ));
+                    } else {
+                        self.screen.scosc();
+                    }
+                }
                 't' => {
                     let mut params_iter = params.iter();
                     let op =
@@ -154,6 +235,7 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                         );
                     }
                 }
+                'u' => self.screen.scorc(),
                 _ => {
                     self.callbacks.unhandled_csi(
                         &mut self.screen,
@@ -173,6 +255,24 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                     .decsel(canonicalize_params_1(params, 0), unhandled),
                 'h' => self.screen.decset(params, unhandled),
                 'l' => self.screen.decrst(params, unhandled),
+                'n' => {
+                    if let Some(reply) = self
+                        .screen
+                        .decdsr(canonicalize_params_1(params, 0), unhandled)
+                    {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
+                'p' if intermediates.get(1) == Some(&b'$') => {
+                    let reply = self
+                        .screen
+                        .decrqm_private(canonicalize_params_1(params, 0));
+                    self.callbacks.reply(&mut self.screen, &reply);
+                }
+                'u' => {
+                    let reply = self.screen.query_keyboard_flags();
+                    self.callbacks.reply(&mut self.screen, &reply);
+                }
                 _ => {
                     self.callbacks.unhandled_csi(
                         &mut self.screen,
@@ -183,6 +283,121 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
                     );
                 }
             },
+            Some(b' ') => match c {
+                'q' => self
+                    .screen
+                    .decscusr(canonicalize_params_1(params, 0), unhandled),
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b' '),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
+            Some(b'$') => match c {
+                'p' => {
+                    let reply =
+                        self.screen.decrqm(canonicalize_params_1(params, 0));
+                    self.callbacks.reply(&mut self.screen, &reply);
+                }
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b'$'),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
+            Some(b'>') => match c {
+                'c' => {
+                    if let Some(reply) = self
+                        .screen
+                        .da2(canonicalize_params_1(params, 0), unhandled)
+                    {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
+                'm' => self.screen.xtmodkeys(params, unhandled),
+                'u' => self
+                    .screen
+                    .push_keyboard_flags(canonicalize_params_1(params, 0)),
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b'>'),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
+            Some(b'<') => match c {
+                'u' => self
+                    .screen
+                    .pop_keyboard_flags(canonicalize_params_1(params, 1)),
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b'<'),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
+            Some(b'=') => match c {
+                'c' => {
+                    if let Some(reply) = self
+                        .screen
+                        .da3(canonicalize_params_1(params, 0), unhandled)
+                    {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
+                'u' => self.screen.set_keyboard_flags(
+                    canonicalize_params_2(params, 0, 1),
+                    unhandled,
+                ),
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b'='),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
+            Some(b'\'') => match c {
+                'w' => {
+                    if let Some(reply) = self.screen.decefr(params) {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
+                'z' => self
+                    .screen
+                    .decelr(canonicalize_params_2(params, 0, 0), unhandled),
+                '{' => self.screen.decsle(params, unhandled),
+                '|' => {
+                    if let Some(reply) = self.screen.decrqlp() {
+                        self.callbacks.reply(&mut self.screen, &reply);
+                    }
+                }
+                _ => {
+                    self.callbacks.unhandled_csi(
+                        &mut self.screen,
+                        Some(b'\''),
+                        intermediates.get(1).copied(),
+                        &params.iter().collect::<Vec<_>>(),
+                        c,
+                    );
+                }
+            },
             Some(i) => {
                 self.callbacks.unhandled_csi(
                     &mut self.screen,
@@ -195,7 +410,10 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
         }
     }
 
-    fn osc_dispatch(&mut self, params: &[&[u8]], _bel_terminated: bool) {
+    fn osc_dispatch(&mut self, params: &[&[u8]], bel_terminated: bool) {
+        let unhandled = |screen: &mut crate::screen::Screen| {
+            self.callbacks.unhandled_osc(screen, params);
+        };
         match params {
             [b"0", s] => {
                 self.callbacks.set_window_icon_name(&mut self.screen, s);
@@ -207,6 +425,67 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
             [b"2", s] => {
                 self.callbacks.set_window_title(&mut self.screen, s);
             }
+            [b"8", link_params, uri @ ..] if !uri.is_empty() => {
+                // the uri itself is allowed to contain semicolons
+                let uri = uri.join(&b';');
+                self.screen.osc8(link_params, &uri, unhandled);
+            }
+            [b"4", rest @ ..] if !rest.is_empty() => {
+                if let Some(reply) =
+                    self.screen.osc4(rest, bel_terminated, unhandled)
+                {
+                    self.callbacks.reply(&mut self.screen, &reply);
+                }
+            }
+            [b"7", uri @ ..] if !uri.is_empty() => {
+                self.screen.osc7(&uri.join(&b';'), unhandled);
+            }
+            // ConEmu uses OSC 9 with a numeric first parameter for a
+            // variety of other purposes (progress reporting, etc), which
+            // aren't notifications
+            [b"9", first, rest @ ..]
+                if rest.is_empty()
+                    || !first.iter().all(u8::is_ascii_digit) =>
+            {
+                // the message itself is allowed to contain semicolons
+                let body = params[1..].join(&b';');
+                let notification = crate::Notification::new(b"", &body);
+                self.callbacks.notify(&mut self.screen, &notification);
+            }
+            [ps @ (b"10" | b"11" | b"12"), rest @ ..] if !rest.is_empty() => {
+                let ps = match *ps {
+                    b"10" => 10,
+                    b"11" => 11,
+                    _ => 12,
+                };
+                if let Some(reply) =
+                    self.screen.osc10(ps, rest, bel_terminated, unhandled)
+                {
+                    self.callbacks.reply(&mut self.screen, &reply);
+                }
+            }
+            [b"99", metadata, payload @ ..] => {
+                if let Some(notification) = self.screen.osc99(
+                    metadata,
+                    &payload.join(&b';'),
+                    unhandled,
+                ) {
+                    self.callbacks.notify(&mut self.screen, &notification);
+                }
+            }
+            [b"104", rest @ ..] => self.screen.osc104(rest, unhandled),
+            [b"110"] => self.screen.osc110(110),
+            [b"111"] => self.screen.osc110(111),
+            [b"112"] => self.screen.osc110(112),
+            [b"133", rest @ ..] => self.screen.osc133(rest, unhandled),
+            [b"777", b"notify", title, body @ ..] => {
+                let notification =
+                    crate::Notification::new(title, &body.join(&b';'));
+                self.callbacks.notify(&mut self.screen, &notification);
+            }
+            // the command itself is taken out of the byte stream before it
+            // gets to vte (see Parser::process)
+            [b"1337", b""] => {}
             [b"52", ty, data] => {
                 match (
                     ty.iter().all(|c| CLIPBOARD_SELECTOR.contains(c)),
@@ -236,6 +515,40 @@ impl<CB: crate::callbacks::Callbacks> vte::Perform for WrappedScreen<CB> {
             }
         }
     }
+
+    fn hook(
+        &mut self,
+        params: &vte::Params,
+        intermediates: &[u8],
+        _ignore: bool,
+        c: char,
+    ) {
+        self.dcs = match (intermediates, c) {
+            ([], 'q') => Some(Dcs::Sixel(crate::sixel::Decoder::new(
+                params,
+                self.screen.background_color(),
+            ))),
+            _ => None,
+        };
+    }
+
+    fn put(&mut self, b: u8) {
+        match &mut self.dcs {
+            Some(Dcs::Sixel(decoder)) => decoder.put(b),
+            None => {}
+        }
+    }
+
+    fn unhook(&mut self) {
+        match self.dcs.take() {
+            Some(Dcs::Sixel(decoder)) => {
+                if let Some(image) = decoder.finish() {
+                    self.screen.sixel(image);
+                }
+            }
+            None => {}
+        }
+    }
 }
 
 fn canonicalize_params_1(params: &vte::Params, default: u16) -> u16 {
@@ -275,3 +588,17 @@ fn canonicalize_params_decstbm(
 
     (top, bottom)
 }
+
+fn canonicalize_params_decslrm(
+    params: &vte::Params,
+    size: crate::grid::Size,
+) -> (u16, u16) {
+    let mut iter = params.iter();
+    let left = iter.next().map_or(0, |x| *x.first().unwrap_or(&0));
+    let left = if left == 0 { 1 } else { left };
+
+    let right = iter.next().map_or(0, |x| *x.first().unwrap_or(&0));
+    let right = if right == 0 { size.cols } else { right };
+
+    (left, right)
+}
@@ -0,0 +1,167 @@
+// the semantic prompt markers from OSC 133, recorded on the row where they
+// were received
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Marker {
+    // OSC 133 ; A
+    PromptStart,
+    // OSC 133 ; B
+    CommandStart,
+    // OSC 133 ; C
+    OutputStart,
+    // OSC 133 ; D [; exit code]
+    CommandFinished(Option<i32>),
+}
+
+impl Marker {
+    pub fn from_params(params: &[&[u8]]) -> Option<Self> {
+        match params {
+            [b"A", ..] => Some(Self::PromptStart),
+            [b"B", ..] => Some(Self::CommandStart),
+            [b"C", ..] => Some(Self::OutputStart),
+            [b"D"] => Some(Self::CommandFinished(None)),
+            [b"D", exit_code, ..] => Some(Self::CommandFinished(
+                std::str::from_utf8(exit_code)
+                    .ok()
+                    .and_then(|exit_code| exit_code.parse().ok()),
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// A command run from a shell prompt, as described by the semantic prompt
+/// markers (OSC 133) that the shell emitted.
+///
+/// Row numbers are indices into the full terminal buffer (scrollback
+/// followed by the visible screen), as returned by
+/// [`Screen::rows_full`](crate::Screen::rows_full). Rows which are shared
+/// between two parts of the command (for instance, a prompt followed by the
+/// command typed on the same line) are included in both ranges.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Command {
+    prompt: std::ops::Range<usize>,
+    input: Option<std::ops::Range<usize>>,
+    output: Option<std::ops::Range<usize>>,
+    exit_code: Option<i32>,
+    finished: bool,
+}
+
+impl Command {
+    /// Returns the rows containing the prompt.
+    #[must_use]
+    pub fn prompt_rows(&self) -> std::ops::Range<usize> {
+        self.prompt.clone()
+    }
+
+    /// Returns the rows containing the command line that was typed at the
+    /// prompt, if the shell has reported where it starts.
+    #[must_use]
+    pub fn input_rows(&self) -> Option<std::ops::Range<usize>> {
+        self.input.clone()
+    }
+
+    /// Returns the rows containing the output of the command, if the
+    /// command has started running.
+    #[must_use]
+    pub fn output_rows(&self) -> Option<std::ops::Range<usize>> {
+        self.output.clone()
+    }
+
+    /// Returns the exit status reported for the command, if it has finished
+    /// and the shell reported one.
+    #[must_use]
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Returns whether the shell has reported that the command finished.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+// a part of the command starts at the row of one marker, and continues up
+// to the row of the next marker. if the next marker isn't at the start of
+// its row, that row is included too.
+fn rows_between(
+    start: usize,
+    (end_row, end_col): (usize, u16),
+) -> std::ops::Range<usize> {
+    let end = if end_col == 0 { end_row } else { end_row + 1 };
+    start..end.max(start + 1)
+}
+
+#[derive(Default)]
+struct PartialCommand {
+    prompt: usize,
+    input: Option<(usize, u16)>,
+    output: Option<(usize, u16)>,
+}
+
+impl PartialCommand {
+    fn finish(
+        &self,
+        end: (usize, u16),
+        exit_code: Option<i32>,
+        finished: bool,
+    ) -> Command {
+        let input_end = self.output.unwrap_or(end);
+        let prompt_end = self.input.unwrap_or(input_end);
+        Command {
+            prompt: rows_between(self.prompt, prompt_end),
+            input: self.input.map(|(row, _)| rows_between(row, input_end)),
+            output: self.output.map(|(row, _)| rows_between(row, end)),
+            exit_code,
+            finished,
+        }
+    }
+}
+
+// end is the position just past the end of the buffer, which is where any
+// unfinished command is considered to end
+pub fn commands<'a>(
+    rows: impl Iterator<Item = &'a crate::row::Row>,
+    end: (usize, u16),
+) -> Vec<Command> {
+    let mut commands = vec![];
+    let mut current: Option<PartialCommand> = None;
+    for (i, row) in rows.enumerate() {
+        for &(col, marker) in row.markers() {
+            match marker {
+                Marker::PromptStart => {
+                    if let Some(command) = current.take() {
+                        commands.push(command.finish((i, col), None, false));
+                    }
+                    current = Some(PartialCommand {
+                        prompt: i,
+                        ..Default::default()
+                    });
+                }
+                Marker::CommandStart => {
+                    if let Some(command) = &mut current {
+                        command.input = Some((i, col));
+                    }
+                }
+                Marker::OutputStart => {
+                    if let Some(command) = &mut current {
+                        command.output = Some((i, col));
+                    }
+                }
+                Marker::CommandFinished(exit_code) => {
+                    if let Some(command) = current.take() {
+                        commands.push(command.finish(
+                            (i, col),
+                            exit_code,
+                            true,
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if let Some(command) = current {
+        commands.push(command.finish(end, None, false));
+    }
+    commands
+}
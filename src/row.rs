@@ -4,6 +4,8 @@ use crate::term::BufWrite as _;
 pub struct Row {
     cells: Vec<crate::Cell>,
     wrapped: bool,
+    markers: Vec<(u16, crate::prompt::Marker)>,
+    images: Vec<crate::image::Fragment>,
 }
 
 impl Row {
@@ -11,6 +13,8 @@ impl Row {
         Self {
             cells: vec![crate::Cell::new(); usize::from(cols)],
             wrapped: false,
+            markers: vec![],
+            images: vec![],
         }
     }
 
@@ -27,12 +31,19 @@ impl Row {
             cell.clear(attrs);
         }
         self.wrapped = false;
+        self.images.clear();
     }
 
     fn cells(&self) -> impl Iterator<Item = &crate::Cell> {
         self.cells.iter()
     }
 
+    pub fn hyperlinks(
+        &self,
+    ) -> impl Iterator<Item = std::num::NonZeroU16> + '_ {
+        self.cells().filter_map(|cell| cell.attrs().hyperlink)
+    }
+
     pub fn get(&self, col: u16) -> Option<&crate::Cell> {
         self.cells.get(usize::from(col))
     }
@@ -44,18 +55,90 @@ impl Row {
     pub fn insert(&mut self, i: u16, cell: crate::Cell) {
         self.cells.insert(usize::from(i), cell);
         self.wrapped = false;
+        self.clip_images(i, u16::MAX);
     }
 
     pub fn remove(&mut self, i: u16) {
         self.clear_wide(i);
         self.cells.remove(usize::from(i));
         self.wrapped = false;
+        self.clip_images(i, u16::MAX);
+    }
+
+    // inserts a cell at i, shifting the cells up to and including right over
+    // by one and discarding the cell that gets pushed past right, for
+    // inserting within the left and right margins
+    pub fn insert_within(&mut self, i: u16, right: u16, cell: crate::Cell) {
+        self.clear_wide(right);
+        if self.cells[usize::from(i)].is_wide_continuation() {
+            self.clear_wide(i);
+            let cell = &mut self.cells[usize::from(i)];
+            cell.clear(*cell.attrs());
+        }
+        self.cells.remove(usize::from(right));
+        self.cells.insert(usize::from(i), cell);
+        self.wrapped = false;
+        self.clip_images(i, right + 1);
+    }
+
+    // removes the cell at i, shifting the cells up to and including right
+    // back by one and filling in the gap at right with the given cell, for
+    // deleting within the left and right margins
+    pub fn remove_within(&mut self, i: u16, right: u16, cell: crate::Cell) {
+        self.clear_wide(i);
+        if self.cells[usize::from(right)].is_wide() {
+            self.clear_wide(right);
+            let cell = &mut self.cells[usize::from(right)];
+            cell.clear(*cell.attrs());
+        }
+        self.cells.remove(usize::from(i));
+        self.cells.insert(usize::from(right), cell);
+        self.wrapped = false;
+        self.clip_images(i, right + 1);
+    }
+
+    // replaces the cells from left to right (inclusive) with the cells in the
+    // same columns of another row, for scrolling within the left and right
+    // margins
+    pub fn copy_from(&mut self, other: &Self, left: u16, right: u16) {
+        self.clear_wide(left);
+        self.clear_wide(right);
+        self.clip_images(left, right + 1);
+        self.images.extend(
+            other
+                .images
+                .iter()
+                .filter_map(|fragment| fragment.intersect(left, right + 1)),
+        );
+        self.remove_markers(left, right);
+        self.markers.extend(
+            other
+                .markers
+                .iter()
+                .filter(|(col, _)| (left..=right).contains(col))
+                .copied(),
+        );
+        let left = usize::from(left);
+        let right = usize::from(right);
+        self.cells[left..=right].clone_from_slice(&other.cells[left..=right]);
+        // wide characters which straddled the margins in the other row can't
+        // be copied over
+        let first = &mut self.cells[left];
+        if first.is_wide_continuation() {
+            first.clear(*first.attrs());
+        }
+        let last = &mut self.cells[right];
+        if last.is_wide() {
+            last.clear(*last.attrs());
+        }
+        self.wrapped = false;
     }
 
     pub fn erase(&mut self, i: u16, attrs: crate::attrs::Attrs) {
         let wide = self.cells[usize::from(i)].is_wide();
         self.clear_wide(i);
         self.cells[usize::from(i)].clear(attrs);
+        self.clip_images(i, i + 1);
         if i == self.cols() - if wide { 2 } else { 1 } {
             self.wrapped = false;
         }
@@ -64,6 +147,7 @@ impl Row {
     pub fn truncate(&mut self, len: u16) {
         self.cells.truncate(usize::from(len));
         self.wrapped = false;
+        self.crop_images(len);
         let last_cell = &mut self.cells[usize::from(len) - 1];
         if last_cell.is_wide() {
             last_cell.clear(*last_cell.attrs());
@@ -73,6 +157,7 @@ impl Row {
     pub fn resize(&mut self, len: u16, cell: crate::Cell) {
         self.cells.resize(usize::from(len), cell);
         self.wrapped = false;
+        self.crop_images(len);
     }
 
     pub fn wrap(&mut self, wrap: bool) {
@@ -83,6 +168,77 @@ impl Row {
         self.wrapped
     }
 
+    pub fn add_marker(&mut self, col: u16, marker: crate::prompt::Marker) {
+        self.markers.push((col, marker));
+    }
+
+    pub fn markers(&self) -> &[(u16, crate::prompt::Marker)] {
+        &self.markers
+    }
+
+    // removes the markers from left to right (inclusive), for when the cells
+    // between the left and right margins are scrolled away
+    pub fn remove_markers(&mut self, left: u16, right: u16) {
+        self.markers
+            .retain(|(col, _)| !(left..=right).contains(col));
+    }
+
+    pub fn images(&self) -> &[crate::image::Fragment] {
+        &self.images
+    }
+
+    // sixel images replace any parts of other sixel images that they are
+    // drawn over
+    pub fn add_image(&mut self, fragment: crate::image::Fragment) {
+        if fragment.overwritable() {
+            self.clip_images(
+                fragment.col(),
+                fragment.col() + fragment.cols(),
+            );
+        }
+        self.images.push(fragment);
+    }
+
+    // removes the parts of any sixel images which cover the columns from
+    // start (inclusive) to end (exclusive), for when those cells are
+    // overwritten
+    pub fn clip_images(&mut self, start: u16, end: u16) {
+        if self.images.is_empty() {
+            return;
+        }
+        self.images = self
+            .images
+            .iter()
+            .flat_map(|fragment| {
+                if fragment.overwritable() {
+                    [
+                        fragment.intersect(0, start),
+                        fragment.intersect(end, u16::MAX),
+                    ]
+                } else {
+                    [Some(fragment.clone()), None]
+                }
+            })
+            .flatten()
+            .collect();
+    }
+
+    pub fn retain_images(
+        &mut self,
+        f: impl FnMut(&crate::image::Fragment) -> bool,
+    ) {
+        self.images.retain(f);
+    }
+
+    // removes the parts of all images which are past the end of the row
+    fn crop_images(&mut self, len: u16) {
+        self.images = self
+            .images
+            .iter()
+            .filter_map(|fragment| fragment.intersect(0, len))
+            .collect();
+    }
+
     pub fn clear_wide(&mut self, col: u16) {
         let cell = &self.cells[usize::from(col)];
         let other = if cell.is_wide() {
@@ -143,6 +299,7 @@ impl Row {
         wrapping: bool,
         prev_pos: Option<crate::grid::Pos>,
         prev_attrs: Option<crate::attrs::Attrs>,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> (crate::grid::Pos, crate::attrs::Attrs) {
         let mut prev_was_wide = false;
         let default_cell = crate::Cell::new();
@@ -163,7 +320,11 @@ impl Row {
         if wrapping && first_cell == &default_cell {
             let default_attrs = default_cell.attrs();
             if &prev_attrs != default_attrs {
-                default_attrs.write_escape_code_diff(contents, &prev_attrs);
+                default_attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *default_attrs;
             }
             contents.push(b' ');
@@ -211,7 +372,11 @@ impl Row {
                     }
                     prev_pos = new_pos;
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
                     crate::term::EraseChar::new(pos.col - prev_col)
@@ -237,7 +402,11 @@ impl Row {
                     }
 
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
 
@@ -269,7 +438,11 @@ impl Row {
             }
             prev_pos = new_pos;
             if &prev_attrs != attrs {
-                attrs.write_escape_code_diff(contents, &prev_attrs);
+                attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *attrs;
             }
             crate::term::ClearRowForward.write_buf(contents);
@@ -289,6 +462,7 @@ impl Row {
         start: u16,
         width: u16,
         mut prev_attrs: crate::attrs::Attrs,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> crate::attrs::Attrs {
         let default_cell = crate::Cell::new();
         let mut prev_was_wide = false;
@@ -341,7 +515,11 @@ impl Row {
 
                 let attrs = cell.attrs();
                 if &prev_attrs != attrs {
-                    attrs.write_escape_code_diff(contents, &prev_attrs);
+                    attrs.write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     prev_attrs = *attrs;
                 }
                 contents.extend(cell.contents().as_bytes());
@@ -356,7 +534,11 @@ impl Row {
 
                 let attrs = cell.attrs();
                 if &prev_attrs != attrs {
-                    attrs.write_escape_code_diff(contents, &prev_attrs);
+                    attrs.write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     prev_attrs = *attrs;
                 }
                 contents.push(b' ');
@@ -381,6 +563,8 @@ impl Row {
         prev_wrapping: bool,
         mut prev_pos: crate::grid::Pos,
         mut prev_attrs: crate::attrs::Attrs,
+        hyperlinks: &crate::hyperlink::Hyperlinks,
+        prev_hyperlinks: &crate::hyperlink::Hyperlinks,
     ) -> (crate::grid::Pos, crate::attrs::Attrs) {
         let mut prev_was_wide = false;
 
@@ -388,15 +572,18 @@ impl Row {
         let prev_first_cell = &prev.cells[usize::from(start)];
         if wrapping
             && !prev_wrapping
-            && first_cell == prev_first_cell
+            && first_cell.same(hyperlinks, prev_first_cell, prev_hyperlinks)
             && prev_pos.row + 1 == row
             && prev_pos.col
                 >= self.cols() - u16::from(prev_first_cell.is_wide())
         {
             let first_cell_attrs = first_cell.attrs();
             if &prev_attrs != first_cell_attrs {
-                first_cell_attrs
-                    .write_escape_code_diff(contents, &prev_attrs);
+                first_cell_attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *first_cell_attrs;
             }
             let mut cell_contents = prev_first_cell.contents();
@@ -457,7 +644,11 @@ impl Row {
                     }
                     prev_pos = new_pos;
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
                     crate::term::EraseChar::new(pos.col - prev_col)
@@ -466,7 +657,7 @@ impl Row {
                 }
             }
 
-            if cell != prev_cell {
+            if !cell.same(hyperlinks, prev_cell, prev_hyperlinks) {
                 let attrs = cell.attrs();
                 if cell.has_contents() {
                     if pos != prev_pos {
@@ -483,7 +674,11 @@ impl Row {
                     }
 
                     if &prev_attrs != attrs {
-                        attrs.write_escape_code_diff(contents, &prev_attrs);
+                        attrs.write_escape_code_diff(
+                            contents,
+                            &prev_attrs,
+                            hyperlinks,
+                        );
                         prev_attrs = *attrs;
                     }
 
@@ -514,7 +709,11 @@ impl Row {
             }
             prev_pos = new_pos;
             if &prev_attrs != attrs {
-                attrs.write_escape_code_diff(contents, &prev_attrs);
+                attrs.write_escape_code_diff(
+                    contents,
+                    &prev_attrs,
+                    hyperlinks,
+                );
                 prev_attrs = *attrs;
             }
             crate::term::ClearRowForward.write_buf(contents);
@@ -550,7 +749,11 @@ impl Row {
             if end_cell.has_contents() {
                 let attrs = end_cell.attrs();
                 if &prev_attrs != attrs {
-                    attrs.write_escape_code_diff(contents, &prev_attrs);
+                    attrs.write_escape_code_diff(
+                        contents,
+                        &prev_attrs,
+                        hyperlinks,
+                    );
                     prev_attrs = *attrs;
                 }
                 contents.extend(end_cell.contents().as_bytes());
@@ -1,11 +1,50 @@
 use crate::term::BufWrite as _;
 use unicode_width::UnicodeWidthChar as _;
 
-const MODE_APPLICATION_KEYPAD: u8 = 0b0000_0001;
-const MODE_APPLICATION_CURSOR: u8 = 0b0000_0010;
-const MODE_HIDE_CURSOR: u8 = 0b0000_0100;
-const MODE_ALTERNATE_SCREEN: u8 = 0b0000_1000;
-const MODE_BRACKETED_PASTE: u8 = 0b0001_0000;
+const MODE_APPLICATION_KEYPAD: u16 = 0b0000_0000_0000_0001;
+const MODE_APPLICATION_CURSOR: u16 = 0b0000_0000_0000_0010;
+const MODE_HIDE_CURSOR: u16 = 0b0000_0000_0000_0100;
+const MODE_ALTERNATE_SCREEN: u16 = 0b0000_0000_0000_1000;
+const MODE_BRACKETED_PASTE: u16 = 0b0000_0000_0001_0000;
+const MODE_INSERT: u16 = 0b0000_0000_0010_0000;
+const MODE_NEWLINE: u16 = 0b0000_0000_0100_0000;
+const MODE_AUTOWRAP: u16 = 0b0000_0000_1000_0000;
+const MODE_REVERSE_WRAPAROUND: u16 = 0b0000_0001_0000_0000;
+const MODE_LEFT_RIGHT_MARGIN: u16 = 0b0000_0010_0000_0000;
+const MODE_CURSOR_BLINK: u16 = 0b0000_0100_0000_0000;
+const MODE_SIXEL_DISPLAY: u16 = 0b0000_1000_0000_0000;
+const MODE_BACKARROW_KEY: u16 = 0b0001_0000_0000_0000;
+const MODE_FOCUS_EVENTS: u16 = 0b0010_0000_0000_0000;
+
+// mode states reported by DECRPM
+const DECRPM_NOT_RECOGNIZED: u8 = 0;
+const DECRPM_SET: u8 = 1;
+const DECRPM_RESET: u8 = 2;
+const DECRPM_PERMANENTLY_SET: u8 = 3;
+const DECRPM_PERMANENTLY_RESET: u8 = 4;
+
+// synchronized updates which take longer than this (by default), or which
+// contain more than this many bytes, are ended automatically, so that a
+// misbehaving application can't freeze the display
+const SYNCHRONIZED_UPDATE_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(1);
+const SYNCHRONIZED_UPDATE_MAX_BYTES: usize = 4 * 1024 * 1024;
+
+// the state of a synchronized update (mode 2026), during which the screen as
+// it was when the update started should be displayed
+#[derive(Clone, Debug)]
+struct SynchronizedUpdate {
+    frame: Box<Screen>,
+    deadline: std::time::Instant,
+    bytes: usize,
+}
+
+impl SynchronizedUpdate {
+    fn expired(&self) -> bool {
+        std::time::Instant::now() >= self.deadline
+            || self.bytes > SYNCHRONIZED_UPDATE_MAX_BYTES
+    }
+}
 
 /// The xterm mouse handling mode currently in use.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
@@ -22,7 +61,12 @@ pub enum MouseProtocolMode {
     /// Also known as VT200 mouse mode.
     PressRelease,
 
-    // Highlight,
+    /// Mouse button events should be reported on button press and release,
+    /// and the application can respond to a press to start highlighting
+    /// text, in which case the release reports the highlighted region
+    /// instead. Also known as highlight tracking.
+    Highlight,
+
     /// Mouse button events should be reported on button press and release, as
     /// well as when the mouse moves between cells while a button is held
     /// down.
@@ -32,7 +76,64 @@ pub enum MouseProtocolMode {
     /// and mouse motion events should be reported when the mouse moves
     /// between cells regardless of whether a button is held down or not.
     AnyMotion,
-    // DecLocator,
+
+    /// The mouse position should be reported using DEC locator reports,
+    /// when requested by the application or when the configured events
+    /// happen.
+    DecLocator,
+}
+
+/// The cursor shape requested by the application.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum CursorStyle {
+    /// The cursor shape configured by the user.
+    #[default]
+    Default,
+
+    /// A blinking block cursor.
+    BlinkingBlock,
+
+    /// A steady block cursor.
+    SteadyBlock,
+
+    /// A blinking underline cursor.
+    BlinkingUnderline,
+
+    /// A steady underline cursor.
+    SteadyUnderline,
+
+    /// A blinking vertical bar cursor.
+    BlinkingBar,
+
+    /// A steady vertical bar cursor.
+    SteadyBar,
+}
+
+impl CursorStyle {
+    fn from_param(param: u16) -> Option<Self> {
+        match param {
+            0 => Some(Self::Default),
+            1 => Some(Self::BlinkingBlock),
+            2 => Some(Self::SteadyBlock),
+            3 => Some(Self::BlinkingUnderline),
+            4 => Some(Self::SteadyUnderline),
+            5 => Some(Self::BlinkingBar),
+            6 => Some(Self::SteadyBar),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn param(self) -> u16 {
+        match self {
+            Self::Default => 0,
+            Self::BlinkingBlock => 1,
+            Self::SteadyBlock => 2,
+            Self::BlinkingUnderline => 3,
+            Self::SteadyUnderline => 4,
+            Self::BlinkingBar => 5,
+            Self::SteadyBar => 6,
+        }
+    }
 }
 
 /// The encoding to use for the enabled [`MouseProtocolMode`].
@@ -47,7 +148,13 @@ pub enum MouseProtocolEncoding {
 
     /// SGR-like encoding.
     Sgr,
-    // Urxvt,
+
+    /// Decimal encoding used by urxvt.
+    Urxvt,
+
+    /// SGR-like encoding, reporting the position in pixels rather than
+    /// cells.
+    SgrPixels,
 }
 
 /// Represents the overall terminal state.
@@ -59,9 +166,30 @@ pub struct Screen {
     attrs: crate::attrs::Attrs,
     saved_attrs: crate::attrs::Attrs,
 
-    modes: u8,
+    charsets: crate::charset::Charsets,
+    saved_charsets: crate::charset::Charsets,
+
+    modes: u16,
     mouse_protocol_mode: MouseProtocolMode,
     mouse_protocol_encoding: MouseProtocolEncoding,
+    mouse_state: crate::mouse::MouseState,
+    locator: crate::locator::Locator,
+    modify_other_keys: u8,
+    cursor_style: CursorStyle,
+    synchronized_update: Option<SynchronizedUpdate>,
+    synchronized_update_timeout: std::time::Duration,
+
+    identity: crate::TerminalIdentity,
+    hyperlinks: crate::hyperlink::Hyperlinks,
+
+    palette: crate::palette::Palette,
+    default_colors: crate::DefaultColors,
+
+    cwd: Option<String>,
+    cell_size: (u16, u16),
+    paste_controls: crate::PasteControls,
+    kitty_images: crate::kitty::Images,
+    pending_notification: Option<crate::notification::PendingNotification>,
 }
 
 impl Screen {
@@ -78,9 +206,30 @@ impl Screen {
             attrs: crate::attrs::Attrs::default(),
             saved_attrs: crate::attrs::Attrs::default(),
 
-            modes: 0,
+            charsets: crate::charset::Charsets::default(),
+            saved_charsets: crate::charset::Charsets::default(),
+
+            modes: MODE_AUTOWRAP,
             mouse_protocol_mode: MouseProtocolMode::default(),
             mouse_protocol_encoding: MouseProtocolEncoding::default(),
+            mouse_state: crate::mouse::MouseState::default(),
+            locator: crate::locator::Locator::default(),
+            modify_other_keys: 0,
+            cursor_style: CursorStyle::default(),
+            synchronized_update: None,
+            synchronized_update_timeout: SYNCHRONIZED_UPDATE_TIMEOUT,
+
+            identity: crate::TerminalIdentity::default(),
+            hyperlinks: crate::hyperlink::Hyperlinks::default(),
+
+            palette: crate::palette::Palette::default(),
+            default_colors: crate::DefaultColors::default(),
+
+            cwd: None,
+            cell_size: (10, 20),
+            paste_controls: crate::PasteControls::default(),
+            kitty_images: crate::kitty::Images::default(),
+            pending_notification: None,
         }
     }
 
@@ -114,6 +263,130 @@ impl Screen {
         self.grid_mut().set_scrollback(rows);
     }
 
+    /// Sets how the terminal identifies itself in response to device
+    /// attribute requests.
+    pub fn set_terminal_identity(
+        &mut self,
+        identity: crate::TerminalIdentity,
+    ) {
+        self.identity = identity;
+    }
+
+    /// Returns how the terminal identifies itself in response to device
+    /// attribute requests.
+    #[must_use]
+    pub fn terminal_identity(&self) -> &crate::TerminalIdentity {
+        &self.identity
+    }
+
+    /// Sets the colors that are reported when the application queries a
+    /// color that it hasn't set itself.
+    pub fn set_default_colors(
+        &mut self,
+        default_colors: crate::DefaultColors,
+    ) {
+        self.default_colors = default_colors;
+    }
+
+    /// Returns the colors that are reported when the application queries a
+    /// color that it hasn't set itself.
+    #[must_use]
+    pub fn default_colors(&self) -> &crate::DefaultColors {
+        &self.default_colors
+    }
+
+    /// Returns the color that `Color::Idx(idx)` should be rendered as, as
+    /// (red, green, blue). This will be the default color unless the
+    /// application has changed it (with `\e]4;...`).
+    #[must_use]
+    pub fn palette_color(&self, idx: u8) -> (u8, u8, u8) {
+        self.palette
+            .indexed(idx)
+            .unwrap_or_else(|| self.default_colors.indexed(idx))
+    }
+
+    /// Returns the color that `Color::Default` should be rendered as when
+    /// used as a foreground color, as (red, green, blue).
+    #[must_use]
+    pub fn foreground_color(&self) -> (u8, u8, u8) {
+        self.dynamic_color(crate::palette::DynamicColor::Foreground)
+    }
+
+    /// Returns the color that `Color::Default` should be rendered as when
+    /// used as a background color, as (red, green, blue).
+    #[must_use]
+    pub fn background_color(&self) -> (u8, u8, u8) {
+        self.dynamic_color(crate::palette::DynamicColor::Background)
+    }
+
+    /// Returns the color that the text cursor should be rendered as, as
+    /// (red, green, blue).
+    #[must_use]
+    pub fn cursor_color(&self) -> (u8, u8, u8) {
+        self.dynamic_color(crate::palette::DynamicColor::Cursor)
+    }
+
+    fn dynamic_color(
+        &self,
+        color: crate::palette::DynamicColor,
+    ) -> (u8, u8, u8) {
+        self.palette
+            .dynamic(color)
+            .unwrap_or_else(|| self.default_colors.dynamic(color))
+    }
+
+    /// Returns the current working directory most recently reported by the
+    /// application (with `\e]7;<uri>`). This is typically a `file://` uri,
+    /// including the hostname.
+    #[must_use]
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    /// Returns the shell commands described by the semantic prompt markers
+    /// (`\e]133;A` through `\e]133;D`) in the main screen and its
+    /// scrollback, from oldest to newest. Commands whose prompt has
+    /// scrolled out of the scrollback are not included.
+    #[must_use]
+    pub fn commands(&self) -> Vec<crate::Command> {
+        self.grid.commands()
+    }
+
+    /// Returns the images drawn on the visible screen (with sixel graphics,
+    /// the kitty graphics protocol, or iTerm2's inline image protocol), in
+    /// the order they were drawn.
+    #[must_use]
+    pub fn images(&self) -> Vec<crate::ImagePlacement> {
+        self.grid().images()
+    }
+
+    /// Sets the size of a character cell in pixels, as (width, height).
+    /// This is used to work out how many cells an image covers. Defaults to
+    /// 10x20. Images which are already drawn are not affected.
+    pub fn set_cell_size(&mut self, width: u16, height: u16) {
+        self.cell_size = (width.max(1), height.max(1));
+    }
+
+    /// Returns the size of a character cell in pixels, as (width, height).
+    #[must_use]
+    pub fn cell_size(&self) -> (u16, u16) {
+        self.cell_size
+    }
+
+    /// Returns the hyperlink with the given id, as returned by
+    /// [`Cell::hyperlink`](crate::Cell::hyperlink).
+    #[must_use]
+    pub fn hyperlink(&self, id: u16) -> Option<&crate::Hyperlink> {
+        self.hyperlinks.get(std::num::NonZeroU16::new(id)?)
+    }
+
+    /// Returns the id of the hyperlink that newly drawn text will be linked
+    /// to, if any.
+    #[must_use]
+    pub fn active_hyperlink(&self) -> Option<u16> {
+        self.attrs.hyperlink.map(std::num::NonZeroU16::get)
+    }
+
     /// Returns the current position in the scrollback.
     ///
     /// This position indicates the offset from the top of the screen, and is
@@ -218,24 +491,28 @@ impl Screen {
 
     /// Return escape codes sufficient to reproduce the entire contents of the
     /// current terminal state. This is a convenience wrapper around
-    /// [`contents_formatted`](Self::contents_formatted) and
+    /// [`contents_formatted`](Self::contents_formatted),
+    /// [`palette_formatted`](Self::palette_formatted) and
     /// [`input_mode_formatted`](Self::input_mode_formatted).
     #[must_use]
     pub fn state_formatted(&self) -> Vec<u8> {
         let mut contents = vec![];
         self.write_contents_formatted(&mut contents);
+        self.write_palette_formatted(&mut contents);
         self.write_input_mode_formatted(&mut contents);
         contents
     }
 
     /// Return escape codes sufficient to turn the terminal state of the
     /// screen `prev` into the current terminal state. This is a convenience
-    /// wrapper around [`contents_diff`](Self::contents_diff) and
+    /// wrapper around [`contents_diff`](Self::contents_diff),
+    /// [`palette_diff`](Self::palette_diff) and
     /// [`input_mode_diff`](Self::input_mode_diff).
     #[must_use]
     pub fn state_diff(&self, prev: &Self) -> Vec<u8> {
         let mut contents = vec![];
         self.write_contents_diff(&mut contents, prev);
+        self.write_palette_diff(&mut contents, prev);
         self.write_input_mode_diff(&mut contents, prev);
         contents
     }
@@ -244,7 +521,8 @@ impl Screen {
     ///
     /// Formatting information will be included inline as terminal escape
     /// codes. The result will be suitable for feeding directly to a raw
-    /// terminal parser, and will result in the same visual output.
+    /// terminal parser, and will result in the same visual output. If the
+    /// alternate screen is active, the output switches to it first.
     #[must_use]
     pub fn contents_formatted(&self) -> Vec<u8> {
         let mut contents = vec![];
@@ -253,9 +531,23 @@ impl Screen {
     }
 
     fn write_contents_formatted(&self, contents: &mut Vec<u8>) {
+        if self.mode(MODE_ALTERNATE_SCREEN) {
+            crate::term::AlternateScreen::new(true).write_buf(contents);
+        }
         crate::term::HideCursor::new(self.hide_cursor()).write_buf(contents);
-        let prev_attrs = self.grid().write_contents_formatted(contents);
-        self.attrs.write_escape_code_diff(contents, &prev_attrs);
+        self.write_cursor_style_formatted(contents);
+        let prev_attrs = self
+            .grid()
+            .write_contents_formatted(contents, &self.hyperlinks);
+        self.attrs.write_escape_code_diff(
+            contents,
+            &prev_attrs,
+            &self.hyperlinks,
+        );
+        self.charsets.write_escape_code_diff(
+            contents,
+            crate::charset::Charsets::default(),
+        );
     }
 
     /// Returns the formatted visible contents of the terminal by row,
@@ -281,7 +573,7 @@ impl Screen {
             // visible_rows can never return enough rows to overflow here
             let i = i.try_into().unwrap();
             let mut contents = vec![];
-            row.write_contents_formatted(
+            let (_, attrs) = row.write_contents_formatted(
                 &mut contents,
                 start,
                 width,
@@ -289,7 +581,13 @@ impl Screen {
                 wrapping,
                 None,
                 None,
+                &self.hyperlinks,
             );
+            // rows are drawn independently, so don't let a hyperlink leak
+            // into whatever gets drawn next
+            if attrs.hyperlink.is_some() {
+                crate::term::Hyperlink::new(None).write_buf(&mut contents);
+            }
             if start == 0 && width == self.grid.size().cols {
                 wrapping = row.wrapped();
             }
@@ -331,7 +629,8 @@ impl Screen {
     #[must_use]
     pub fn contents_formatted_full(&self) -> Vec<u8> {
         let mut contents = vec![];
-        self.grid.write_contents_formatted_full(&mut contents);
+        self.grid
+            .write_contents_formatted_full(&mut contents, &self.hyperlinks);
         contents
     }
 
@@ -382,6 +681,7 @@ impl Screen {
                 start,
                 width,
                 prev_attrs,
+                &self.hyperlinks,
             );
             contents
         })
@@ -409,12 +709,90 @@ impl Screen {
             crate::term::HideCursor::new(self.hide_cursor())
                 .write_buf(contents);
         }
-        let prev_attrs = self.grid().write_contents_diff(
+        if self.cursor_style != prev.cursor_style {
+            crate::term::CursorStyle::new(self.cursor_style)
+                .write_buf(contents);
+        }
+        if self.cursor_blink() != prev.cursor_blink() {
+            crate::term::CursorBlink::new(self.cursor_blink())
+                .write_buf(contents);
+        }
+        // the terminal's current link has to be given an id from this
+        // screen, and if this screen doesn't know about it, it is closed
+        let mut prev_attrs = prev.attrs;
+        if let Some(link) =
+            prev.attrs.hyperlink.and_then(|id| prev.hyperlinks.get(id))
+        {
+            prev_attrs.hyperlink = self.hyperlinks.find(link);
+            if prev_attrs.hyperlink.is_none() {
+                crate::term::Hyperlink::new(None).write_buf(contents);
+            }
+        }
+        // entering the alternate screen saves the cursor like the
+        // application did, but leaving it only switches back, since the
+        // terminal may not have the saved cursor that this screen has. either
+        // way, the contents of the newly active screen aren't known, so they
+        // are redrawn.
+        let switched = self.mode(MODE_ALTERNATE_SCREEN)
+            != prev.mode(MODE_ALTERNATE_SCREEN);
+        if switched {
+            if self.mode(MODE_ALTERNATE_SCREEN) {
+                crate::term::AlternateScreen::new(true).write_buf(contents);
+            } else {
+                crate::term::AlternateBuffer::new(false).write_buf(contents);
+            }
+            if prev_attrs.hyperlink.is_some() {
+                crate::term::Hyperlink::new(None).write_buf(contents);
+            }
+        }
+        let start = contents.len();
+        let prev_attrs = if switched {
+            self.grid()
+                .write_contents_formatted(contents, &self.hyperlinks)
+        } else {
+            self.grid().write_contents_diff(
+                contents,
+                prev.grid(),
+                prev_attrs,
+                &self.hyperlinks,
+                &prev.hyperlinks,
+            )
+        };
+        let mut prev_charsets = prev.charsets;
+        // if we drew anything, make sure that the state of the terminal
+        // doesn't change how it gets drawn
+        if contents.len() > start {
+            let mut reset = vec![];
+
+            // the cell contents are already translated, so make sure they
+            // don't get translated a second time
+            prev_charsets = prev.charsets.for_drawing();
+            prev_charsets.write_escape_code_diff(&mut reset, prev.charsets);
+
+            // depending on whether the input modes have been updated yet,
+            // the modes from either screen could be active here
+            if self.insert_mode() || prev.insert_mode() {
+                crate::term::InsertMode::new(false).write_buf(&mut reset);
+                if self.insert_mode() {
+                    crate::term::InsertMode::new(true).write_buf(contents);
+                }
+            }
+            if !self.autowrap() || !prev.autowrap() {
+                crate::term::Autowrap::new(true).write_buf(&mut reset);
+                if !self.autowrap() {
+                    crate::term::Autowrap::new(false).write_buf(contents);
+                }
+            }
+
+            contents.splice(start..start, reset);
+        }
+        self.attrs.write_escape_code_diff(
             contents,
-            prev.grid(),
-            prev.attrs,
+            &prev_attrs,
+            &self.hyperlinks,
         );
-        self.attrs.write_escape_code_diff(contents, &prev_attrs);
+        self.charsets
+            .write_escape_code_diff(contents, prev_charsets);
     }
 
     /// Returns a sequence of terminal byte streams sufficient to turn the
@@ -442,7 +820,7 @@ impl Screen {
                 // visible_rows can never return enough rows to overflow here
                 let i = i.try_into().unwrap();
                 let mut contents = vec![];
-                row.write_contents_diff(
+                let (_, attrs) = row.write_contents_diff(
                     &mut contents,
                     prev_row,
                     start,
@@ -452,11 +830,50 @@ impl Screen {
                     false,
                     crate::grid::Pos { row: i, col: start },
                     crate::attrs::Attrs::default(),
+                    &self.hyperlinks,
+                    &prev.hyperlinks,
                 );
+                if attrs.hyperlink.is_some() {
+                    crate::term::Hyperlink::new(None)
+                        .write_buf(&mut contents);
+                }
                 contents
             })
     }
 
+    /// Returns terminal escape sequences sufficient to set the colors that
+    /// the application has changed (with `\e]4;...`, `\e]10;...`, etc).
+    /// Colors which the application hasn't changed are left alone, so that
+    /// they continue to use the defaults of the terminal being drawn to.
+    #[must_use]
+    pub fn palette_formatted(&self) -> Vec<u8> {
+        let mut contents = vec![];
+        self.write_palette_formatted(&mut contents);
+        contents
+    }
+
+    fn write_palette_formatted(&self, contents: &mut Vec<u8>) {
+        self.palette.write_escape_code_diff(
+            contents,
+            &crate::palette::Palette::default(),
+        );
+    }
+
+    /// Returns terminal escape sequences sufficient to change the previous
+    /// terminal's colors to the colors set in the current terminal. Colors
+    /// which were reset by the application are reset rather than being set
+    /// explicitly.
+    #[must_use]
+    pub fn palette_diff(&self, prev: &Self) -> Vec<u8> {
+        let mut contents = vec![];
+        self.write_palette_diff(&mut contents, prev);
+        contents
+    }
+
+    fn write_palette_diff(&self, contents: &mut Vec<u8>, prev: &Self) {
+        self.palette.write_escape_code_diff(contents, &prev.palette);
+    }
+
     /// Returns terminal escape sequences sufficient to set the current
     /// terminal's input modes.
     ///
@@ -465,6 +882,17 @@ impl Screen {
     /// * application cursor
     /// * bracketed paste
     /// * xterm mouse support
+    /// * DEC locator reporting
+    /// * insert mode
+    /// * newline mode
+    /// * autowrap
+    /// * reverse wraparound
+    /// * left/right margin mode
+    /// * sixel display mode
+    /// * backarrow key mode
+    /// * focus events
+    /// * xterm modifyOtherKeys
+    /// * kitty keyboard protocol flags
     #[must_use]
     pub fn input_mode_formatted(&self) -> Vec<u8> {
         let mut contents = vec![];
@@ -483,6 +911,26 @@ impl Screen {
         .write_buf(contents);
         crate::term::BracketedPaste::new(self.mode(MODE_BRACKETED_PASTE))
             .write_buf(contents);
+        crate::term::InsertMode::new(self.mode(MODE_INSERT))
+            .write_buf(contents);
+        crate::term::NewlineMode::new(self.mode(MODE_NEWLINE))
+            .write_buf(contents);
+        crate::term::Autowrap::new(self.mode(MODE_AUTOWRAP))
+            .write_buf(contents);
+        crate::term::ReverseWraparound::new(
+            self.mode(MODE_REVERSE_WRAPAROUND),
+        )
+        .write_buf(contents);
+        crate::term::LeftRightMarginMode::new(
+            self.mode(MODE_LEFT_RIGHT_MARGIN),
+        )
+        .write_buf(contents);
+        crate::term::SixelDisplayMode::new(self.mode(MODE_SIXEL_DISPLAY))
+            .write_buf(contents);
+        crate::term::BackarrowKeyMode::new(self.mode(MODE_BACKARROW_KEY))
+            .write_buf(contents);
+        crate::term::FocusEvents::new(self.mode(MODE_FOCUS_EVENTS))
+            .write_buf(contents);
         crate::term::MouseProtocolMode::new(
             self.mouse_protocol_mode,
             MouseProtocolMode::None,
@@ -493,11 +941,33 @@ impl Screen {
             MouseProtocolEncoding::Default,
         )
         .write_buf(contents);
+        crate::term::DecLocator::new(self.dec_locator_mode(), None)
+            .write_buf(contents);
+        crate::term::LocatorEvents::new(
+            self.locator_events(),
+            (false, false),
+        )
+        .write_buf(contents);
+        crate::term::ModifyOtherKeys::new(self.modify_other_keys)
+            .write_buf(contents);
+        let alternate = self.mode(MODE_ALTERNATE_SCREEN);
+        let (stack, other_stack) = self.keyboard_flags_stacks(alternate);
+        crate::term::KeyboardFlags::new(stack, &[]).write_buf(contents);
+        if !other_stack.is_empty() {
+            crate::term::AlternateBuffer::new(!alternate).write_buf(contents);
+            crate::term::KeyboardFlags::new(other_stack, &[])
+                .write_buf(contents);
+            crate::term::AlternateBuffer::new(alternate).write_buf(contents);
+        }
     }
 
     /// Returns terminal escape sequences sufficient to change the previous
     /// terminal's input modes to the input modes enabled in the current
     /// terminal.
+    ///
+    /// This expects the terminal to be showing the same screen (main or
+    /// alternate) as the current terminal, as it will be after
+    /// [`contents_diff`](Self::contents_diff).
     #[must_use]
     pub fn input_mode_diff(&self, prev: &Self) -> Vec<u8> {
         let mut contents = vec![];
@@ -527,6 +997,46 @@ impl Screen {
             crate::term::BracketedPaste::new(self.mode(MODE_BRACKETED_PASTE))
                 .write_buf(contents);
         }
+        if self.mode(MODE_INSERT) != prev.mode(MODE_INSERT) {
+            crate::term::InsertMode::new(self.mode(MODE_INSERT))
+                .write_buf(contents);
+        }
+        if self.mode(MODE_NEWLINE) != prev.mode(MODE_NEWLINE) {
+            crate::term::NewlineMode::new(self.mode(MODE_NEWLINE))
+                .write_buf(contents);
+        }
+        if self.mode(MODE_AUTOWRAP) != prev.mode(MODE_AUTOWRAP) {
+            crate::term::Autowrap::new(self.mode(MODE_AUTOWRAP))
+                .write_buf(contents);
+        }
+        if self.mode(MODE_REVERSE_WRAPAROUND)
+            != prev.mode(MODE_REVERSE_WRAPAROUND)
+        {
+            crate::term::ReverseWraparound::new(
+                self.mode(MODE_REVERSE_WRAPAROUND),
+            )
+            .write_buf(contents);
+        }
+        if self.mode(MODE_LEFT_RIGHT_MARGIN)
+            != prev.mode(MODE_LEFT_RIGHT_MARGIN)
+        {
+            crate::term::LeftRightMarginMode::new(
+                self.mode(MODE_LEFT_RIGHT_MARGIN),
+            )
+            .write_buf(contents);
+        }
+        if self.mode(MODE_SIXEL_DISPLAY) != prev.mode(MODE_SIXEL_DISPLAY) {
+            crate::term::SixelDisplayMode::new(self.mode(MODE_SIXEL_DISPLAY))
+                .write_buf(contents);
+        }
+        if self.mode(MODE_BACKARROW_KEY) != prev.mode(MODE_BACKARROW_KEY) {
+            crate::term::BackarrowKeyMode::new(self.mode(MODE_BACKARROW_KEY))
+                .write_buf(contents);
+        }
+        if self.mode(MODE_FOCUS_EVENTS) != prev.mode(MODE_FOCUS_EVENTS) {
+            crate::term::FocusEvents::new(self.mode(MODE_FOCUS_EVENTS))
+                .write_buf(contents);
+        }
         crate::term::MouseProtocolMode::new(
             self.mouse_protocol_mode,
             prev.mouse_protocol_mode,
@@ -537,6 +1047,48 @@ impl Screen {
             prev.mouse_protocol_encoding,
         )
         .write_buf(contents);
+        crate::term::DecLocator::new(
+            self.dec_locator_mode(),
+            prev.dec_locator_mode(),
+        )
+        .write_buf(contents);
+        crate::term::LocatorEvents::new(
+            self.locator_events(),
+            prev.locator_events(),
+        )
+        .write_buf(contents);
+        if self.modify_other_keys != prev.modify_other_keys {
+            crate::term::ModifyOtherKeys::new(self.modify_other_keys)
+                .write_buf(contents);
+        }
+        // each screen's stack is compared with the same screen's stack in
+        // prev, since the active screens can be different
+        let alternate = self.mode(MODE_ALTERNATE_SCREEN);
+        let (stack, other_stack) = self.keyboard_flags_stacks(alternate);
+        let (prev_stack, prev_other_stack) =
+            prev.keyboard_flags_stacks(alternate);
+        crate::term::KeyboardFlags::new(stack, prev_stack)
+            .write_buf(contents);
+        if other_stack != prev_other_stack {
+            crate::term::AlternateBuffer::new(!alternate).write_buf(contents);
+            crate::term::KeyboardFlags::new(other_stack, prev_other_stack)
+                .write_buf(contents);
+            crate::term::AlternateBuffer::new(alternate).write_buf(contents);
+        }
+    }
+
+    // returns the kitty keyboard flag stack of the main or alternate screen,
+    // followed by the stack of the other one
+    fn keyboard_flags_stacks(&self, alternate: bool) -> (&[u8], &[u8]) {
+        let (grid, other_grid) = if alternate {
+            (&self.alternate_grid, &self.grid)
+        } else {
+            (&self.grid, &self.alternate_grid)
+        };
+        (
+            grid.keyboard_flags().stack(),
+            other_grid.keyboard_flags().stack(),
+        )
     }
 
     /// Returns terminal escape sequences sufficient to set the current
@@ -550,6 +1102,12 @@ impl Screen {
     /// * italic
     /// * underline
     /// * inverse
+    /// * blink
+    /// * conceal
+    /// * strikethrough
+    /// * overline
+    /// * hyperlink
+    /// * character set designations and shifts
     ///
     /// This is not typically necessary, since
     /// [`contents_formatted`](Self::contents_formatted) will leave
@@ -569,6 +1127,11 @@ impl Screen {
         self.attrs.write_escape_code_diff(
             contents,
             &crate::attrs::Attrs::default(),
+            &self.hyperlinks,
+        );
+        self.charsets.write_escape_code_diff(
+            contents,
+            crate::charset::Charsets::default(),
         );
     }
 
@@ -582,7 +1145,8 @@ impl Screen {
     }
 
     /// Returns terminal escape sequences sufficient to set the current
-    /// cursor state of the terminal.
+    /// cursor state of the terminal. This includes the cursor position and
+    /// visibility, as well as any tab stops which differ from the defaults.
     ///
     /// This is not typically necessary, since
     /// [`contents_formatted`](Self::contents_formatted) will leave
@@ -607,8 +1171,18 @@ impl Screen {
 
     fn write_cursor_state_formatted(&self, contents: &mut Vec<u8>) {
         crate::term::HideCursor::new(self.hide_cursor()).write_buf(contents);
-        self.grid()
-            .write_cursor_position_formatted(contents, None, None);
+        self.write_cursor_style_formatted(contents);
+        if !self.grid().has_default_tabs() {
+            // this moves the cursor, but we're about to set its absolute
+            // position anyway
+            self.grid().write_tabs_formatted(contents);
+        }
+        self.grid().write_cursor_position_formatted(
+            contents,
+            None,
+            None,
+            &self.hyperlinks,
+        );
 
         // we don't just call write_attributes_formatted here, because that
         // would still be confusing - consider the case where the user sets
@@ -618,6 +1192,23 @@ impl Screen {
         // straightforward.
     }
 
+    fn write_cursor_style_formatted(&self, contents: &mut Vec<u8>) {
+        if self.cursor_style != CursorStyle::default() {
+            crate::term::CursorStyle::new(self.cursor_style)
+                .write_buf(contents);
+        }
+        if self.cursor_blink() {
+            crate::term::CursorBlink::new(true).write_buf(contents);
+        }
+    }
+
+    /// Returns the columns which currently have tab stops set, in increasing
+    /// order.
+    #[must_use = "this only returns the tab stops"]
+    pub fn tab_stops(&self) -> impl Iterator<Item = u16> + '_ {
+        self.grid().tab_stops()
+    }
+
     /// Returns the [`Cell`](crate::Cell) object at the given location in the
     /// terminal, if it exists.
     #[must_use]
@@ -663,123 +1254,473 @@ impl Screen {
         self.mode(MODE_BRACKETED_PASTE)
     }
 
-    /// Returns the currently active [`MouseProtocolMode`].
-    #[must_use]
-    pub fn mouse_protocol_mode(&self) -> MouseProtocolMode {
-        self.mouse_protocol_mode
+    /// Sets what [`encode_paste`](Self::encode_paste) does with control
+    /// characters in pasted text. Defaults to removing them.
+    pub fn set_paste_controls(&mut self, controls: crate::PasteControls) {
+        self.paste_controls = controls;
     }
 
-    /// Returns the currently active [`MouseProtocolEncoding`].
+    /// Returns the bytes to send to the application when the given text is
+    /// pasted. The text is wrapped in bracketed paste markers if
+    /// [`bracketed_paste`](Self::bracketed_paste) is enabled, and any
+    /// markers within the text are removed so that it can't end the paste
+    /// early. Newlines are sent as carriage returns, and other control
+    /// characters are handled as set by
+    /// [`set_paste_controls`](Self::set_paste_controls).
     #[must_use]
-    pub fn mouse_protocol_encoding(&self) -> MouseProtocolEncoding {
-        self.mouse_protocol_encoding
+    pub fn encode_paste(&self, data: &[u8]) -> Vec<u8> {
+        crate::paste::encode(
+            data,
+            self.mode(MODE_BRACKETED_PASTE),
+            self.paste_controls,
+        )
     }
 
-    /// Returns the currently active foreground color.
+    /// Returns whether the terminal should be in insert mode, where newly
+    /// drawn text shifts the rest of the row to the right rather than
+    /// overwriting it.
     #[must_use]
-    pub fn fgcolor(&self) -> crate::Color {
-        self.attrs.fgcolor
+    pub fn insert_mode(&self) -> bool {
+        self.mode(MODE_INSERT)
     }
 
-    /// Returns the currently active background color.
+    /// Returns whether the terminal should be in newline mode, where a line
+    /// feed also moves the cursor to the start of the line.
     #[must_use]
-    pub fn bgcolor(&self) -> crate::Color {
-        self.attrs.bgcolor
+    pub fn newline_mode(&self) -> bool {
+        self.mode(MODE_NEWLINE)
     }
 
-    /// Returns whether newly drawn text should be rendered with the bold text
-    /// attribute.
+    /// Returns whether text drawn past the end of a row should wrap onto the
+    /// next row. When this is disabled, text drawn at the end of a row
+    /// overwrites the last column instead.
     #[must_use]
-    pub fn bold(&self) -> bool {
-        self.attrs.bold()
+    pub fn autowrap(&self) -> bool {
+        self.mode(MODE_AUTOWRAP)
     }
 
-    /// Returns whether newly drawn text should be rendered with the dim text
-    /// attribute.
+    /// Returns whether the terminal should be in reverse wraparound mode,
+    /// where moving the cursor backwards from the first column moves it to
+    /// the end of the previous row. This only has an effect when
+    /// [`autowrap`](Self::autowrap) is also enabled.
     #[must_use]
-    pub fn dim(&self) -> bool {
-        self.attrs.dim()
+    pub fn reverse_wraparound(&self) -> bool {
+        self.mode(MODE_REVERSE_WRAPAROUND)
     }
 
-    /// Returns whether newly drawn text should be rendered with the italic
-    /// text attribute.
+    /// Returns whether the terminal is in left/right margin mode, where
+    /// `CSI Pl ; Pr s` sets the left and right margins rather than saving
+    /// the cursor position.
     #[must_use]
-    pub fn italic(&self) -> bool {
-        self.attrs.italic()
+    pub fn left_right_margin_mode(&self) -> bool {
+        self.mode(MODE_LEFT_RIGHT_MARGIN)
     }
 
-    /// Returns whether newly drawn text should be rendered with the
-    /// underlined text attribute.
+    /// Returns whether the backarrow key mode (DECBKM) is set, in which case
+    /// the backspace key sends a backspace character (`^H`) rather than a
+    /// delete character (`^?`).
     #[must_use]
-    pub fn underline(&self) -> bool {
-        self.attrs.underline()
+    pub fn backarrow_key_mode(&self) -> bool {
+        self.mode(MODE_BACKARROW_KEY)
     }
 
-    /// Returns whether newly drawn text should be rendered with the inverse
-    /// text attribute.
+    /// Returns whether the application has asked to be told when the
+    /// terminal gains or loses focus.
     #[must_use]
-    pub fn inverse(&self) -> bool {
-        self.attrs.inverse()
+    pub fn focus_events(&self) -> bool {
+        self.mode(MODE_FOCUS_EVENTS)
     }
 
-    pub(crate) fn grid(&self) -> &crate::grid::Grid {
-        if self.mode(MODE_ALTERNATE_SCREEN) {
-            &self.alternate_grid
+    /// Returns the bytes to send to the application when the terminal gains
+    /// (`CSI I`) or loses (`CSI O`) focus. This is empty unless
+    /// [`focus_events`](Self::focus_events) is enabled.
+    #[must_use]
+    pub fn encode_focus(&self, focused: bool) -> Vec<u8> {
+        if !self.mode(MODE_FOCUS_EVENTS) {
+            return vec![];
+        }
+        if focused {
+            b"\x1b[I".to_vec()
         } else {
-            &self.grid
+            b"\x1b[O".to_vec()
         }
     }
 
-    fn grid_mut(&mut self) -> &mut crate::grid::Grid {
-        if self.mode(MODE_ALTERNATE_SCREEN) {
-            &mut self.alternate_grid
-        } else {
-            &mut self.grid
-        }
+    /// Returns the level of xterm's modifyOtherKeys mode requested by the
+    /// application with `CSI > 4 ; level m` (0, 1 or 2). When this is
+    /// enabled, key presses with modifiers which would otherwise be lost are
+    /// sent as `CSI 27 ; modifiers ; code ~`.
+    #[must_use]
+    pub fn modify_other_keys(&self) -> u8 {
+        self.modify_other_keys
     }
 
-    fn enter_alternate_grid(&mut self) {
-        self.grid_mut().set_scrollback(0);
-        self.set_mode(MODE_ALTERNATE_SCREEN);
-        self.alternate_grid.allocate_rows();
+    /// Returns the kitty keyboard protocol flags currently in effect, as
+    /// requested by the application with `CSI > flags u` and friends. The
+    /// main and alternate screens each have their own stack of flags. 0
+    /// means that the protocol is not in use.
+    #[must_use]
+    pub fn keyboard_flags(&self) -> u8 {
+        self.grid().keyboard_flags().current()
+    }
+
+    /// Returns the bytes to send to the application for the given key
+    /// press, taking into account the input modes that the application has
+    /// requested (such as [`application_cursor`](Self::application_cursor),
+    /// [`application_keypad`](Self::application_keypad),
+    /// [`newline_mode`](Self::newline_mode),
+    /// [`backarrow_key_mode`](Self::backarrow_key_mode) and
+    /// [`modify_other_keys`](Self::modify_other_keys)). Keys with modifiers
+    /// use xterm's encoding (`CSI 1 ; 5 A` for control+up), unless the
+    /// application has enabled the kitty keyboard protocol (see
+    /// [`keyboard_flags`](Self::keyboard_flags)), in which case keys are
+    /// encoded as `CSI code ; modifiers : event-type ; text u`.
+    #[must_use]
+    pub fn encode_key(&self, event: &crate::KeyEvent) -> Vec<u8> {
+        event.encode(self)
     }
 
-    fn exit_alternate_grid(&mut self) {
-        self.clear_mode(MODE_ALTERNATE_SCREEN);
+    /// Returns whether the application is in the middle of a synchronized
+    /// update (mode 2026), during which [`frame`](Self::frame) should be
+    /// displayed instead of this screen. Updates which last longer than the
+    /// timeout (see
+    /// [`set_synchronized_update_timeout`](Self::set_synchronized_update_timeout)),
+    /// or which contain more than a few megabytes of output, are ended
+    /// automatically.
+    #[must_use]
+    pub fn synchronized_output(&self) -> bool {
+        self.synchronized_update
+            .as_ref()
+            .is_some_and(|update| !update.expired())
     }
 
-    fn save_cursor(&mut self) {
-        self.grid_mut().save_cursor();
-        self.saved_attrs = self.attrs;
+    /// Sets how long a synchronized update can last before it is ended
+    /// automatically. This applies to updates which start after it is set.
+    /// The default is one second.
+    pub fn set_synchronized_update_timeout(
+        &mut self,
+        timeout: std::time::Duration,
+    ) {
+        self.synchronized_update_timeout = timeout;
     }
 
-    fn restore_cursor(&mut self) {
-        self.grid_mut().restore_cursor();
-        self.attrs = self.saved_attrs;
+    /// Returns the last complete frame, which is the state of the screen
+    /// from when the current synchronized update started, or this screen if
+    /// there is no synchronized update in progress. Displaying this rather
+    /// than the screen itself avoids showing partially drawn output.
+    ///
+    /// The frame only contains what is needed to display the screen, so it
+    /// has no scrollback (the rows which were visible when the update
+    /// started become its only rows), and the images and hyperlinks which
+    /// weren't visible are left out.
+    #[must_use]
+    pub fn frame(&self) -> &Self {
+        match &self.synchronized_update {
+            Some(update) if !update.expired() => &update.frame,
+            _ => self,
+        }
     }
 
-    fn set_mode(&mut self, mode: u8) {
+    /// Returns the cursor shape requested by the application.
+    #[must_use]
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    /// Returns whether the application has requested a blinking cursor. This
+    /// is tracked separately from [`cursor_style`](Self::cursor_style).
+    #[must_use]
+    pub fn cursor_blink(&self) -> bool {
+        self.mode(MODE_CURSOR_BLINK)
+    }
+
+    /// Returns the currently active [`MouseProtocolMode`].
+    #[must_use]
+    pub fn mouse_protocol_mode(&self) -> MouseProtocolMode {
+        self.mouse_protocol_mode
+    }
+
+    /// Returns the currently active [`MouseProtocolEncoding`].
+    #[must_use]
+    pub fn mouse_protocol_encoding(&self) -> MouseProtocolEncoding {
+        self.mouse_protocol_encoding
+    }
+
+    /// Returns the bytes to send to the application for the given mouse
+    /// event, based on the current [`MouseProtocolMode`] and
+    /// [`MouseProtocolEncoding`]. This is empty if the application hasn't
+    /// asked for the event to be reported, or if its position can't be
+    /// represented in the current encoding (the default encoding can't
+    /// report positions past column or row 223).
+    ///
+    /// Motion events are only reported when the mouse moves to a different
+    /// cell than the last reported event, so this needs to be called for
+    /// every mouse event, rather than just the ones that should be sent.
+    /// This is also how the DEC locator knows where the mouse is, in order
+    /// to answer position requests from the application.
+    pub fn encode_mouse(&mut self, event: &crate::MouseEvent) -> Vec<u8> {
+        let reports = self.locator.mouse_event(event, self.cell_size);
+        if self.mouse_protocol_mode == MouseProtocolMode::DecLocator {
+            if !reports.is_empty() && self.locator.one_shot() {
+                self.clear_mouse_mode(MouseProtocolMode::DecLocator);
+            }
+            return reports;
+        }
+        event.encode(
+            self.mouse_protocol_mode,
+            self.mouse_protocol_encoding,
+            self.cell_size,
+            &mut self.mouse_state,
+        )
+    }
+
+    /// Returns the character sets currently designated into G0 through G3.
+    #[must_use]
+    pub fn charsets(&self) -> [crate::Charset; 4] {
+        self.charsets.designations()
+    }
+
+    /// Returns the index (0 through 3) of the character set currently
+    /// invoked into GL, as selected by SI, SO, LS2, or LS3.
+    #[must_use]
+    pub fn active_charset(&self) -> u8 {
+        self.charsets.gl()
+    }
+
+    /// Returns the currently active foreground color.
+    #[must_use]
+    pub fn fgcolor(&self) -> crate::Color {
+        self.attrs.fgcolor
+    }
+
+    /// Returns the currently active background color.
+    #[must_use]
+    pub fn bgcolor(&self) -> crate::Color {
+        self.attrs.bgcolor
+    }
+
+    /// Returns whether newly drawn text should be rendered with the bold text
+    /// attribute.
+    #[must_use]
+    pub fn bold(&self) -> bool {
+        self.attrs.bold()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the dim text
+    /// attribute.
+    #[must_use]
+    pub fn dim(&self) -> bool {
+        self.attrs.dim()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the italic
+    /// text attribute.
+    #[must_use]
+    pub fn italic(&self) -> bool {
+        self.attrs.italic()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the
+    /// underlined text attribute.
+    #[must_use]
+    pub fn underline(&self) -> bool {
+        self.attrs.underline()
+    }
+
+    /// Returns the style of line that newly drawn text should be underlined
+    /// with.
+    #[must_use]
+    pub fn underline_style(&self) -> crate::UnderlineStyle {
+        self.attrs.underline_style()
+    }
+
+    /// Returns the color that newly drawn text should be underlined with.
+    #[must_use]
+    pub fn underline_color(&self) -> crate::Color {
+        self.attrs.underline_color
+    }
+
+    /// Returns whether newly drawn text should be rendered with the inverse
+    /// text attribute.
+    #[must_use]
+    pub fn inverse(&self) -> bool {
+        self.attrs.inverse()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the blinking
+    /// text attribute.
+    #[must_use]
+    pub fn blink(&self) -> bool {
+        self.attrs.blink()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the concealed
+    /// text attribute.
+    #[must_use]
+    pub fn conceal(&self) -> bool {
+        self.attrs.conceal()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the strikethrough
+    /// text attribute.
+    #[must_use]
+    pub fn strikethrough(&self) -> bool {
+        self.attrs.strikethrough()
+    }
+
+    /// Returns whether newly drawn text should be rendered with the overlined
+    /// text attribute.
+    #[must_use]
+    pub fn overline(&self) -> bool {
+        self.attrs.overline()
+    }
+
+    pub(crate) fn grid(&self) -> &crate::grid::Grid {
+        if self.mode(MODE_ALTERNATE_SCREEN) {
+            &self.alternate_grid
+        } else {
+            &self.grid
+        }
+    }
+
+    fn grid_mut(&mut self) -> &mut crate::grid::Grid {
+        if self.mode(MODE_ALTERNATE_SCREEN) {
+            &mut self.alternate_grid
+        } else {
+            &mut self.grid
+        }
+    }
+
+    fn enter_alternate_grid(&mut self) {
+        self.grid_mut().set_scrollback(0);
+        self.set_mode(MODE_ALTERNATE_SCREEN);
+        self.alternate_grid.allocate_rows();
+    }
+
+    fn exit_alternate_grid(&mut self) {
+        self.clear_mode(MODE_ALTERNATE_SCREEN);
+    }
+
+    fn save_cursor(&mut self) {
+        self.grid_mut().save_cursor();
+        self.saved_attrs = self.attrs;
+        self.saved_charsets = self.charsets;
+    }
+
+    fn restore_cursor(&mut self) {
+        self.grid_mut().restore_cursor();
+        self.attrs = self.saved_attrs;
+        self.charsets = self.saved_charsets;
+    }
+
+    fn set_mode(&mut self, mode: u16) {
         self.modes |= mode;
     }
 
-    fn clear_mode(&mut self, mode: u8) {
+    fn clear_mode(&mut self, mode: u16) {
         self.modes &= !mode;
     }
 
-    fn mode(&self, mode: u8) -> bool {
+    fn mode(&self, mode: u16) -> bool {
         self.modes & mode != 0
     }
 
+    // starting an update while one is in progress keeps the existing frame,
+    // but an update which has expired is replaced
+    fn begin_synchronized_update(&mut self) {
+        if !self.synchronized_output() {
+            self.synchronized_update = Some(SynchronizedUpdate {
+                frame: Box::new(self.snapshot()),
+                deadline: std::time::Instant::now()
+                    + self.synchronized_update_timeout,
+                bytes: 0,
+            });
+        }
+    }
+
+    // a copy of just the parts of the screen which are needed to display
+    // it, which is much cheaper to make than a full clone
+    fn snapshot(&self) -> Self {
+        let (grid, alternate_grid) = if self.mode(MODE_ALTERNATE_SCREEN) {
+            (
+                crate::grid::Grid::new(self.grid.size(), 0),
+                self.alternate_grid.snapshot(),
+            )
+        } else {
+            (
+                self.grid.snapshot(),
+                crate::grid::Grid::new(self.alternate_grid.size(), 0),
+            )
+        };
+        let hyperlinks = self.hyperlinks.snapshot(
+            grid.hyperlinks()
+                .chain(alternate_grid.hyperlinks())
+                .chain(self.attrs.hyperlink),
+        );
+        Self {
+            grid,
+            alternate_grid,
+            attrs: self.attrs,
+            saved_attrs: self.saved_attrs,
+            charsets: self.charsets,
+            saved_charsets: self.saved_charsets,
+            modes: self.modes,
+            mouse_protocol_mode: self.mouse_protocol_mode,
+            mouse_protocol_encoding: self.mouse_protocol_encoding,
+            mouse_state: crate::mouse::MouseState::default(),
+            locator: crate::locator::Locator::default(),
+            modify_other_keys: self.modify_other_keys,
+            cursor_style: self.cursor_style,
+            synchronized_update: None,
+            synchronized_update_timeout: self.synchronized_update_timeout,
+            identity: crate::TerminalIdentity::default(),
+            hyperlinks,
+            palette: self.palette.clone(),
+            default_colors: self.default_colors.clone(),
+            cwd: None,
+            cell_size: self.cell_size,
+            paste_controls: self.paste_controls,
+            kitty_images: crate::kitty::Images::default(),
+            pending_notification: None,
+        }
+    }
+
+    // called by the parser after processing output, to end synchronized
+    // updates which contain too much output
+    pub(crate) fn synchronized_bytes(&mut self, len: usize) {
+        if let Some(update) = &mut self.synchronized_update {
+            update.bytes = update.bytes.saturating_add(len);
+            if update.bytes > SYNCHRONIZED_UPDATE_MAX_BYTES {
+                self.synchronized_update = None;
+            }
+        }
+    }
+
     fn set_mouse_mode(&mut self, mode: MouseProtocolMode) {
+        if self.mouse_protocol_mode == MouseProtocolMode::DecLocator {
+            self.locator.disable();
+        }
         self.mouse_protocol_mode = mode;
     }
 
     fn clear_mouse_mode(&mut self, mode: MouseProtocolMode) {
         if self.mouse_protocol_mode == mode {
             self.mouse_protocol_mode = MouseProtocolMode::default();
+            if mode == MouseProtocolMode::DecLocator {
+                self.locator.disable();
+            }
         }
     }
 
+    fn dec_locator_mode(&self) -> Option<(bool, bool)> {
+        (self.mouse_protocol_mode == MouseProtocolMode::DecLocator)
+            .then(|| (self.locator.one_shot(), self.locator.pixels()))
+    }
+
+    fn locator_events(&self) -> (bool, bool) {
+        (self.locator.report_press(), self.locator.report_release())
+    }
+
     fn set_mouse_encoding(&mut self, encoding: MouseProtocolEncoding) {
         self.mouse_protocol_encoding = encoding;
     }
@@ -793,6 +1734,7 @@ impl Screen {
 
 impl Screen {
     pub(crate) fn text(&mut self, c: char) {
+        let c = self.charsets.map(c);
         let pos = self.grid().pos();
         let size = self.grid().size();
         let attrs = self.attrs;
@@ -816,23 +1758,31 @@ impl Screen {
         // reconsidering this behavior, but only with a really good reason
         // (xterm handles this by introducing the concept of triple width
         // cells, which i really don't want to do).
-        let mut wrap = false;
-        if pos.col > size.cols - width {
-            let last_cell = self
-                .grid()
-                .drawing_cell(crate::grid::Pos {
-                    row: pos.row,
-                    col: size.cols - 1,
-                })
-                // pos.row is valid, since it comes directly from
-                // self.grid().pos() which we assume to always have a valid
-                // row value. size.cols - 1 is also always a valid column.
-                .unwrap();
-            if last_cell.has_contents() || last_cell.is_wide_continuation() {
-                wrap = true;
+        if self.mode(MODE_AUTOWRAP) {
+            let mut wrap = false;
+            let wrap_col = self.grid().wrap_col();
+            if pos.col + width > wrap_col + 1 {
+                let last_cell = self
+                    .grid()
+                    .drawing_cell(crate::grid::Pos {
+                        row: pos.row,
+                        col: wrap_col,
+                    })
+                    // pos.row is valid, since it comes directly from
+                    // self.grid().pos() which we assume to always have a
+                    // valid row value. wrap_col is also always a valid
+                    // column.
+                    .unwrap();
+                if last_cell.has_contents()
+                    || last_cell.is_wide_continuation()
+                {
+                    wrap = true;
+                }
             }
+            self.grid_mut().col_wrap(width, wrap);
+        } else {
+            self.grid_mut().col_clamp_width(width);
         }
-        self.grid_mut().col_wrap(width, wrap);
         let pos = self.grid().pos();
 
         if width == 0 {
@@ -907,6 +1857,10 @@ impl Screen {
                 }
             }
         } else {
+            if self.mode(MODE_INSERT) {
+                self.grid_mut().insert_cells(width);
+            }
+
             if self
                 .grid()
                 .drawing_cell(pos)
@@ -970,6 +1924,9 @@ impl Screen {
                 // that self.grid().pos().col has a valid value.
                 .unwrap();
             cell.set(c, attrs);
+            self.grid_mut()
+                .current_row_mut()
+                .clip_images(pos.col, pos.col + width);
             self.grid_mut().col_inc(1);
             if width > 1 {
                 let pos = self.grid().pos();
@@ -1034,15 +1991,18 @@ impl Screen {
     // control codes
 
     pub(crate) fn bs(&mut self) {
-        self.grid_mut().col_dec(1);
+        self.cub(1);
     }
 
     pub(crate) fn tab(&mut self) {
-        self.grid_mut().col_tab();
+        self.grid_mut().col_tab(1);
     }
 
     pub(crate) fn lf(&mut self) {
         self.grid_mut().row_inc_scroll(1);
+        if self.mode(MODE_NEWLINE) {
+            self.cr();
+        }
     }
 
     pub(crate) fn vt(&mut self) {
@@ -1054,7 +2014,15 @@ impl Screen {
     }
 
     pub(crate) fn cr(&mut self) {
-        self.grid_mut().col_set(0);
+        self.grid_mut().col_left_margin();
+    }
+
+    pub(crate) fn so(&mut self) {
+        self.charsets.locking_shift(1);
+    }
+
+    pub(crate) fn si(&mut self) {
+        self.charsets.locking_shift(0);
     }
 
     // escape codes
@@ -1069,6 +2037,31 @@ impl Screen {
         self.restore_cursor();
     }
 
+    // ESC ( / ESC ) / ESC * / ESC +
+    pub(crate) fn scs(&mut self, g: u8, charset: crate::Charset) {
+        self.charsets.designate(g, charset);
+    }
+
+    // ESC N
+    pub(crate) fn ss2(&mut self) {
+        self.charsets.single_shift(2);
+    }
+
+    // ESC O
+    pub(crate) fn ss3(&mut self) {
+        self.charsets.single_shift(3);
+    }
+
+    // ESC n
+    pub(crate) fn ls2(&mut self) {
+        self.charsets.locking_shift(2);
+    }
+
+    // ESC o
+    pub(crate) fn ls3(&mut self) {
+        self.charsets.locking_shift(3);
+    }
+
     // ESC =
     pub(crate) fn deckpam(&mut self) {
         self.set_mode(MODE_APPLICATION_KEYPAD);
@@ -1079,6 +2072,11 @@ impl Screen {
         self.clear_mode(MODE_APPLICATION_KEYPAD);
     }
 
+    // ESC H
+    pub(crate) fn hts(&mut self) {
+        self.grid_mut().set_tab();
+    }
+
     // ESC M
     pub(crate) fn ri(&mut self) {
         self.grid_mut().row_dec_scroll(1);
@@ -1086,7 +2084,17 @@ impl Screen {
 
     // ESC c
     pub(crate) fn ris(&mut self) {
+        let identity = std::mem::take(&mut self.identity);
+        let default_colors = std::mem::take(&mut self.default_colors);
+        let cell_size = self.cell_size;
+        let paste_controls = self.paste_controls;
+        let synchronized_update_timeout = self.synchronized_update_timeout;
         *self = Self::new(self.grid.size(), self.grid.scrollback_len());
+        self.identity = identity;
+        self.default_colors = default_colors;
+        self.cell_size = cell_size;
+        self.paste_controls = paste_controls;
+        self.synchronized_update_timeout = synchronized_update_timeout;
     }
 
     // csi codes
@@ -1113,18 +2121,22 @@ impl Screen {
 
     // CSI D
     pub(crate) fn cub(&mut self, offset: u16) {
-        self.grid_mut().col_dec(offset);
+        if self.mode(MODE_AUTOWRAP) && self.mode(MODE_REVERSE_WRAPAROUND) {
+            self.grid_mut().col_dec_wrap(offset);
+        } else {
+            self.grid_mut().col_dec(offset);
+        }
     }
 
     // CSI E
     pub(crate) fn cnl(&mut self, offset: u16) {
-        self.grid_mut().col_set(0);
+        self.grid_mut().col_left_margin();
         self.grid_mut().row_inc_clamp(offset);
     }
 
     // CSI F
     pub(crate) fn cpl(&mut self, offset: u16) {
-        self.grid_mut().col_set(0);
+        self.grid_mut().col_left_margin();
         self.grid_mut().row_dec_clamp(offset);
     }
 
@@ -1141,6 +2153,11 @@ impl Screen {
         });
     }
 
+    // CSI I
+    pub(crate) fn cht(&mut self, count: u16) {
+        self.grid_mut().col_tab(count);
+    }
+
     // CSI J
     pub(crate) fn ed(
         &mut self,
@@ -1214,17 +2231,120 @@ impl Screen {
         self.grid_mut().scroll_down(count);
     }
 
+    // CSI T (with five parameters)
+    pub(crate) fn xthimouse(&mut self, params: &vte::Params) {
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| param.first().copied().unwrap_or(0))
+            .collect();
+        self.mouse_state.highlight(&params);
+    }
+
     // CSI X
     pub(crate) fn ech(&mut self, count: u16) {
         let attrs = self.attrs;
         self.grid_mut().erase_cells(count, attrs);
     }
 
+    // CSI Z
+    pub(crate) fn cbt(&mut self, count: u16) {
+        self.grid_mut().col_back_tab(count);
+    }
+
+    // CSI c
+    pub(crate) fn da1(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        if mode == 0 {
+            Some(self.identity.primary_attributes_report())
+        } else {
+            unhandled(self);
+            None
+        }
+    }
+
+    // CSI > c
+    pub(crate) fn da2(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        if mode == 0 {
+            Some(self.identity.secondary_attributes_report())
+        } else {
+            unhandled(self);
+            None
+        }
+    }
+
+    // CSI = c
+    pub(crate) fn da3(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        if mode == 0 {
+            Some(self.identity.tertiary_attributes_report())
+        } else {
+            unhandled(self);
+            None
+        }
+    }
+
     // CSI d
     pub(crate) fn vpa(&mut self, row: u16) {
         self.grid_mut().row_set(row - 1);
     }
 
+    // CSI g
+    pub(crate) fn tbc(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        match mode {
+            0 => self.grid_mut().clear_tab(),
+            3 => self.grid_mut().clear_all_tabs(),
+            _ => unhandled(self),
+        }
+    }
+
+    // CSI h
+    pub(crate) fn sm(
+        &mut self,
+        params: &vte::Params,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        for param in params {
+            match param {
+                [4] => self.set_mode(MODE_INSERT),
+                // SRM is permanently set, since there is no local echo
+                [12] => {}
+                [20] => self.set_mode(MODE_NEWLINE),
+                _ => unhandled(self),
+            }
+        }
+    }
+
+    // CSI l
+    pub(crate) fn rm(
+        &mut self,
+        params: &vte::Params,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        for param in params {
+            match param {
+                [4] => self.clear_mode(MODE_INSERT),
+                // SRM is permanently set, since there is no local echo
+                [12] => {}
+                [20] => self.clear_mode(MODE_NEWLINE),
+                _ => unhandled(self),
+            }
+        }
+    }
+
     // CSI ? h
     pub(crate) fn decset(
         &mut self,
@@ -1235,28 +2355,44 @@ impl Screen {
             match param {
                 [1] => self.set_mode(MODE_APPLICATION_CURSOR),
                 [6] => self.grid_mut().set_origin_mode(true),
+                [7] => self.set_mode(MODE_AUTOWRAP),
                 [9] => self.set_mouse_mode(MouseProtocolMode::Press),
+                [12] => self.set_mode(MODE_CURSOR_BLINK),
                 [25] => self.clear_mode(MODE_HIDE_CURSOR),
+                [45] => self.set_mode(MODE_REVERSE_WRAPAROUND),
                 [47] => self.enter_alternate_grid(),
+                [66] => self.set_mode(MODE_APPLICATION_KEYPAD),
+                [67] => self.set_mode(MODE_BACKARROW_KEY),
+                [69] => self.set_mode(MODE_LEFT_RIGHT_MARGIN),
+                [80] => self.set_mode(MODE_SIXEL_DISPLAY),
                 [1000] => {
                     self.set_mouse_mode(MouseProtocolMode::PressRelease);
                 }
+                [1001] => self.set_mouse_mode(MouseProtocolMode::Highlight),
                 [1002] => {
                     self.set_mouse_mode(MouseProtocolMode::ButtonMotion);
                 }
                 [1003] => self.set_mouse_mode(MouseProtocolMode::AnyMotion),
+                [1004] => self.set_mode(MODE_FOCUS_EVENTS),
                 [1005] => {
                     self.set_mouse_encoding(MouseProtocolEncoding::Utf8);
                 }
                 [1006] => {
                     self.set_mouse_encoding(MouseProtocolEncoding::Sgr);
                 }
+                [1015] => {
+                    self.set_mouse_encoding(MouseProtocolEncoding::Urxvt);
+                }
+                [1016] => {
+                    self.set_mouse_encoding(MouseProtocolEncoding::SgrPixels);
+                }
                 [1049] => {
                     self.decsc();
                     self.alternate_grid.clear();
                     self.enter_alternate_grid();
                 }
                 [2004] => self.set_mode(MODE_BRACKETED_PASTE),
+                [2026] => self.begin_synchronized_update(),
                 _ => unhandled(self),
             }
         }
@@ -1272,36 +2408,115 @@ impl Screen {
             match param {
                 [1] => self.clear_mode(MODE_APPLICATION_CURSOR),
                 [6] => self.grid_mut().set_origin_mode(false),
+                [7] => self.clear_mode(MODE_AUTOWRAP),
                 [9] => self.clear_mouse_mode(MouseProtocolMode::Press),
+                [12] => self.clear_mode(MODE_CURSOR_BLINK),
                 [25] => self.set_mode(MODE_HIDE_CURSOR),
+                [45] => self.clear_mode(MODE_REVERSE_WRAPAROUND),
                 [47] => {
                     self.exit_alternate_grid();
                 }
+                [69] => {
+                    self.clear_mode(MODE_LEFT_RIGHT_MARGIN);
+                    self.grid.clear_horizontal_margins();
+                    self.alternate_grid.clear_horizontal_margins();
+                }
+                [66] => self.clear_mode(MODE_APPLICATION_KEYPAD),
+                [67] => self.clear_mode(MODE_BACKARROW_KEY),
+                [80] => self.clear_mode(MODE_SIXEL_DISPLAY),
                 [1000] => {
                     self.clear_mouse_mode(MouseProtocolMode::PressRelease);
                 }
+                [1001] => {
+                    self.clear_mouse_mode(MouseProtocolMode::Highlight);
+                }
                 [1002] => {
                     self.clear_mouse_mode(MouseProtocolMode::ButtonMotion);
                 }
                 [1003] => {
                     self.clear_mouse_mode(MouseProtocolMode::AnyMotion);
                 }
+                [1004] => self.clear_mode(MODE_FOCUS_EVENTS),
                 [1005] => {
                     self.clear_mouse_encoding(MouseProtocolEncoding::Utf8);
                 }
                 [1006] => {
                     self.clear_mouse_encoding(MouseProtocolEncoding::Sgr);
                 }
+                [1015] => {
+                    self.clear_mouse_encoding(MouseProtocolEncoding::Urxvt);
+                }
+                [1016] => {
+                    self.clear_mouse_encoding(
+                        MouseProtocolEncoding::SgrPixels,
+                    );
+                }
                 [1049] => {
                     self.exit_alternate_grid();
                     self.decrc();
                 }
                 [2004] => self.clear_mode(MODE_BRACKETED_PASTE),
+                [2026] => self.synchronized_update = None,
                 _ => unhandled(self),
             }
         }
     }
 
+    // CSI $ p
+    pub(crate) fn decrqm(&self, mode: u16) -> Vec<u8> {
+        let state = match mode {
+            4 => decrpm_state(self.mode(MODE_INSERT)),
+            20 => decrpm_state(self.mode(MODE_NEWLINE)),
+            // keyboard action mode
+            2 => DECRPM_PERMANENTLY_RESET,
+            // send/receive mode (we never echo input locally)
+            12 => DECRPM_PERMANENTLY_SET,
+            _ => DECRPM_NOT_RECOGNIZED,
+        };
+        format!("\x1b[{mode};{state}$y").into_bytes()
+    }
+
+    // CSI ? $ p
+    pub(crate) fn decrqm_private(&self, mode: u16) -> Vec<u8> {
+        let mouse_mode =
+            |mouse_mode| decrpm_state(self.mouse_protocol_mode == mouse_mode);
+        let mouse_encoding = |mouse_encoding| {
+            decrpm_state(self.mouse_protocol_encoding == mouse_encoding)
+        };
+        let state = match mode {
+            1 => decrpm_state(self.mode(MODE_APPLICATION_CURSOR)),
+            6 => decrpm_state(self.grid().origin_mode()),
+            7 => decrpm_state(self.mode(MODE_AUTOWRAP)),
+            9 => mouse_mode(MouseProtocolMode::Press),
+            12 => decrpm_state(self.mode(MODE_CURSOR_BLINK)),
+            25 => decrpm_state(!self.mode(MODE_HIDE_CURSOR)),
+            45 => decrpm_state(self.mode(MODE_REVERSE_WRAPAROUND)),
+            47 | 1049 => decrpm_state(self.mode(MODE_ALTERNATE_SCREEN)),
+            66 => decrpm_state(self.mode(MODE_APPLICATION_KEYPAD)),
+            67 => decrpm_state(self.mode(MODE_BACKARROW_KEY)),
+            69 => decrpm_state(self.mode(MODE_LEFT_RIGHT_MARGIN)),
+            80 => decrpm_state(self.mode(MODE_SIXEL_DISPLAY)),
+            1000 => mouse_mode(MouseProtocolMode::PressRelease),
+            1001 => mouse_mode(MouseProtocolMode::Highlight),
+            1002 => mouse_mode(MouseProtocolMode::ButtonMotion),
+            1003 => mouse_mode(MouseProtocolMode::AnyMotion),
+            1004 => decrpm_state(self.mode(MODE_FOCUS_EVENTS)),
+            1005 => mouse_encoding(MouseProtocolEncoding::Utf8),
+            1006 => mouse_encoding(MouseProtocolEncoding::Sgr),
+            1015 => mouse_encoding(MouseProtocolEncoding::Urxvt),
+            1016 => mouse_encoding(MouseProtocolEncoding::SgrPixels),
+            2004 => decrpm_state(self.mode(MODE_BRACKETED_PASTE)),
+            2026 => decrpm_state(self.synchronized_output()),
+            // ansi mode (vt52 mode is not supported)
+            2 => DECRPM_PERMANENTLY_SET,
+            // 132 column mode, smooth scrolling, reverse video, and grapheme
+            // clustering are not supported
+            3 | 4 | 5 | 2027 => DECRPM_PERMANENTLY_RESET,
+            _ => DECRPM_NOT_RECOGNIZED,
+        };
+        format!("\x1b[?{mode};{state}$y").into_bytes()
+    }
+
     // CSI m
     pub(crate) fn sgr(
         &mut self,
@@ -1312,7 +2527,7 @@ impl Screen {
         // instance with a 0 in it, but vte doesn't allow creating new Params
         // instances
         if params.is_empty() {
-            self.attrs = crate::attrs::Attrs::default();
+            self.attrs.clear_sgr();
             return;
         }
 
@@ -1349,25 +2564,49 @@ impl Screen {
 
         loop {
             match next_param!() {
-                [0] => self.attrs = crate::attrs::Attrs::default(),
+                [0] => self.attrs.clear_sgr(),
                 [1] => self.attrs.set_bold(),
                 [2] => self.attrs.set_dim(),
                 [3] => self.attrs.set_italic(true),
-                [4] => self.attrs.set_underline(true),
+                [4] => {
+                    self.attrs
+                        .set_underline_style(crate::UnderlineStyle::Single);
+                }
+                [4, n] => {
+                    if let Some(style) = crate::UnderlineStyle::from_param(*n)
+                    {
+                        self.attrs.set_underline_style(style);
+                    } else {
+                        unhandled(self);
+                    }
+                }
+                [5 | 6] => self.attrs.set_blink(true),
                 [7] => self.attrs.set_inverse(true),
+                [8] => self.attrs.set_conceal(true),
+                [9] => self.attrs.set_strikethrough(true),
+                [21] => {
+                    self.attrs
+                        .set_underline_style(crate::UnderlineStyle::Double);
+                }
                 [22] => self.attrs.set_normal_intensity(),
                 [23] => self.attrs.set_italic(false),
-                [24] => self.attrs.set_underline(false),
+                [24] => {
+                    self.attrs
+                        .set_underline_style(crate::UnderlineStyle::None);
+                }
+                [25] => self.attrs.set_blink(false),
                 [27] => self.attrs.set_inverse(false),
+                [28] => self.attrs.set_conceal(false),
+                [29] => self.attrs.set_strikethrough(false),
                 [n] if (30..=37).contains(n) => {
                     self.attrs.fgcolor = crate::Color::Idx(to_u8!(*n) - 30);
                 }
-                [38, 2, r, g, b] => {
-                    self.attrs.fgcolor =
-                        crate::Color::Rgb(to_u8!(*r), to_u8!(*g), to_u8!(*b));
-                }
-                [38, 5, i] => {
-                    self.attrs.fgcolor = crate::Color::Idx(to_u8!(*i));
+                [38, subparams @ ..] if !subparams.is_empty() => {
+                    if let Some(color) = subparam_color(subparams) {
+                        self.attrs.fgcolor = color;
+                    } else {
+                        unhandled(self);
+                    }
                 }
                 [38] => match next_param!() {
                     [2] => {
@@ -1391,12 +2630,12 @@ impl Screen {
                 [n] if (40..=47).contains(n) => {
                     self.attrs.bgcolor = crate::Color::Idx(to_u8!(*n) - 40);
                 }
-                [48, 2, r, g, b] => {
-                    self.attrs.bgcolor =
-                        crate::Color::Rgb(to_u8!(*r), to_u8!(*g), to_u8!(*b));
-                }
-                [48, 5, i] => {
-                    self.attrs.bgcolor = crate::Color::Idx(to_u8!(*i));
+                [48, subparams @ ..] if !subparams.is_empty() => {
+                    if let Some(color) = subparam_color(subparams) {
+                        self.attrs.bgcolor = color;
+                    } else {
+                        unhandled(self);
+                    }
                 }
                 [48] => match next_param!() {
                     [2] => {
@@ -1417,6 +2656,35 @@ impl Screen {
                 [49] => {
                     self.attrs.bgcolor = crate::Color::Default;
                 }
+                [53] => self.attrs.set_overline(true),
+                [55] => self.attrs.set_overline(false),
+                [58, subparams @ ..] if !subparams.is_empty() => {
+                    if let Some(color) = subparam_color(subparams) {
+                        self.attrs.underline_color = color;
+                    } else {
+                        unhandled(self);
+                    }
+                }
+                [58] => match next_param!() {
+                    [2] => {
+                        let r = next_param_u8!();
+                        let g = next_param_u8!();
+                        let b = next_param_u8!();
+                        self.attrs.underline_color =
+                            crate::Color::Rgb(r, g, b);
+                    }
+                    [5] => {
+                        self.attrs.underline_color =
+                            crate::Color::Idx(next_param_u8!());
+                    }
+                    _ => {
+                        unhandled(self);
+                        return;
+                    }
+                },
+                [59] => {
+                    self.attrs.underline_color = crate::Color::Default;
+                }
                 [n] if (90..=97).contains(n) => {
                     self.attrs.fgcolor = crate::Color::Idx(to_u8!(*n) - 82);
                 }
@@ -1428,10 +2696,792 @@ impl Screen {
         }
     }
 
+    // CSI n
+    pub(crate) fn dsr(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        match mode {
+            5 => Some(b"\x1b[0n".to_vec()),
+            6 => {
+                let (row, col) = self.grid().report_pos();
+                Some(format!("\x1b[{};{}R", row + 1, col + 1).into_bytes())
+            }
+            _ => {
+                unhandled(self);
+                None
+            }
+        }
+    }
+
+    // CSI ? n
+    pub(crate) fn decdsr(
+        &mut self,
+        mode: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        if mode == 6 {
+            let (row, col) = self.grid().report_pos();
+            Some(format!("\x1b[?{};{};1R", row + 1, col + 1).into_bytes())
+        } else {
+            unhandled(self);
+            None
+        }
+    }
+
+    // CSI SP q
+    pub(crate) fn decscusr(
+        &mut self,
+        style: u16,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        if let Some(style) = CursorStyle::from_param(style) {
+            self.cursor_style = style;
+        } else {
+            unhandled(self);
+        }
+    }
+
+    // CSI > m
+    pub(crate) fn xtmodkeys(
+        &mut self,
+        params: &vte::Params,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        // only modifyOtherKeys is tracked, since the other resources this
+        // can set (modifyCursorKeys and so on) are rarely changed. leaving
+        // out the value resets it.
+        let mut params = params.iter().map(|param| param.first().copied());
+        match (params.next(), params.next(), params.next()) {
+            (Some(Some(4)), None, None) => self.modify_other_keys = 0,
+            (Some(Some(4)), Some(level), None) => match level.unwrap_or(0) {
+                level @ 0..=2 => {
+                    self.modify_other_keys = level.try_into().unwrap();
+                }
+                _ => unhandled(self),
+            },
+            _ => unhandled(self),
+        }
+    }
+
+    // CSI ' z
+    pub(crate) fn decelr(
+        &mut self,
+        (enable, units): (u16, u16),
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        let pixels = match units {
+            0 | 2 => false,
+            1 => true,
+            _ => {
+                unhandled(self);
+                return;
+            }
+        };
+        match enable {
+            0 => self.clear_mouse_mode(MouseProtocolMode::DecLocator),
+            1 | 2 => {
+                self.set_mouse_mode(MouseProtocolMode::DecLocator);
+                self.locator.enable(enable == 2, pixels);
+            }
+            _ => unhandled(self),
+        }
+    }
+
+    // CSI ' {
+    pub(crate) fn decsle(
+        &mut self,
+        params: &vte::Params,
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| param.first().copied().unwrap_or(0))
+            .collect();
+        if !self.locator.select_events(&params) {
+            unhandled(self);
+        }
+    }
+
+    // CSI ' w
+    pub(crate) fn decefr(&mut self, params: &vte::Params) -> Option<Vec<u8>> {
+        if self.mouse_protocol_mode != MouseProtocolMode::DecLocator {
+            return None;
+        }
+        let mut rect = [0; 4];
+        for (edge, param) in rect.iter_mut().zip(params.iter()) {
+            *edge = param.first().copied().unwrap_or(0);
+        }
+        let report = self.locator.set_filter(rect)?;
+        if self.locator.one_shot() {
+            self.clear_mouse_mode(MouseProtocolMode::DecLocator);
+        }
+        Some(report)
+    }
+
+    // CSI ' |
+    pub(crate) fn decrqlp(&mut self) -> Option<Vec<u8>> {
+        if self.mouse_protocol_mode != MouseProtocolMode::DecLocator {
+            return None;
+        }
+        let report = self.locator.position_report();
+        if self.locator.one_shot() {
+            self.clear_mouse_mode(MouseProtocolMode::DecLocator);
+        }
+        Some(report)
+    }
+
+    // CSI > u
+    pub(crate) fn push_keyboard_flags(&mut self, flags: u16) {
+        self.grid_mut()
+            .keyboard_flags_mut()
+            .push(u8::try_from(flags).unwrap_or(u8::MAX));
+    }
+
+    // CSI < u
+    pub(crate) fn pop_keyboard_flags(&mut self, count: u16) {
+        self.grid_mut().keyboard_flags_mut().pop(usize::from(count));
+    }
+
+    // CSI = u
+    pub(crate) fn set_keyboard_flags(
+        &mut self,
+        (flags, mode): (u16, u16),
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        let flags = u8::try_from(flags).unwrap_or(u8::MAX);
+        if !self.grid_mut().keyboard_flags_mut().set(flags, mode) {
+            unhandled(self);
+        }
+    }
+
+    // CSI ? u
+    pub(crate) fn query_keyboard_flags(&self) -> Vec<u8> {
+        format!("\x1b[?{}u", self.keyboard_flags()).into_bytes()
+    }
+
     // CSI r
     pub(crate) fn decstbm(&mut self, (top, bottom): (u16, u16)) {
         self.grid_mut().set_scroll_region(top - 1, bottom - 1);
     }
+
+    // CSI s (without left/right margin mode)
+    pub(crate) fn scosc(&mut self) {
+        self.save_cursor();
+    }
+
+    // CSI s (with left/right margin mode)
+    pub(crate) fn decslrm(&mut self, (left, right): (u16, u16)) {
+        self.grid_mut().set_horizontal_margins(left - 1, right - 1);
+    }
+
+    // CSI u
+    pub(crate) fn scorc(&mut self) {
+        self.restore_cursor();
+    }
+
+    // osc codes
+
+    // OSC 4
+    pub(crate) fn osc4(
+        &mut self,
+        params: &[&[u8]],
+        bel_terminated: bool,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        if params.len() % 2 != 0 {
+            unhandled(self);
+            return None;
+        }
+        let mut replies = vec![];
+        for pair in params.chunks(2) {
+            let Some(idx) = parse_osc_u8(pair[0]) else {
+                unhandled(self);
+                continue;
+            };
+            if pair[1] == b"?" {
+                replies.extend(
+                    format!(
+                        "\x1b]4;{idx};{}",
+                        rgb_report(self.palette_color(idx))
+                    )
+                    .as_bytes(),
+                );
+                replies.extend(osc_terminator(bel_terminated));
+            } else if let Some(rgb) = crate::palette::parse_color(pair[1]) {
+                self.palette.set_indexed(idx, Some(rgb));
+            } else {
+                unhandled(self);
+            }
+        }
+        (!replies.is_empty()).then_some(replies)
+    }
+
+    // OSC 7
+    pub(crate) fn osc7(
+        &mut self,
+        uri: &[u8],
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        if let Ok(uri) = std::str::from_utf8(uri) {
+            self.cwd = Some(uri.to_string());
+        } else {
+            unhandled(self);
+        }
+    }
+
+    // OSC 10, OSC 11, OSC 12
+    pub(crate) fn osc10(
+        &mut self,
+        ps: u16,
+        params: &[&[u8]],
+        bel_terminated: bool,
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<Vec<u8>> {
+        let mut replies = vec![];
+        // each additional parameter applies to the next color in sequence
+        let mut color = crate::palette::DynamicColor::from_osc(ps);
+        for param in params {
+            let Some(current) = color else {
+                unhandled(self);
+                break;
+            };
+            if *param == b"?" {
+                replies.extend(
+                    format!(
+                        "\x1b]{};{}",
+                        current.osc(),
+                        rgb_report(self.dynamic_color(current))
+                    )
+                    .as_bytes(),
+                );
+                replies.extend(osc_terminator(bel_terminated));
+            } else if let Some(rgb) = crate::palette::parse_color(param) {
+                self.palette.set_dynamic(current, Some(rgb));
+            } else {
+                unhandled(self);
+            }
+            color = current.next();
+        }
+        (!replies.is_empty()).then_some(replies)
+    }
+
+    // OSC 99
+    pub(crate) fn osc99(
+        &mut self,
+        metadata: &[u8],
+        payload: &[u8],
+        mut unhandled: impl FnMut(&mut Self),
+    ) -> Option<crate::Notification> {
+        let Some(metadata) = crate::notification::Metadata::parse(metadata)
+        else {
+            unhandled(self);
+            return None;
+        };
+        let decoded;
+        let payload = if metadata.base64 {
+            let Some(payload) = crate::base64::decode(payload) else {
+                unhandled(self);
+                return None;
+            };
+            decoded = payload;
+            &decoded
+        } else {
+            payload
+        };
+
+        // a chunk for a different notification means that the pending one
+        // is never going to be finished
+        let mut pending = self
+            .pending_notification
+            .take()
+            .filter(|pending| pending.id() == metadata.id.as_deref())
+            .unwrap_or_else(|| metadata.start());
+        metadata.apply(&mut pending, payload);
+        if metadata.done {
+            Some(pending.finish())
+        } else {
+            self.pending_notification = Some(pending);
+            None
+        }
+    }
+
+    // OSC 104
+    pub(crate) fn osc104(
+        &mut self,
+        params: &[&[u8]],
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        if params.iter().all(|param| param.is_empty()) {
+            self.palette.reset_all_indexed();
+            return;
+        }
+        for param in params.iter().filter(|param| !param.is_empty()) {
+            if let Some(idx) = parse_osc_u8(param) {
+                self.palette.set_indexed(idx, None);
+            } else {
+                unhandled(self);
+            }
+        }
+    }
+
+    // OSC 110, OSC 111, OSC 112
+    pub(crate) fn osc110(&mut self, ps: u16) {
+        if let Some(color) = crate::palette::DynamicColor::from_osc(ps - 100)
+        {
+            self.palette.set_dynamic(color, None);
+        }
+    }
+
+    // OSC 8
+    pub(crate) fn osc8(
+        &mut self,
+        params: &[u8],
+        uri: &[u8],
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        let (Ok(params), Ok(uri)) =
+            (std::str::from_utf8(params), std::str::from_utf8(uri))
+        else {
+            unhandled(self);
+            return;
+        };
+        if uri.len() > crate::hyperlink::MAX_URI_LEN {
+            unhandled(self);
+            return;
+        }
+        self.attrs.hyperlink = if uri.is_empty() {
+            None
+        } else {
+            let link = crate::hyperlink::Hyperlink::new(params, uri);
+            self.hyperlinks.intern(link.clone()).or_else(|| {
+                self.reclaim_hyperlinks();
+                // if every link is still in use, text is drawn without a
+                // link instead
+                self.hyperlinks.intern(link)
+            })
+        };
+    }
+
+    fn reclaim_hyperlinks(&mut self) {
+        let in_use = self
+            .grid
+            .hyperlinks()
+            .chain(self.alternate_grid.hyperlinks())
+            .chain(self.attrs.hyperlink)
+            .chain(self.saved_attrs.hyperlink)
+            .collect::<Vec<_>>();
+        self.hyperlinks.reclaim(in_use);
+    }
+
+    // OSC 133
+    pub(crate) fn osc133(
+        &mut self,
+        params: &[&[u8]],
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        if let Some(marker) = crate::prompt::Marker::from_params(params) {
+            let col = self.grid().pos().col;
+            self.grid_mut().current_row_mut().add_marker(col, marker);
+        } else {
+            unhandled(self);
+        }
+    }
+
+    // OSC 1337
+    pub(crate) fn osc1337(
+        &mut self,
+        command: &[u8],
+        mut unhandled: impl FnMut(&mut Self),
+    ) {
+        // files which aren't inline are meant to be downloaded, which is up
+        // to the application using this library
+        let Some(file) = crate::iterm::File::parse(command)
+            .filter(crate::iterm::File::inline)
+        else {
+            unhandled(self);
+            return;
+        };
+        let size = self.grid().size();
+        let screen = (
+            u32::from(size.cols) * u32::from(self.cell_size.0),
+            u32::from(size.rows) * u32::from(self.cell_size.1),
+        );
+        let Some((image, dest)) = file.into_image(self.cell_size, screen)
+        else {
+            unhandled(self);
+            return;
+        };
+        let placement = crate::image::Placement::scaled(
+            std::sync::Arc::new(image),
+            self.cell_size,
+            dest,
+        );
+
+        // like iterm2, the cursor ends up on the last row of the image, just
+        // after its last column
+        let col = self.grid().pos().col;
+        let (_, cols) = self.draw_image_scrolling(placement);
+        self.grid_mut().col_set(col.saturating_add(cols));
+    }
+
+    // dcs codes
+
+    // DCS q
+    pub(crate) fn sixel(&mut self, image: crate::Image) {
+        let placement = crate::image::Placement::new(
+            std::sync::Arc::new(image),
+            self.cell_size,
+        );
+
+        if self.mode(MODE_SIXEL_DISPLAY) {
+            // in sixel display mode, images are drawn at the top left of the
+            // screen, are cut off at the bottom of the screen rather than
+            // scrolling, and don't move the cursor
+            self.draw_image(placement, crate::grid::Pos { row: 0, col: 0 });
+            return;
+        }
+
+        // otherwise, images are drawn at the cursor and scroll the screen
+        // as needed. like xterm, the cursor ends up on the line after the
+        // image, in the column that the image started in.
+        let col = self.grid().pos().col;
+        self.draw_image_scrolling(placement);
+        self.grid_mut().row_inc_scroll(1);
+        self.grid_mut().col_set(col);
+    }
+
+    // apc codes
+
+    // APC G
+    pub(crate) fn kitty_graphics(&mut self, data: &[u8]) -> Option<Vec<u8>> {
+        let mut parts = data.splitn(2, |&c| c == b';');
+        let control = parts.next().unwrap_or_default();
+        let payload = parts.next().unwrap_or_default();
+        // we can't respond if we don't know what the image id is
+        let command = crate::kitty::Command::parse(control)?;
+        let (command, payload) =
+            self.kitty_images.receive(command, payload)?;
+        if command.action == b'd' {
+            // deletions are never responded to
+            self.kitty_delete(&command);
+            return None;
+        }
+
+        let mut image_id = command.image_id;
+        let result = payload.and_then(|payload| match command.action {
+            b't' | b'T' | b'q' => {
+                let image = command.decode(&payload)?;
+                if command.action != b'q' {
+                    if command.image_id != 0 {
+                        // retransmitting an image replaces it entirely
+                        self.delete_kitty_placements(|placement| {
+                            placement.kitty_id().map(|(id, _)| id)
+                                == Some(command.image_id)
+                        });
+                    }
+                    image_id = self.kitty_images.insert(
+                        command.image_id,
+                        command.image_number,
+                        image,
+                    );
+                }
+                if command.action == b'T' {
+                    self.kitty_put(&command, image_id)?;
+                }
+                Ok(())
+            }
+            b'p' => {
+                if command.image_id == 0 && command.image_number != 0 {
+                    image_id = self
+                        .kitty_images
+                        .id_for_number(command.image_number)
+                        .unwrap_or(0);
+                }
+                self.kitty_put(&command, image_id)
+            }
+            _ => {
+                Err(crate::kitty::Error::new("EINVAL", "unsupported action"))
+            }
+        });
+        command.response(image_id, result)
+    }
+
+    fn kitty_put(
+        &mut self,
+        command: &crate::kitty::Command,
+        image_id: u32,
+    ) -> Result<(), crate::kitty::Error> {
+        if command.virtual_placement {
+            return Err(crate::kitty::Error::new(
+                "EINVAL",
+                "unicode placeholders are not supported",
+            ));
+        }
+        let Some(image) = self.kitty_images.get(image_id).cloned() else {
+            return Err(crate::kitty::Error::new(
+                "ENOENT",
+                "image not found",
+            ));
+        };
+        if command.placement_id != 0 {
+            // placing an image with an existing placement id moves it
+            self.delete_kitty_placements(|placement| {
+                placement.kitty_id() == Some((image_id, command.placement_id))
+            });
+        }
+        let (source, dest) = command.placement_rects(&image, self.cell_size);
+        let placement = crate::image::Placement::kitty(
+            image,
+            self.cell_size,
+            source,
+            dest,
+            command.z_index,
+            (image_id, command.placement_id),
+        );
+        if command.move_cursor {
+            // the cursor ends up on the last row of the image, just after
+            // its last column
+            let col = self.grid().pos().col;
+            let (_, cols) = self.draw_image_scrolling(placement);
+            self.grid_mut().col_set(col.saturating_add(cols));
+        } else {
+            self.draw_image(placement, self.grid().pos());
+        }
+        Ok(())
+    }
+
+    fn kitty_delete(&mut self, command: &crate::kitty::Command) {
+        // deleting by id affects placements anywhere, but the other forms
+        // only affect placements which are visible on the screen
+        let delete = command.delete.to_ascii_lowercase();
+        let ids = match delete {
+            b'i' => Some((command.image_id, command.image_id)),
+            b'n' => self
+                .kitty_images
+                .id_for_number(command.image_number)
+                .map(|id| (id, id)),
+            b'r' => Some((command.source.0, command.source.1)),
+            _ => None,
+        };
+        let deleted: Vec<_> = if let Some((first, last)) = ids {
+            let placement_id = command.placement_id;
+            self.delete_kitty_placements(|placement| {
+                placement.kitty_id().is_some_and(|(id, p)| {
+                    (first..=last).contains(&id)
+                        && (delete == b'r'
+                            || placement_id == 0
+                            || p == placement_id)
+                })
+            });
+            self.kitty_images
+                .ids()
+                .filter(|id| (first..=last).contains(id))
+                .collect()
+        } else {
+            // cell positions in delete commands are 1-based
+            let cursor = self.grid().pos();
+            let cell = (
+                command.source.1.saturating_sub(1),
+                command.source.0.saturating_sub(1),
+            );
+            let covers = |row: u16, fragment: &crate::image::Fragment| {
+                let row = u32::from(row);
+                match delete {
+                    b'a' | b'z' => true,
+                    b'c' => {
+                        row == u32::from(cursor.row)
+                            && fragment.covers(cursor.col)
+                    }
+                    b'p' | b'q' => {
+                        row == cell.0
+                            && u16::try_from(cell.1)
+                                .is_ok_and(|col| fragment.covers(col))
+                    }
+                    b'x' => u16::try_from(cell.1)
+                        .is_ok_and(|col| fragment.covers(col)),
+                    b'y' => row == cell.0,
+                    _ => false,
+                }
+            };
+            let z_index =
+                matches!(delete, b'q' | b'z').then_some(command.z_index);
+            let mut matching = vec![];
+            for (row, fragment) in
+                self.grid().drawing_rows().enumerate().flat_map(|(row, r)| {
+                    r.images().iter().map(move |f| (row, f))
+                })
+            {
+                // we limit the number of rows to a u16 (see Size)
+                let row: u16 = row.try_into().unwrap();
+                let placement = fragment.placement();
+                if placement.kitty_id().is_some()
+                    && z_index.map_or(true, |z| placement.z_index() == z)
+                    && covers(row, fragment)
+                {
+                    matching.push(std::sync::Arc::as_ptr(placement));
+                }
+            }
+            self.delete_kitty_placements(|placement| {
+                matching.iter().any(|&p| std::ptr::eq(p, placement))
+            })
+        };
+
+        // upper case variants also delete the image data, once it's no
+        // longer displayed anywhere
+        if command.delete.is_ascii_uppercase() {
+            let mut in_use = vec![];
+            for grid in [&self.grid, &self.alternate_grid] {
+                for row in grid.all_rows() {
+                    in_use.extend(row.images().iter().filter_map(
+                        |fragment| {
+                            fragment.placement().kitty_id().map(|(id, _)| id)
+                        },
+                    ));
+                }
+            }
+            for id in deleted {
+                if !in_use.contains(&id) {
+                    self.kitty_images.remove(id);
+                }
+            }
+        }
+    }
+
+    // removes the matching placements from the current screen, returning
+    // the ids of the images which they displayed
+    fn delete_kitty_placements(
+        &mut self,
+        mut f: impl FnMut(&crate::image::Placement) -> bool,
+    ) -> Vec<u32> {
+        let mut deleted = vec![];
+        for row in self.grid_mut().all_rows_mut() {
+            row.retain_images(|fragment| {
+                let placement = fragment.placement();
+                if f(placement) {
+                    deleted.extend(placement.kitty_id().map(|(id, _)| id));
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        deleted
+    }
+
+    // draws an image at the cursor, scrolling the screen as needed to fit
+    // it. the cursor is left on the last row of the image. returns the
+    // number of (rows, cols) that the image covers.
+    fn draw_image_scrolling(
+        &mut self,
+        placement: crate::image::Placement,
+    ) -> (u16, u16) {
+        let (rows, cols) = placement.cells();
+        let placement = std::sync::Arc::new(placement);
+        let size = self.grid().size();
+        let col = self.grid().pos().col.min(size.cols - 1);
+        for placement_row in 0..rows {
+            if placement_row > 0 {
+                self.grid_mut().row_inc_scroll(1);
+            }
+            let fragment = crate::image::Fragment::new(
+                placement.clone(),
+                col,
+                cols.min(size.cols - col),
+                placement_row,
+            );
+            self.grid_mut().current_row_mut().add_image(fragment);
+        }
+        (rows, cols)
+    }
+
+    // draws an image starting at the given position, cutting it off at the
+    // bottom of the screen rather than scrolling
+    fn draw_image(
+        &mut self,
+        placement: crate::image::Placement,
+        pos: crate::grid::Pos,
+    ) {
+        let (rows, cols) = placement.cells();
+        let placement = std::sync::Arc::new(placement);
+        let size = self.grid().size();
+        let col = pos.col.min(size.cols - 1);
+        for placement_row in 0..rows.min(size.rows - pos.row) {
+            let fragment = crate::image::Fragment::new(
+                placement.clone(),
+                col,
+                cols.min(size.cols - col),
+                placement_row,
+            );
+            self.grid_mut()
+                .drawing_row_mut(pos.row + placement_row)
+                // we just limited the row to the size of the screen
+                .unwrap()
+                .add_image(fragment);
+        }
+    }
+}
+
+fn parse_osc_u8(param: &[u8]) -> Option<u8> {
+    std::str::from_utf8(param).ok()?.parse().ok()
+}
+
+// colors are reported with four hex digits per component, like xterm does
+fn rgb_report((r, g, b): (u8, u8, u8)) -> String {
+    let (r, g, b) =
+        (u16::from(r) * 257, u16::from(g) * 257, u16::from(b) * 257);
+    format!("rgb:{r:04x}/{g:04x}/{b:04x}")
+}
+
+// replies to osc queries use the same terminator as the query did
+fn osc_terminator(bel_terminated: bool) -> &'static [u8] {
+    if bel_terminated {
+        b"\x07"
+    } else {
+        b"\x1b\\"
+    }
+}
+
+fn decrpm_state(set: bool) -> u8 {
+    if set {
+        DECRPM_SET
+    } else {
+        DECRPM_RESET
+    }
+}
+
+// parses the colon separated form of the color parameters to sgr 38, 48 and
+// 58, as described in itu t.416. the color space id is optional, since most
+// terminals also accept it being left out entirely, and the trailing
+// tolerance parameters are ignored.
+fn subparam_color(subparams: &[u16]) -> Option<crate::Color> {
+    match *subparams {
+        [2, r, g, b] | [2, _, r, g, b, ..] => Some(crate::Color::Rgb(
+            u16_to_u8(r)?,
+            u16_to_u8(g)?,
+            u16_to_u8(b)?,
+        )),
+        [3, c, m, y] | [3, _, c, m, y, ..] => Some(crate::Color::Rgb(
+            u8::MAX - u16_to_u8(c)?,
+            u8::MAX - u16_to_u8(m)?,
+            u8::MAX - u16_to_u8(y)?,
+        )),
+        [4, c, m, y, k] | [4, _, c, m, y, k, ..] => {
+            let black = u16::from(u8::MAX - u16_to_u8(k)?);
+            let component = |value| -> Option<u8> {
+                let value = u16::from(u8::MAX - u16_to_u8(value)?);
+                u16_to_u8(value * black / u16::from(u8::MAX))
+            };
+            Some(crate::Color::Rgb(
+                component(c)?,
+                component(m)?,
+                component(y)?,
+            ))
+        }
+        [5, i] => Some(crate::Color::Idx(u16_to_u8(i)?)),
+        _ => None,
+    }
 }
 
 fn u16_to_u8(i: u16) -> Option<u8> {
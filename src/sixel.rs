@@ -0,0 +1,307 @@
+// images larger than this are clipped, so that a malicious or broken
+// application can't make us allocate arbitrary amounts of memory
+const MAX_WIDTH: usize = 4096;
+const MAX_HEIGHT: usize = 4096;
+
+const COLOR_REGISTERS: usize = 256;
+
+// the default color registers of a vt340, as percentages of red, green and
+// blue. the remaining registers start out black.
+const DEFAULT_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum State {
+    Data,
+    Repeat,
+    Color,
+    Raster,
+}
+
+// decodes the data section of a sixel DCS sequence (everything between the
+// `q` and the string terminator) into an rgba bitmap
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    state: State,
+    params: Vec<u32>,
+
+    registers: [[u8; 4]; COLOR_REGISTERS],
+    color: usize,
+    background: [u8; 4],
+
+    // pixels which have not been drawn are None, and are filled in with the
+    // background when the image is finished
+    pixels: Vec<Option<[u8; 4]>>,
+    stride: usize,
+    width: usize,
+    height: usize,
+
+    x: usize,
+    y: usize,
+}
+
+impl Decoder {
+    // params are the parameters of the DCS sequence. the only one we care
+    // about is the second, which selects whether pixels which aren't drawn
+    // are transparent (1) or filled with the background color (0 or 2).
+    pub fn new(params: &vte::Params, background: (u8, u8, u8)) -> Self {
+        let transparent =
+            params.iter().nth(1).and_then(|p| p.first()) == Some(&1);
+        let mut registers = [[0, 0, 0, 255]; COLOR_REGISTERS];
+        for (register, (r, g, b)) in registers.iter_mut().zip(DEFAULT_COLORS)
+        {
+            *register = [
+                percent(u32::from(r)),
+                percent(u32::from(g)),
+                percent(u32::from(b)),
+                255,
+            ];
+        }
+        Self {
+            state: State::Data,
+            params: vec![],
+
+            registers,
+            color: 0,
+            background: if transparent {
+                [0, 0, 0, 0]
+            } else {
+                [background.0, background.1, background.2, 255]
+            },
+
+            pixels: vec![],
+            stride: 0,
+            width: 0,
+            height: 0,
+
+            x: 0,
+            y: 0,
+        }
+    }
+
+    pub fn put(&mut self, b: u8) {
+        if self.state != State::Data {
+            match b {
+                b'0'..=b'9' => {
+                    let param = if let Some(param) = self.params.last_mut() {
+                        param
+                    } else {
+                        self.params.push(0);
+                        // we just pushed a value
+                        self.params.last_mut().unwrap()
+                    };
+                    *param = param
+                        .saturating_mul(10)
+                        .saturating_add(u32::from(b - b'0'));
+                    return;
+                }
+                b';' => {
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    self.params.push(0);
+                    return;
+                }
+                _ => {
+                    // a repeat command is terminated by the sixel that it
+                    // repeats, but anything else ends a command and is then
+                    // processed as normal
+                    let consumed = self.state == State::Repeat
+                        && matches!(b, b'?'..=b'~');
+                    self.finish_command(b);
+                    self.state = State::Data;
+                    if consumed {
+                        return;
+                    }
+                }
+            }
+        }
+
+        match b {
+            b'?'..=b'~' => self.sixel(b - b'?', 1),
+            b'!' => self.start_command(State::Repeat),
+            b'#' => self.start_command(State::Color),
+            b'"' => self.start_command(State::Raster),
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y = self.y.saturating_add(6);
+            }
+            // everything else (including whitespace) is ignored
+            _ => {}
+        }
+    }
+
+    pub fn finish(mut self) -> Option<crate::Image> {
+        if self.state != State::Data {
+            self.finish_command(0);
+        }
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let mut rgba = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks(self.stride).take(self.height) {
+            for pixel in &row[..self.width] {
+                rgba.extend_from_slice(&pixel.unwrap_or(self.background));
+            }
+        }
+        Some(crate::Image::new(
+            // width and height are limited to MAX_WIDTH and MAX_HEIGHT
+            self.width.try_into().unwrap(),
+            self.height.try_into().unwrap(),
+            rgba,
+        ))
+    }
+
+    fn start_command(&mut self, state: State) {
+        self.state = state;
+        self.params.clear();
+    }
+
+    // called with the byte that ended the command's parameters
+    fn finish_command(&mut self, b: u8) {
+        let param = |i: usize| self.params.get(i).copied().unwrap_or(0);
+        match self.state {
+            State::Data => {}
+            State::Repeat => {
+                if let b'?'..=b'~' = b {
+                    // a repeat count of 0 means 1
+                    let count =
+                        usize::try_from(param(0).max(1)).unwrap_or(MAX_WIDTH);
+                    self.sixel(b - b'?', count);
+                }
+            }
+            State::Color => {
+                // out of range registers wrap around, like they do in xterm
+                let register =
+                    usize::try_from(param(0)).unwrap_or(0) % COLOR_REGISTERS;
+                if self.params.len() >= 5 {
+                    let color = match param(1) {
+                        1 => Some(hls(param(2), param(3), param(4))),
+                        2 => Some((
+                            percent(param(2)),
+                            percent(param(3)),
+                            percent(param(4)),
+                        )),
+                        _ => None,
+                    };
+                    if let Some((r, g, b)) = color {
+                        self.registers[register] = [r, g, b, 255];
+                    }
+                }
+                self.color = register;
+            }
+            State::Raster => {
+                // the aspect ratio is ignored (like xterm does), but the
+                // image size makes the image at least that large, even if
+                // not all of it is drawn
+                if self.params.len() >= 4 {
+                    let width =
+                        usize::try_from(param(2)).unwrap_or(MAX_WIDTH);
+                    let height =
+                        usize::try_from(param(3)).unwrap_or(MAX_HEIGHT);
+                    self.grow(width.min(MAX_WIDTH), height.min(MAX_HEIGHT));
+                }
+            }
+        }
+    }
+
+    fn sixel(&mut self, bits: u8, count: usize) {
+        let start = self.x;
+        self.x = self.x.saturating_add(count);
+        if start >= MAX_WIDTH || self.y >= MAX_HEIGHT {
+            return;
+        }
+        let end = self.x.min(MAX_WIDTH);
+        // the image only needs to be tall enough for the bits which are
+        // actually set, so that images don't have to be a multiple of six
+        // pixels tall
+        let bottom = if bits == 0 {
+            self.height
+        } else {
+            // bits is at most 63, so this is at most 6
+            let set: usize = (8 - bits.leading_zeros()).try_into().unwrap();
+            (self.y + set).min(MAX_HEIGHT)
+        };
+        self.grow(end, bottom);
+        let color = self.registers[self.color];
+        for bit in 0..bottom.saturating_sub(self.y) {
+            if bits & (1 << bit) != 0 {
+                let row = (self.y + bit) * self.stride;
+                self.pixels[row + start..row + end].fill(Some(color));
+            }
+        }
+    }
+
+    // makes sure the image is at least width x height pixels
+    fn grow(&mut self, width: usize, height: usize) {
+        let width = width.max(self.width);
+        let height = height.max(self.height);
+        if width > self.stride {
+            // grow the allocation geometrically, to avoid copying the whole
+            // image for every sixel in the first band
+            let stride = width.max(self.stride * 2).min(MAX_WIDTH);
+            let mut pixels = vec![None; stride * self.height];
+            for (old, new) in self
+                .pixels
+                .chunks(self.stride.max(1))
+                .zip(pixels.chunks_mut(stride))
+            {
+                new[..self.stride].copy_from_slice(old);
+            }
+            self.pixels = pixels;
+            self.stride = stride;
+        }
+        if height > self.height {
+            self.pixels.resize(self.stride * height, None);
+        }
+        self.width = width;
+        self.height = height;
+    }
+}
+
+fn percent(value: u32) -> u8 {
+    // value.min(100) * 255 / 100 is always less than 256
+    (value.min(100) * 255 / 100).try_into().unwrap()
+}
+
+// sixel uses the dec variant of hls, where a hue of 0 degrees is blue
+// rather than red
+fn hls(hue: u32, lightness: u32, saturation: u32) -> (u8, u8, u8) {
+    let hue = i64::from((hue % 360 + 240) % 360);
+    let lightness = i64::from(lightness.min(100));
+    let saturation = i64::from(saturation.min(100));
+
+    let chroma = (100 - (2 * lightness - 100).abs()) * saturation / 100;
+    let secondary = chroma * (60 - ((hue % 120) - 60).abs()) / 60;
+    let offset = lightness - chroma / 2;
+    let (red, green, blue) = match hue / 60 {
+        0 => (chroma, secondary, 0),
+        1 => (secondary, chroma, 0),
+        2 => (0, chroma, secondary),
+        3 => (0, secondary, chroma),
+        4 => (secondary, 0, chroma),
+        _ => (chroma, 0, secondary),
+    };
+    let component = |c: i64| {
+        // c + offset is always between 0 and 100
+        percent((c + offset).clamp(0, 100).try_into().unwrap())
+    };
+    (component(red), component(green), component(blue))
+}
@@ -94,6 +94,50 @@ impl BufWrite for MoveTo {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct MoveToCol {
+    col: u16,
+}
+
+impl MoveToCol {
+    pub fn new(col: u16) -> Self {
+        Self { col }
+    }
+}
+
+impl BufWrite for MoveToCol {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.col == 0 {
+            buf.extend_from_slice(b"\x1b[G");
+        } else {
+            buf.extend_from_slice(b"\x1b[");
+            extend_itoa(buf, self.col + 1);
+            buf.push(b'G');
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SetTabStop;
+
+impl BufWrite for SetTabStop {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1bH");
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ClearTabStops;
+
+impl BufWrite for ClearTabStops {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[3g");
+    }
+}
+
 #[derive(Default, Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct ClearAttrs;
@@ -118,8 +162,13 @@ pub struct Attrs {
     bgcolor: Option<crate::Color>,
     intensity: Option<Intensity>,
     italic: Option<bool>,
-    underline: Option<bool>,
+    underline: Option<crate::UnderlineStyle>,
+    underline_color: Option<crate::Color>,
     inverse: Option<bool>,
+    blink: Option<bool>,
+    conceal: Option<bool>,
+    strikethrough: Option<bool>,
+    overline: Option<bool>,
 }
 
 impl Attrs {
@@ -143,15 +192,40 @@ impl Attrs {
         self
     }
 
-    pub fn underline(mut self, underline: bool) -> Self {
+    pub fn underline(mut self, underline: crate::UnderlineStyle) -> Self {
         self.underline = Some(underline);
         self
     }
 
+    pub fn underline_color(mut self, underline_color: crate::Color) -> Self {
+        self.underline_color = Some(underline_color);
+        self
+    }
+
     pub fn inverse(mut self, inverse: bool) -> Self {
         self.inverse = Some(inverse);
         self
     }
+
+    pub fn blink(mut self, blink: bool) -> Self {
+        self.blink = Some(blink);
+        self
+    }
+
+    pub fn conceal(mut self, conceal: bool) -> Self {
+        self.conceal = Some(conceal);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn overline(mut self, overline: bool) -> Self {
+        self.overline = Some(overline);
+        self
+    }
 }
 
 impl BufWrite for Attrs {
@@ -163,7 +237,12 @@ impl BufWrite for Attrs {
             && self.intensity.is_none()
             && self.italic.is_none()
             && self.underline.is_none()
+            && self.underline_color.is_none()
             && self.inverse.is_none()
+            && self.blink.is_none()
+            && self.conceal.is_none()
+            && self.strikethrough.is_none()
+            && self.overline.is_none()
         {
             return;
         }
@@ -182,6 +261,13 @@ impl BufWrite for Attrs {
             }};
         }
 
+        macro_rules! write_subparam {
+            ($i:expr) => {{
+                buf.push(b':');
+                extend_itoa(buf, $i);
+            }};
+        }
+
         if let Some(fgcolor) = self.fgcolor {
             match fgcolor {
                 crate::Color::Default => {
@@ -251,10 +337,35 @@ impl BufWrite for Attrs {
         }
 
         if let Some(underline) = self.underline {
-            if underline {
-                write_param!(4);
-            } else {
-                write_param!(24);
+            match underline {
+                crate::UnderlineStyle::None => write_param!(24),
+                crate::UnderlineStyle::Single => write_param!(4),
+                style => {
+                    write_param!(4);
+                    write_subparam!(style.param());
+                }
+            }
+        }
+
+        if let Some(underline_color) = self.underline_color {
+            match underline_color {
+                crate::Color::Default => {
+                    write_param!(59);
+                }
+                crate::Color::Idx(i) => {
+                    write_param!(58);
+                    write_subparam!(5);
+                    write_subparam!(i);
+                }
+                crate::Color::Rgb(r, g, b) => {
+                    write_param!(58);
+                    write_subparam!(2);
+                    // the empty subparameter is the color space id
+                    buf.push(b':');
+                    write_subparam!(r);
+                    write_subparam!(g);
+                    write_subparam!(b);
+                }
             }
         }
 
@@ -266,6 +377,38 @@ impl BufWrite for Attrs {
             }
         }
 
+        if let Some(blink) = self.blink {
+            if blink {
+                write_param!(5);
+            } else {
+                write_param!(25);
+            }
+        }
+
+        if let Some(conceal) = self.conceal {
+            if conceal {
+                write_param!(8);
+            } else {
+                write_param!(28);
+            }
+        }
+
+        if let Some(strikethrough) = self.strikethrough {
+            if strikethrough {
+                write_param!(9);
+            } else {
+                write_param!(29);
+            }
+        }
+
+        if let Some(overline) = self.overline {
+            if overline {
+                write_param!(53);
+            } else {
+                write_param!(55);
+            }
+        }
+
         buf.push(b'm');
     }
 }
@@ -356,6 +499,94 @@ impl BufWrite for HideCursor {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct CursorStyle {
+    style: crate::CursorStyle,
+}
+
+impl CursorStyle {
+    pub fn new(style: crate::CursorStyle) -> Self {
+        Self { style }
+    }
+}
+
+impl BufWrite for CursorStyle {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[");
+        extend_itoa(buf, self.style.param());
+        buf.extend_from_slice(b" q");
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct CursorBlink {
+    state: bool,
+}
+
+impl CursorBlink {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for CursorBlink {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?12h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?12l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct AlternateScreen {
+    state: bool,
+}
+
+impl AlternateScreen {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for AlternateScreen {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?1049h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?1049l");
+        }
+    }
+}
+
+// switches screens without saving or restoring the cursor and without
+// clearing the alternate screen
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct AlternateBuffer {
+    state: bool,
+}
+
+impl AlternateBuffer {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for AlternateBuffer {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?47h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?47l");
+        }
+    }
+}
+
 #[derive(Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct MoveFromTo {
@@ -449,6 +680,237 @@ impl BufWrite for BracketedPaste {
     }
 }
 
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct InsertMode {
+    state: bool,
+}
+
+impl InsertMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for InsertMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[4h");
+        } else {
+            buf.extend_from_slice(b"\x1b[4l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct NewlineMode {
+    state: bool,
+}
+
+impl NewlineMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for NewlineMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[20h");
+        } else {
+            buf.extend_from_slice(b"\x1b[20l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct Autowrap {
+    state: bool,
+}
+
+impl Autowrap {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for Autowrap {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?7h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?7l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ReverseWraparound {
+    state: bool,
+}
+
+impl ReverseWraparound {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for ReverseWraparound {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?45h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?45l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct LeftRightMarginMode {
+    state: bool,
+}
+
+impl LeftRightMarginMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for LeftRightMarginMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?69h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?69l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SixelDisplayMode {
+    state: bool,
+}
+
+impl SixelDisplayMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for SixelDisplayMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?80h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?80l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct BackarrowKeyMode {
+    state: bool,
+}
+
+impl BackarrowKeyMode {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for BackarrowKeyMode {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?67h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?67l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct FocusEvents {
+    state: bool,
+}
+
+impl FocusEvents {
+    pub fn new(state: bool) -> Self {
+        Self { state }
+    }
+}
+
+impl BufWrite for FocusEvents {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.state {
+            buf.extend_from_slice(b"\x1b[?1004h");
+        } else {
+            buf.extend_from_slice(b"\x1b[?1004l");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ModifyOtherKeys {
+    level: u8,
+}
+
+impl ModifyOtherKeys {
+    pub fn new(level: u8) -> Self {
+        Self { level }
+    }
+}
+
+impl BufWrite for ModifyOtherKeys {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b[>4;");
+        extend_itoa(buf, self.level);
+        buf.push(b'm');
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct KeyboardFlags<'a> {
+    stack: &'a [u8],
+    prev: &'a [u8],
+}
+
+impl<'a> KeyboardFlags<'a> {
+    pub fn new(stack: &'a [u8], prev: &'a [u8]) -> Self {
+        Self { stack, prev }
+    }
+}
+
+impl BufWrite for KeyboardFlags<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        // pop the entries which are different, and push the new ones
+        let common = self
+            .stack
+            .iter()
+            .zip(self.prev)
+            .take_while(|(flags, prev)| flags == prev)
+            .count();
+        if self.prev.len() > common {
+            buf.extend_from_slice(b"\x1b[<");
+            extend_itoa(buf, self.prev.len() - common);
+            buf.push(b'u');
+        }
+        for &flags in &self.stack[common..] {
+            buf.extend_from_slice(b"\x1b[>");
+            extend_itoa(buf, flags);
+            buf.push(b'u');
+        }
+    }
+}
+
 #[derive(Default, Debug)]
 #[must_use = "this struct does nothing unless you call write_buf"]
 pub struct MouseProtocolMode {
@@ -480,12 +942,18 @@ impl BufWrite for MouseProtocolMode {
                 crate::MouseProtocolMode::PressRelease => {
                     buf.extend_from_slice(b"\x1b[?1000l");
                 }
+                crate::MouseProtocolMode::Highlight => {
+                    buf.extend_from_slice(b"\x1b[?1001l");
+                }
                 crate::MouseProtocolMode::ButtonMotion => {
                     buf.extend_from_slice(b"\x1b[?1002l");
                 }
                 crate::MouseProtocolMode::AnyMotion => {
                     buf.extend_from_slice(b"\x1b[?1003l");
                 }
+                crate::MouseProtocolMode::DecLocator => {
+                    buf.extend_from_slice(b"\x1b['z");
+                }
             },
             crate::MouseProtocolMode::Press => {
                 buf.extend_from_slice(b"\x1b[?9h");
@@ -493,13 +961,82 @@ impl BufWrite for MouseProtocolMode {
             crate::MouseProtocolMode::PressRelease => {
                 buf.extend_from_slice(b"\x1b[?1000h");
             }
+            crate::MouseProtocolMode::Highlight => {
+                buf.extend_from_slice(b"\x1b[?1001h");
+            }
             crate::MouseProtocolMode::ButtonMotion => {
                 buf.extend_from_slice(b"\x1b[?1002h");
             }
             crate::MouseProtocolMode::AnyMotion => {
                 buf.extend_from_slice(b"\x1b[?1003h");
             }
+            // written by DecLocator, along with its settings
+            crate::MouseProtocolMode::DecLocator => {}
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct DecLocator {
+    // (one shot, pixels), if the locator is enabled
+    mode: Option<(bool, bool)>,
+    prev: Option<(bool, bool)>,
+}
+
+impl DecLocator {
+    pub fn new(
+        mode: Option<(bool, bool)>,
+        prev: Option<(bool, bool)>,
+    ) -> Self {
+        Self { mode, prev }
+    }
+}
+
+impl BufWrite for DecLocator {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.mode == self.prev {
+            return;
+        }
+
+        // disabling the locator is handled by MouseProtocolMode
+        if let Some((one_shot, pixels)) = self.mode {
+            buf.extend_from_slice(if one_shot {
+                b"\x1b[2"
+            } else {
+                b"\x1b[1"
+            });
+            if pixels {
+                buf.extend_from_slice(b";1");
+            }
+            buf.extend_from_slice(b"'z");
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct LocatorEvents {
+    // (report button presses, report button releases)
+    events: (bool, bool),
+    prev: (bool, bool),
+}
+
+impl LocatorEvents {
+    pub fn new(events: (bool, bool), prev: (bool, bool)) -> Self {
+        Self { events, prev }
+    }
+}
+
+impl BufWrite for LocatorEvents {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        if self.events == self.prev {
+            return;
         }
+
+        let (press, release) = self.events;
+        buf.extend_from_slice(if press { b"\x1b[1" } else { b"\x1b[2" });
+        buf.extend_from_slice(if release { b";3'{" } else { b";4'{" });
     }
 }
 
@@ -534,6 +1071,12 @@ impl BufWrite for MouseProtocolEncoding {
                 crate::MouseProtocolEncoding::Sgr => {
                     buf.extend_from_slice(b"\x1b[?1006l");
                 }
+                crate::MouseProtocolEncoding::Urxvt => {
+                    buf.extend_from_slice(b"\x1b[?1015l");
+                }
+                crate::MouseProtocolEncoding::SgrPixels => {
+                    buf.extend_from_slice(b"\x1b[?1016l");
+                }
             },
             crate::MouseProtocolEncoding::Utf8 => {
                 buf.extend_from_slice(b"\x1b[?1005h");
@@ -541,10 +1084,208 @@ impl BufWrite for MouseProtocolEncoding {
             crate::MouseProtocolEncoding::Sgr => {
                 buf.extend_from_slice(b"\x1b[?1006h");
             }
+            crate::MouseProtocolEncoding::Urxvt => {
+                buf.extend_from_slice(b"\x1b[?1015h");
+            }
+            crate::MouseProtocolEncoding::SgrPixels => {
+                buf.extend_from_slice(b"\x1b[?1016h");
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct DesignateCharset {
+    g: u8,
+    charset: crate::Charset,
+}
+
+impl DesignateCharset {
+    pub fn new(g: u8, charset: crate::Charset) -> Self {
+        Self { g, charset }
+    }
+}
+
+impl BufWrite for DesignateCharset {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.push(b'\x1b');
+        buf.push(match self.g {
+            0 => b'(',
+            1 => b')',
+            2 => b'*',
+            3 => b'+',
+            _ => unreachable!(),
+        });
+        buf.push(self.charset.final_byte());
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct LockingShift {
+    g: u8,
+}
+
+impl LockingShift {
+    pub fn new(g: u8) -> Self {
+        Self { g }
+    }
+}
+
+impl BufWrite for LockingShift {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        match self.g {
+            0 => buf.push(b'\x0f'),
+            1 => buf.push(b'\x0e'),
+            2 => buf.extend_from_slice(b"\x1bn"),
+            3 => buf.extend_from_slice(b"\x1bo"),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SingleShift {
+    g: u8,
+}
+
+impl SingleShift {
+    pub fn new(g: u8) -> Self {
+        Self { g }
+    }
+}
+
+impl BufWrite for SingleShift {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        match self.g {
+            2 => buf.extend_from_slice(b"\x1bN"),
+            3 => buf.extend_from_slice(b"\x1bO"),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct Hyperlink<'a> {
+    link: Option<&'a crate::Hyperlink>,
+}
+
+impl<'a> Hyperlink<'a> {
+    pub fn new(link: Option<&'a crate::Hyperlink>) -> Self {
+        Self { link }
+    }
+}
+
+impl BufWrite for Hyperlink<'_> {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b]8;");
+        if let Some(link) = self.link {
+            if let Some(id) = link.id() {
+                buf.extend_from_slice(b"id=");
+                buf.extend_from_slice(id.as_bytes());
+            }
+            buf.push(b';');
+            buf.extend_from_slice(link.uri().as_bytes());
+        } else {
+            buf.push(b';');
         }
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SetPaletteColor {
+    idx: u8,
+    rgb: (u8, u8, u8),
+}
+
+impl SetPaletteColor {
+    pub fn new(idx: u8, rgb: (u8, u8, u8)) -> Self {
+        Self { idx, rgb }
+    }
+}
+
+impl BufWrite for SetPaletteColor {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b]4;");
+        extend_itoa(buf, self.idx);
+        buf.push(b';');
+        extend_rgb_spec(buf, self.rgb);
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ResetPaletteColor {
+    idx: u8,
+}
+
+impl ResetPaletteColor {
+    pub fn new(idx: u8) -> Self {
+        Self { idx }
     }
 }
 
+impl BufWrite for ResetPaletteColor {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b]104;");
+        extend_itoa(buf, self.idx);
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct SetDynamicColor {
+    ps: u16,
+    rgb: (u8, u8, u8),
+}
+
+impl SetDynamicColor {
+    pub fn new(ps: u16, rgb: (u8, u8, u8)) -> Self {
+        Self { ps, rgb }
+    }
+}
+
+impl BufWrite for SetDynamicColor {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b]");
+        extend_itoa(buf, self.ps);
+        buf.push(b';');
+        extend_rgb_spec(buf, self.rgb);
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+#[derive(Debug)]
+#[must_use = "this struct does nothing unless you call write_buf"]
+pub struct ResetDynamicColor {
+    ps: u16,
+}
+
+impl ResetDynamicColor {
+    pub fn new(ps: u16) -> Self {
+        Self { ps }
+    }
+}
+
+impl BufWrite for ResetDynamicColor {
+    fn write_buf(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(b"\x1b]");
+        extend_itoa(buf, self.ps + 100);
+        buf.extend_from_slice(b"\x1b\\");
+    }
+}
+
+fn extend_rgb_spec(buf: &mut Vec<u8>, (r, g, b): (u8, u8, u8)) {
+    buf.extend_from_slice(format!("rgb:{r:02x}/{g:02x}/{b:02x}").as_bytes());
+}
+
 fn extend_itoa<I: itoa::Integer>(buf: &mut Vec<u8>, i: I) {
     let mut itoa_buf = itoa::Buffer::new();
     buf.extend_from_slice(itoa_buf.format(i).as_bytes());
@@ -10,6 +10,16 @@ fn attrs() {
     helpers::fixture("attrs");
 }
 
+#[test]
+fn underline() {
+    helpers::fixture("underline");
+}
+
+#[test]
+fn sgr_extended() {
+    helpers::fixture("sgr_extended");
+}
+
 #[test]
 fn attributes_formatted() {
     let mut parser = vt100::Parser::default();
@@ -21,3 +31,88 @@ fn attributes_formatted() {
     parser.process(b"\x1b[m");
     assert_eq!(parser.screen().attributes_formatted(), b"\x1b[m");
 }
+
+#[test]
+fn underline_formatted() {
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[4:3;58:2::255:0:0m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[4:3;58:2::255:0:0m"
+    );
+    parser.process(b"\x1b[24;58;5;12m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[58:5:12m"
+    );
+    parser.process(b"\x1b[21m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[4:2;58:5:12m"
+    );
+    parser.process(b"\x1b[59;4m");
+    assert_eq!(parser.screen().attributes_formatted(), b"\x1b[m\x1b[4m");
+}
+
+#[test]
+fn sgr_extended_formatted() {
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[6;8;9;53m");
+    assert_eq!(
+        parser.screen().attributes_formatted(),
+        b"\x1b[m\x1b[5;8;9;53m"
+    );
+    parser.process(b"a\x1b[25;29mb");
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        b"\x1b[?25h\x1b[m\x1b[H\x1b[J\x1b[5;8;9;53ma\x1b[25;29mb"
+    );
+    parser.process(b"\x1b[28;55m");
+    assert_eq!(parser.screen().attributes_formatted(), b"\x1b[m");
+}
+
+#[test]
+fn colon_colors() {
+    let mut parser = vt100::Parser::default();
+
+    parser.process(b"\x1b[38:2::10:20:30;48:2:0:40:50:60;58:2:1:2:3mA");
+    let cell = parser.screen().cell(0, 0).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Rgb(10, 20, 30));
+    assert_eq!(cell.bgcolor(), vt100::Color::Rgb(40, 50, 60));
+    assert_eq!(cell.underline_color(), vt100::Color::Rgb(1, 2, 3));
+
+    parser.process(b"\x1b[m\x1b[38:5:100;1;48;5;200;4:3;58:5:7mB");
+    let cell = parser.screen().cell(0, 1).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Idx(100));
+    assert_eq!(cell.bgcolor(), vt100::Color::Idx(200));
+    assert_eq!(cell.underline_color(), vt100::Color::Idx(7));
+    assert!(cell.bold());
+    assert_eq!(cell.underline_style(), vt100::UnderlineStyle::Curly);
+
+    parser.process(b"\x1b[m\x1b[38;2;1;2;3;48:2::4:5:6;3;58;2;7;8;9mC");
+    let cell = parser.screen().cell(0, 2).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Rgb(1, 2, 3));
+    assert_eq!(cell.bgcolor(), vt100::Color::Rgb(4, 5, 6));
+    assert_eq!(cell.underline_color(), vt100::Color::Rgb(7, 8, 9));
+    assert!(cell.italic());
+
+    parser.process(b"\x1b[m\x1b[38:3::255:0:128;48:4::0:255:255:0mD");
+    let cell = parser.screen().cell(0, 3).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Rgb(0, 255, 127));
+    assert_eq!(cell.bgcolor(), vt100::Color::Rgb(255, 0, 0));
+
+    parser.process(b"\x1b[m\x1b[38:4::0:0:0:51;48:2::1:2:3:0:0:0mE");
+    let cell = parser.screen().cell(0, 4).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Rgb(204, 204, 204));
+    assert_eq!(cell.bgcolor(), vt100::Color::Rgb(1, 2, 3));
+
+    parser.process(b"\x1b[m\x1b[31;38:9:1;1mF");
+    let cell = parser.screen().cell(0, 5).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Idx(1));
+    assert!(cell.bold());
+
+    parser.process(b"\x1b[m\x1b[38:2::256:0:0;4mG");
+    let cell = parser.screen().cell(0, 6).unwrap();
+    assert_eq!(cell.fgcolor(), vt100::Color::Default);
+    assert!(cell.underline());
+}
@@ -52,6 +52,46 @@ fn tab() {
     helpers::fixture("tab");
 }
 
+#[test]
+fn tab_stops() {
+    helpers::fixture("tab_stops");
+}
+
+#[test]
+fn tab_stops_state() {
+    let mut parser = vt100::Parser::new(24, 20, 0);
+    assert_eq!(parser.screen().tab_stops().collect::<Vec<_>>(), vec![8, 16]);
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[3g\x1b[4G\x1bH\x1b[11G\x1bH\x1b[2;3H");
+    assert_eq!(parser.screen().tab_stops().collect::<Vec<_>>(), vec![3, 10]);
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        b"\x1b[?25h\x1b[m\x1b[3g\x1b[4G\x1bH\x1b[11G\x1bH\x1b[H\x1b[J\x1b[2;3H"
+    );
+    assert_eq!(
+        parser.screen().contents_diff(&screen),
+        b"\x1b[3g\x1b[4G\x1bH\x1b[11G\x1bH\x1b[2;3H"
+    );
+
+    let mut new_parser = vt100::Parser::new(24, 20, 0);
+    new_parser.process(&parser.screen().state_formatted());
+    assert_eq!(
+        new_parser.screen().tab_stops().collect::<Vec<_>>(),
+        vec![3, 10]
+    );
+
+    // explicitly set tab stops are kept, and new columns get the default
+    // tab stops
+    parser.screen_mut().set_size(24, 40);
+    assert_eq!(
+        parser.screen().tab_stops().collect::<Vec<_>>(),
+        vec![3, 10, 24, 32]
+    );
+    parser.screen_mut().set_size(24, 8);
+    assert_eq!(parser.screen().tab_stops().collect::<Vec<_>>(), vec![3]);
+}
+
 #[test]
 fn lf() {
     helpers::fixture("lf");
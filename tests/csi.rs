@@ -86,3 +86,159 @@ fn xtwinops() {
         "bbbbbcccccccccccccccccccc"
     );
 }
+
+#[test]
+fn device_status_report() {
+    let mut vt = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    vt.process(b"\x1b[5n");
+    assert_eq!(vt.callbacks().replies, [b"\x1b[0n"]);
+
+    vt.callbacks_mut().replies.clear();
+    vt.process(b"\x1b[6n\x1b[5;10H\x1b[6n\x1b[?6n");
+    assert_eq!(
+        vt.callbacks().replies,
+        [&b"\x1b[1;1R"[..], b"\x1b[5;10R", b"\x1b[?5;10;1R"]
+    );
+
+    // a cursor just past the end of the row is reported in the last column
+    vt.callbacks_mut().replies.clear();
+    vt.process(b"\x1b[1;75Hfoobar\x1b[6n");
+    assert_eq!(vt.callbacks().replies, [b"\x1b[1;80R"]);
+
+    // origin mode reports positions relative to the margins
+    vt.callbacks_mut().replies.clear();
+    vt.process(b"\x1b[5;20r\x1b[?69h\x1b[10;30s\x1b[?6h\x1b[3;4H\x1b[6n");
+    vt.process(b"\x1b[?6l\x1b[6n");
+    assert_eq!(vt.callbacks().replies, [&b"\x1b[3;4R"[..], b"\x1b[1;1R"]);
+
+    // unknown reports are ignored
+    vt.callbacks_mut().replies.clear();
+    vt.process(b"\x1b[7n\x1b[?5n");
+    assert!(vt.callbacks().replies.is_empty());
+}
+
+#[test]
+fn device_attributes() {
+    let mut vt = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    vt.process(b"\x1b[c\x1b[0c\x1b[>c\x1b[=c\x1b[1c");
+    assert_eq!(
+        vt.callbacks().replies,
+        [
+            &b"\x1b[?62;22c"[..],
+            b"\x1b[?62;22c",
+            b"\x1b[>1;0;0c",
+            b"\x1bP!|00000000\x1b\\",
+        ]
+    );
+
+    vt.callbacks_mut().replies.clear();
+    vt.screen_mut()
+        .set_terminal_identity(vt100::TerminalIdentity {
+            primary_attributes: vec![64, 1, 4, 22],
+            terminal_type: 41,
+            firmware_version: 380,
+            unit_id: 0x7e57_7e57,
+        });
+    vt.process(b"\x1b[c\x1b[>0c\x1b[=0c");
+    assert_eq!(
+        vt.callbacks().replies,
+        [
+            &b"\x1b[?64;1;4;22c"[..],
+            b"\x1b[>41;380;0c",
+            b"\x1bP!|7E577E57\x1b\\",
+        ]
+    );
+
+    // the identity is not reset by a full reset
+    vt.callbacks_mut().replies.clear();
+    vt.process(b"\x1bc\x1b[>c");
+    assert_eq!(vt.callbacks().replies, [b"\x1b[>41;380;0c"]);
+}
+
+#[test]
+fn decrqm() {
+    fn query(
+        vt: &mut vt100::Parser<helpers::Replies>,
+        query: &[u8],
+    ) -> Vec<u8> {
+        vt.callbacks_mut().replies.clear();
+        vt.process(query);
+        assert_eq!(vt.callbacks().replies.len(), 1);
+        vt.callbacks_mut().replies.pop().unwrap()
+    }
+
+    let mut vt = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    assert_eq!(query(&mut vt, b"\x1b[4$p"), b"\x1b[4;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[20$p"), b"\x1b[20;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[2$p"), b"\x1b[2;4$y");
+    assert_eq!(query(&mut vt, b"\x1b[12$p"), b"\x1b[12;3$y");
+    assert_eq!(query(&mut vt, b"\x1b[1234$p"), b"\x1b[1234;0$y");
+    vt.process(b"\x1b[4h\x1b[20h");
+    assert_eq!(query(&mut vt, b"\x1b[4$p"), b"\x1b[4;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[20$p"), b"\x1b[20;1$y");
+
+    assert_eq!(query(&mut vt, b"\x1b[?1$p"), b"\x1b[?1;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?7$p"), b"\x1b[?7;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?25$p"), b"\x1b[?25;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1049$p"), b"\x1b[?1049;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?2004$p"), b"\x1b[?2004;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1000$p"), b"\x1b[?1000;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1006$p"), b"\x1b[?1006;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1015$p"), b"\x1b[?1015;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1004$p"), b"\x1b[?1004;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?2026$p"), b"\x1b[?2026;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?80$p"), b"\x1b[?80;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?67$p"), b"\x1b[?67;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?66$p"), b"\x1b[?66;2$y");
+    vt.process(b"\x1b[?66h");
+    assert_eq!(query(&mut vt, b"\x1b[?66$p"), b"\x1b[?66;1$y");
+    assert!(vt.screen().application_keypad());
+    vt.process(b"\x1b[?66l");
+    assert_eq!(query(&mut vt, b"\x1b[?66$p"), b"\x1b[?66;2$y");
+    assert!(!vt.screen().application_keypad());
+    vt.process(b"\x1b=");
+    assert_eq!(query(&mut vt, b"\x1b[?66$p"), b"\x1b[?66;1$y");
+    vt.process(b"\x1b>");
+    vt.process(
+        b"\x1b[?1h\x1b[?7l\x1b[?25l\x1b[?1049h\x1b[?6h\x1b[?2004h\x1b[?1002h\x1b[?1006h\x1b[?80h\x1b[?67h",
+    );
+    assert_eq!(query(&mut vt, b"\x1b[?1$p"), b"\x1b[?1;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?6$p"), b"\x1b[?6;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?7$p"), b"\x1b[?7;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?25$p"), b"\x1b[?25;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1049$p"), b"\x1b[?1049;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?2004$p"), b"\x1b[?2004;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1000$p"), b"\x1b[?1000;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1002$p"), b"\x1b[?1002;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1005$p"), b"\x1b[?1005;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1006$p"), b"\x1b[?1006;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?80$p"), b"\x1b[?80;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?67$p"), b"\x1b[?67;1$y");
+    vt.process(b"\x1b[?1001h\x1b[?1016h\x1b[?1004h\x1b[?2026h");
+    assert_eq!(query(&mut vt, b"\x1b[?1001$p"), b"\x1b[?1001;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1002$p"), b"\x1b[?1002;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1006$p"), b"\x1b[?1006;2$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1016$p"), b"\x1b[?1016;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1004$p"), b"\x1b[?1004;1$y");
+    assert_eq!(query(&mut vt, b"\x1b[?2026$p"), b"\x1b[?2026;1$y");
+
+    assert_eq!(query(&mut vt, b"\x1b[?2$p"), b"\x1b[?2;3$y");
+    assert_eq!(query(&mut vt, b"\x1b[?3$p"), b"\x1b[?3;4$y");
+    assert_eq!(query(&mut vt, b"\x1b[?2027$p"), b"\x1b[?2027;4$y");
+    assert_eq!(query(&mut vt, b"\x1b[?1234$p"), b"\x1b[?1234;0$y");
+}
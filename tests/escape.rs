@@ -77,3 +77,50 @@ fn decsc_resize() {
     assert_eq!(parser.screen().contents(), "yoo\n\n\n\n\n\n\n\n\n\n\n\n\n\n                                                           z");
     assert_eq!(parser.screen().cursor_position(), (14, 60));
 }
+
+#[test]
+fn charset() {
+    helpers::fixture("charset");
+}
+
+#[test]
+fn charset_state() {
+    let mut parser = vt100::Parser::default();
+    assert_eq!(parser.screen().charsets(), [vt100::Charset::Ascii; 4]);
+    assert_eq!(parser.screen().active_charset(), 0);
+
+    let screen = parser.screen().clone();
+    parser.process(b"q\x1b)0\x0e");
+    assert_eq!(
+        parser.screen().charsets(),
+        [
+            vt100::Charset::Ascii,
+            vt100::Charset::DecSpecialGraphics,
+            vt100::Charset::Ascii,
+            vt100::Charset::Ascii,
+        ]
+    );
+    assert_eq!(parser.screen().active_charset(), 1);
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        b"\x1b[?25h\x1b[m\x1b[H\x1b[Jq\x1b)0\x0e"
+    );
+    assert_eq!(parser.screen().contents_diff(&screen), b"q\x1b)0\x0e");
+
+    // already translated contents must not be translated again when
+    // redrawn on top of a terminal with the line drawing set active
+    let screen = parser.screen().clone();
+    parser.process(b"q\x0fq");
+    assert_eq!(parser.screen().contents(), "q─q");
+    assert_eq!(
+        parser.screen().contents_diff(&screen),
+        b"\x1b)B\xe2\x94\x80q\x1b)0\x0f"
+    );
+
+    let mut new_parser = vt100::Parser::default();
+    new_parser.process(&screen.state_formatted());
+    new_parser.process(&parser.screen().state_diff(&screen));
+    assert_eq!(new_parser.screen().contents(), "q─q");
+    assert_eq!(new_parser.screen().charsets(), parser.screen().charsets());
+    assert_eq!(new_parser.screen().active_charset(), 0);
+}
@@ -30,13 +30,41 @@ pub struct FixtureCell {
     italic: bool,
     #[serde(default, skip_serializing_if = "is_default")]
     underline: bool,
+    // only recorded when it can't be inferred from `underline`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_underline_style",
+        serialize_with = "serialize_underline_style",
+        skip_serializing_if = "is_default"
+    )]
+    underline_style: Option<vt100::UnderlineStyle>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_color",
+        serialize_with = "serialize_color",
+        skip_serializing_if = "is_default"
+    )]
+    underline_color: vt100::Color,
     #[serde(default, skip_serializing_if = "is_default")]
     inverse: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    blink: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    conceal: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    strikethrough: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    overline: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    hyperlink: Option<String>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    hyperlink_id: Option<String>,
 }
 
 impl FixtureCell {
     #[allow(dead_code)]
-    pub fn from_cell(cell: &vt100::Cell) -> Self {
+    pub fn from_cell(screen: &vt100::Screen, cell: &vt100::Cell) -> Self {
+        let hyperlink = cell.hyperlink().and_then(|id| screen.hyperlink(id));
         Self {
             contents: cell.contents().to_string(),
             is_wide: cell.is_wide(),
@@ -47,7 +75,21 @@ impl FixtureCell {
             dim: cell.dim(),
             italic: cell.italic(),
             underline: cell.underline(),
+            underline_style: match cell.underline_style() {
+                vt100::UnderlineStyle::None
+                | vt100::UnderlineStyle::Single => None,
+                style => Some(style),
+            },
+            underline_color: cell.underline_color(),
             inverse: cell.inverse(),
+            blink: cell.blink(),
+            conceal: cell.conceal(),
+            strikethrough: cell.strikethrough(),
+            overline: cell.overline(),
+            hyperlink: hyperlink.map(|link| link.uri().to_string()),
+            hyperlink_id: hyperlink
+                .and_then(vt100::Hyperlink::id)
+                .map(str::to_string),
         }
     }
 }
@@ -65,6 +107,25 @@ pub struct FixtureScreen {
     hide_cursor: bool,
     #[serde(default, skip_serializing_if = "is_default")]
     bracketed_paste: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    insert_mode: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    newline_mode: bool,
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    autowrap: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    reverse_wraparound: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    left_right_margin_mode: bool,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_cursor_style",
+        serialize_with = "serialize_cursor_style",
+        skip_serializing_if = "is_default"
+    )]
+    cursor_style: vt100::CursorStyle,
+    #[serde(default, skip_serializing_if = "is_default")]
+    cursor_blink: bool,
     #[serde(
         default,
         deserialize_with = "deserialize_mouse_protocol_mode",
@@ -98,7 +159,7 @@ impl FixtureScreen {
                 if cell != empty_cell {
                     cells.insert(
                         format!("{row},{col}"),
-                        FixtureCell::from_cell(cell),
+                        FixtureCell::from_cell(screen, cell),
                     );
                 }
             }
@@ -111,6 +172,13 @@ impl FixtureScreen {
             application_cursor: screen.application_cursor(),
             hide_cursor: screen.hide_cursor(),
             bracketed_paste: screen.bracketed_paste(),
+            insert_mode: screen.insert_mode(),
+            newline_mode: screen.newline_mode(),
+            autowrap: screen.autowrap(),
+            reverse_wraparound: screen.reverse_wraparound(),
+            left_right_margin_mode: screen.left_right_margin_mode(),
+            cursor_style: screen.cursor_style(),
+            cursor_blink: screen.cursor_blink(),
             mouse_protocol_mode: screen.mouse_protocol_mode(),
             mouse_protocol_encoding: screen.mouse_protocol_encoding(),
         }
@@ -121,6 +189,15 @@ fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     t == &T::default()
 }
 
+const fn default_true() -> bool {
+    true
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+const fn is_true(b: &bool) -> bool {
+    *b
+}
+
 fn deserialize_color<'a, D>(
     deserializer: D,
 ) -> std::result::Result<vt100::Color, D::Error>
@@ -164,6 +241,81 @@ where
     serializer.serialize_str(&s)
 }
 
+fn deserialize_underline_style<'a, D>(
+    deserializer: D,
+) -> std::result::Result<Option<vt100::UnderlineStyle>, D::Error>
+where
+    D: serde::de::Deserializer<'a>,
+{
+    let name = <String>::deserialize(deserializer)?;
+    match name.as_ref() {
+        "none" => Ok(Some(vt100::UnderlineStyle::None)),
+        "single" => Ok(Some(vt100::UnderlineStyle::Single)),
+        "double" => Ok(Some(vt100::UnderlineStyle::Double)),
+        "curly" => Ok(Some(vt100::UnderlineStyle::Curly)),
+        "dotted" => Ok(Some(vt100::UnderlineStyle::Dotted)),
+        "dashed" => Ok(Some(vt100::UnderlineStyle::Dashed)),
+        _ => unimplemented!(),
+    }
+}
+
+fn serialize_underline_style<S>(
+    style: &Option<vt100::UnderlineStyle>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let Some(style) = style else { unreachable!() };
+    let s = match style {
+        vt100::UnderlineStyle::None => "none",
+        vt100::UnderlineStyle::Single => "single",
+        vt100::UnderlineStyle::Double => "double",
+        vt100::UnderlineStyle::Curly => "curly",
+        vt100::UnderlineStyle::Dotted => "dotted",
+        vt100::UnderlineStyle::Dashed => "dashed",
+    };
+    serializer.serialize_str(s)
+}
+
+fn deserialize_cursor_style<'a, D>(
+    deserializer: D,
+) -> std::result::Result<vt100::CursorStyle, D::Error>
+where
+    D: serde::de::Deserializer<'a>,
+{
+    let name = <String>::deserialize(deserializer)?;
+    match name.as_ref() {
+        "default" => Ok(vt100::CursorStyle::Default),
+        "blinking_block" => Ok(vt100::CursorStyle::BlinkingBlock),
+        "steady_block" => Ok(vt100::CursorStyle::SteadyBlock),
+        "blinking_underline" => Ok(vt100::CursorStyle::BlinkingUnderline),
+        "steady_underline" => Ok(vt100::CursorStyle::SteadyUnderline),
+        "blinking_bar" => Ok(vt100::CursorStyle::BlinkingBar),
+        "steady_bar" => Ok(vt100::CursorStyle::SteadyBar),
+        _ => unimplemented!(),
+    }
+}
+
+fn serialize_cursor_style<S>(
+    style: &vt100::CursorStyle,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let s = match style {
+        vt100::CursorStyle::Default => "default",
+        vt100::CursorStyle::BlinkingBlock => "blinking_block",
+        vt100::CursorStyle::SteadyBlock => "steady_block",
+        vt100::CursorStyle::BlinkingUnderline => "blinking_underline",
+        vt100::CursorStyle::SteadyUnderline => "steady_underline",
+        vt100::CursorStyle::BlinkingBar => "blinking_bar",
+        vt100::CursorStyle::SteadyBar => "steady_bar",
+    };
+    serializer.serialize_str(s)
+}
+
 fn deserialize_mouse_protocol_mode<'a, D>(
     deserializer: D,
 ) -> std::result::Result<vt100::MouseProtocolMode, D::Error>
@@ -175,8 +327,10 @@ where
         "none" => Ok(vt100::MouseProtocolMode::None),
         "press" => Ok(vt100::MouseProtocolMode::Press),
         "press_release" => Ok(vt100::MouseProtocolMode::PressRelease),
+        "highlight" => Ok(vt100::MouseProtocolMode::Highlight),
         "button_motion" => Ok(vt100::MouseProtocolMode::ButtonMotion),
         "any_motion" => Ok(vt100::MouseProtocolMode::AnyMotion),
+        "dec_locator" => Ok(vt100::MouseProtocolMode::DecLocator),
         _ => unimplemented!(),
     }
 }
@@ -192,8 +346,10 @@ where
         vt100::MouseProtocolMode::None => "none",
         vt100::MouseProtocolMode::Press => "press",
         vt100::MouseProtocolMode::PressRelease => "press_release",
+        vt100::MouseProtocolMode::Highlight => "highlight",
         vt100::MouseProtocolMode::ButtonMotion => "button_motion",
         vt100::MouseProtocolMode::AnyMotion => "any_motion",
+        vt100::MouseProtocolMode::DecLocator => "dec_locator",
     };
     serializer.serialize_str(s)
 }
@@ -209,6 +365,8 @@ where
         "default" => Ok(vt100::MouseProtocolEncoding::Default),
         "utf8" => Ok(vt100::MouseProtocolEncoding::Utf8),
         "sgr" => Ok(vt100::MouseProtocolEncoding::Sgr),
+        "urxvt" => Ok(vt100::MouseProtocolEncoding::Urxvt),
+        "sgr_pixels" => Ok(vt100::MouseProtocolEncoding::SgrPixels),
         _ => unimplemented!(),
     }
 }
@@ -224,6 +382,8 @@ where
         vt100::MouseProtocolEncoding::Default => "default",
         vt100::MouseProtocolEncoding::Utf8 => "utf8",
         vt100::MouseProtocolEncoding::Sgr => "sgr",
+        vt100::MouseProtocolEncoding::Urxvt => "urxvt",
+        vt100::MouseProtocolEncoding::SgrPixels => "sgr_pixels",
     };
     serializer.serialize_str(s)
 }
@@ -261,6 +421,19 @@ fn assert_produces(input: &[u8], expected: &FixtureScreen) {
     );
     assert_eq!(parser.screen().hide_cursor(), expected.hide_cursor);
     assert_eq!(parser.screen().bracketed_paste(), expected.bracketed_paste);
+    assert_eq!(parser.screen().insert_mode(), expected.insert_mode);
+    assert_eq!(parser.screen().newline_mode(), expected.newline_mode);
+    assert_eq!(parser.screen().autowrap(), expected.autowrap);
+    assert_eq!(
+        parser.screen().reverse_wraparound(),
+        expected.reverse_wraparound
+    );
+    assert_eq!(
+        parser.screen().left_right_margin_mode(),
+        expected.left_right_margin_mode
+    );
+    assert_eq!(parser.screen().cursor_style(), expected.cursor_style);
+    assert_eq!(parser.screen().cursor_blink(), expected.cursor_blink);
     assert_eq!(
         parser.screen().mouse_protocol_mode(),
         expected.mouse_protocol_mode
@@ -291,7 +464,36 @@ fn assert_produces(input: &[u8], expected: &FixtureScreen) {
             assert_eq!(got_cell.dim(), expected_cell.dim);
             assert_eq!(got_cell.italic(), expected_cell.italic);
             assert_eq!(got_cell.underline(), expected_cell.underline);
+            assert_eq!(
+                got_cell.underline_style(),
+                expected_cell.underline_style.unwrap_or(
+                    if expected_cell.underline {
+                        vt100::UnderlineStyle::Single
+                    } else {
+                        vt100::UnderlineStyle::None
+                    }
+                )
+            );
+            assert_eq!(
+                got_cell.underline_color(),
+                expected_cell.underline_color
+            );
             assert_eq!(got_cell.inverse(), expected_cell.inverse);
+            assert_eq!(got_cell.blink(), expected_cell.blink);
+            assert_eq!(got_cell.conceal(), expected_cell.conceal);
+            assert_eq!(got_cell.strikethrough(), expected_cell.strikethrough);
+            assert_eq!(got_cell.overline(), expected_cell.overline);
+            let hyperlink = got_cell
+                .hyperlink()
+                .and_then(|id| parser.screen().hyperlink(id));
+            assert_eq!(
+                hyperlink.map(vt100::Hyperlink::uri),
+                expected_cell.hyperlink.as_deref()
+            );
+            assert_eq!(
+                hyperlink.and_then(vt100::Hyperlink::id),
+                expected_cell.hyperlink_id.as_deref()
+            );
         }
     }
 }
@@ -6,6 +6,19 @@ pub use fixtures::FixtureScreen;
 
 pub static mut QUIET: bool = false;
 
+// collects the replies that the terminal sends to the application
+#[allow(dead_code)]
+#[derive(Default)]
+pub struct Replies {
+    pub replies: Vec<Vec<u8>>,
+}
+
+impl vt100::Callbacks for Replies {
+    fn reply(&mut self, _: &mut vt100::Screen, data: &[u8]) {
+        self.replies.push(data.to_vec());
+    }
+}
+
 macro_rules! is {
     ($got:expr, $expected:expr) => {
         if ($got) != ($expected) {
@@ -92,25 +105,97 @@ pub fn compare_screens(
         for col in 0..cols {
             let expected_cell = expected.cell(row, col);
             let got_cell = got.cell(row, col);
-            is!(got_cell, expected_cell);
+            // hyperlink ids are only meaningful within a single screen, so
+            // compare the links themselves instead
+            let got_link = got_cell
+                .and_then(vt100::Cell::hyperlink)
+                .and_then(|id| got.hyperlink(id));
+            let expected_link = expected_cell
+                .and_then(vt100::Cell::hyperlink)
+                .and_then(|id| expected.hyperlink(id));
+            is!(got_link, expected_link);
+            if got_link.is_none() {
+                is!(got_cell, expected_cell);
+            } else if let (Some(got_cell), Some(expected_cell)) =
+                (got_cell, expected_cell)
+            {
+                is!(got_cell.contents(), expected_cell.contents());
+                is!(got_cell.is_wide(), expected_cell.is_wide());
+                is!(
+                    got_cell.is_wide_continuation(),
+                    expected_cell.is_wide_continuation()
+                );
+                is!(got_cell.fgcolor(), expected_cell.fgcolor());
+                is!(got_cell.bgcolor(), expected_cell.bgcolor());
+                is!(got_cell.bold(), expected_cell.bold());
+                is!(got_cell.dim(), expected_cell.dim());
+                is!(got_cell.italic(), expected_cell.italic());
+                is!(
+                    got_cell.underline_style(),
+                    expected_cell.underline_style()
+                );
+                is!(
+                    got_cell.underline_color(),
+                    expected_cell.underline_color()
+                );
+                is!(got_cell.inverse(), expected_cell.inverse());
+                is!(got_cell.blink(), expected_cell.blink());
+                is!(got_cell.conceal(), expected_cell.conceal());
+                is!(got_cell.strikethrough(), expected_cell.strikethrough());
+                is!(got_cell.overline(), expected_cell.overline());
+            }
         }
     }
 
     is!(got.cursor_position(), expected.cursor_position());
+    is!(
+        got.tab_stops().collect::<Vec<_>>(),
+        expected.tab_stops().collect::<Vec<_>>()
+    );
     ok!(got.cursor_position().0 <= rows);
     ok!(expected.cursor_position().0 <= rows);
     ok!(got.cursor_position().1 <= cols);
     ok!(expected.cursor_position().1 <= cols);
 
+    is!(got.alternate_screen(), expected.alternate_screen());
     is!(got.application_keypad(), expected.application_keypad());
     is!(got.application_cursor(), expected.application_cursor());
     is!(got.hide_cursor(), expected.hide_cursor());
     is!(got.bracketed_paste(), expected.bracketed_paste());
+    is!(got.insert_mode(), expected.insert_mode());
+    is!(got.newline_mode(), expected.newline_mode());
+    is!(got.autowrap(), expected.autowrap());
+    is!(got.reverse_wraparound(), expected.reverse_wraparound());
+    is!(
+        got.left_right_margin_mode(),
+        expected.left_right_margin_mode()
+    );
+    for idx in 0..=255 {
+        is!(got.palette_color(idx), expected.palette_color(idx));
+    }
+    is!(got.foreground_color(), expected.foreground_color());
+    is!(got.background_color(), expected.background_color());
+    is!(got.cursor_color(), expected.cursor_color());
+    is!(Bytes(&got.palette_diff(expected)), Bytes(b""));
+    is!(got.charsets(), expected.charsets());
+    is!(got.active_charset(), expected.active_charset());
+    is!(got.cursor_style(), expected.cursor_style());
+    is!(got.cursor_blink(), expected.cursor_blink());
     is!(got.mouse_protocol_mode(), expected.mouse_protocol_mode());
     is!(
         got.mouse_protocol_encoding(),
         expected.mouse_protocol_encoding()
     );
+    is!(got.focus_events(), expected.focus_events());
+    is!(got.modify_other_keys(), expected.modify_other_keys());
+    is!(got.keyboard_flags(), expected.keyboard_flags());
+    // this also covers the state that isn't exposed directly, such as the
+    // keyboard flags of the inactive screen and the DEC locator settings
+    is!(
+        Bytes(&got.input_mode_formatted()),
+        Bytes(&expected.input_mode_formatted())
+    );
+    is!(Bytes(&got.input_mode_diff(expected)), Bytes(b""));
 
     true
 }
@@ -129,6 +214,7 @@ pub fn rows_formatted_reproduces_state(input: &[u8]) -> bool {
 
 pub fn contents_formatted_reproduces_screen(screen: &vt100::Screen) -> bool {
     let mut new_input = screen.contents_formatted();
+    new_input.extend(screen.palette_formatted());
     new_input.extend(screen.input_mode_formatted());
     assert_eq!(new_input, screen.state_formatted());
     let mut new_parser = vt100::Parser::default();
@@ -140,6 +226,9 @@ pub fn contents_formatted_reproduces_screen(screen: &vt100::Screen) -> bool {
 
 pub fn rows_formatted_reproduces_screen(screen: &vt100::Screen) -> bool {
     let mut new_input = vec![];
+    if screen.alternate_screen() {
+        new_input.extend(b"\x1b[?1049h");
+    }
     let mut wrapped = false;
     for (idx, row) in screen.rows_formatted(0, 80).enumerate() {
         new_input.extend(b"\x1b[m");
@@ -152,6 +241,7 @@ pub fn rows_formatted_reproduces_screen(screen: &vt100::Screen) -> bool {
     new_input.extend(b"\x1b[m");
     new_input.extend(screen.cursor_state_formatted());
     new_input.extend(screen.attributes_formatted());
+    new_input.extend(screen.palette_formatted());
     new_input.extend(screen.input_mode_formatted());
     let mut new_parser = vt100::Parser::default();
     new_parser.process(&new_input);
@@ -190,11 +280,13 @@ pub fn contents_diff_reproduces_state_from_screens(
     screen: &vt100::Screen,
 ) -> bool {
     let mut diff_input = screen.contents_diff(prev_screen);
+    diff_input.extend(screen.palette_diff(prev_screen));
     diff_input.extend(screen.input_mode_diff(prev_screen));
     assert_eq!(diff_input, screen.state_diff(prev_screen));
 
     let mut diff_prev_input = prev_screen.contents_formatted();
-    diff_prev_input.extend(screen.input_mode_formatted());
+    diff_prev_input.extend(prev_screen.palette_formatted());
+    diff_prev_input.extend(prev_screen.input_mode_formatted());
 
     let mut new_parser = vt100::Parser::default();
     new_parser.process(&diff_prev_input);
@@ -0,0 +1,525 @@
+mod helpers;
+
+#[test]
+fn sixel_decode() {
+    let mut parser = vt100::Parser::default();
+    assert!(parser.screen().images().is_empty());
+
+    // two bands of red, the second of which only has its top two pixels set
+    parser.process(b"\x1bPq#1;2;100;0;0#1!3~-!3B\x1b\\");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    let image = images[0].image();
+    assert_eq!((image.width(), image.height()), (3, 8));
+    assert_eq!(image.rgba().len(), 3 * 8 * 4);
+    assert!(image
+        .rgba()
+        .chunks(4)
+        .all(|pixel| pixel == [255, 0, 0, 255]));
+
+    // pixels which aren't drawn are filled with the background color,
+    // unless the second parameter is 1. hls colors use the dec convention
+    // of 0 degrees being blue.
+    parser.process(b"\x1bP0;1q\"1;1;2;2#2;1;0;50;100#2@\x1b\\");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 2);
+    let image = images[1].image();
+    assert_eq!((image.width(), image.height()), (2, 2));
+    assert_eq!(
+        image.rgba(),
+        [0, 0, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+
+    parser.process(b"\x1bPq\"1;1;1;2#0;2;0;0;100$\x1b\\");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 3);
+    assert_eq!(images[2].image().rgba(), [0, 0, 0, 255, 0, 0, 0, 255]);
+
+    // empty images are ignored, as are other device control strings
+    parser.process(b"\x1bPq#1\x1b\\\x1bP$qm\x1b\\");
+    assert_eq!(parser.screen().images().len(), 3);
+}
+
+#[test]
+fn sixel_placement() {
+    let mut parser = vt100::Parser::new(5, 10, 10);
+    parser.screen_mut().set_cell_size(2, 4);
+
+    // a 5x10 pixel image covers 3x3 cells
+    let sixel = b"\x1bPq#1;2;100;100;100!5~-!5N\x1b\\";
+    parser.process(b"\x1b[2;3H");
+    parser.process(sixel);
+    assert_eq!(parser.screen().cursor_position(), (4, 2));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].position(), (1, 2));
+    assert_eq!(images[0].size(), (3, 3));
+    assert_eq!(images[0].source_rect(), (0, 0, 5, 10));
+
+    // images scroll along with the text, including into the scrollback
+    parser.process(b"\r\n\n");
+    let images = parser.screen().images();
+    assert_eq!(images[0].position(), (0, 2));
+    assert_eq!(images[0].size(), (2, 3));
+    assert_eq!(images[0].source_rect(), (0, 4, 5, 6));
+    parser.screen_mut().set_scrollback(1);
+    let images = parser.screen().images();
+    assert_eq!(images[0].position(), (0, 2));
+    assert_eq!(images[0].size(), (3, 3));
+    parser.screen_mut().set_scrollback(0);
+
+    // text drawn over an image replaces that part of it
+    parser.process(b"\x1b[1;4Hx");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 3);
+    assert_eq!(images[0].position(), (0, 2));
+    assert_eq!(images[0].size(), (1, 1));
+    assert_eq!(images[0].source_rect(), (0, 4, 2, 4));
+    assert_eq!(images[1].position(), (0, 4));
+    assert_eq!(images[1].size(), (1, 1));
+    assert_eq!(images[1].source_rect(), (4, 4, 1, 4));
+    assert_eq!(images[2].position(), (1, 2));
+    assert_eq!(images[2].size(), (1, 3));
+    assert_eq!(images[2].source_rect(), (0, 8, 5, 2));
+
+    parser.process(b"\x1b[2;1H\x1b[K\x1b[1;1H\x1b[K");
+    assert!(parser.screen().images().is_empty());
+
+    // images drawn at the bottom of the screen scroll it, and images which
+    // are too wide are cut off at the right edge of the screen
+    parser.process(b"\x1b[5;9H");
+    parser.process(sixel);
+    assert_eq!(parser.screen().cursor_position(), (4, 8));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].position(), (1, 8));
+    assert_eq!(images[0].size(), (3, 2));
+    assert_eq!(images[0].source_rect(), (0, 0, 4, 10));
+
+    // an image drawn on top of another replaces it
+    parser.process(b"\x1b[2;9H");
+    parser.process(sixel);
+    assert_eq!(parser.screen().images().len(), 1);
+
+    parser.process(b"\x1b[2J");
+    assert!(parser.screen().images().is_empty());
+}
+
+#[test]
+fn sixel_display_mode() {
+    let mut parser = vt100::Parser::new(2, 10, 0);
+    parser.screen_mut().set_cell_size(2, 4);
+    parser.process(b"\x1b[?80h\x1b[2;5H");
+    parser.process(b"\x1bPq#1;2;100;100;100!5~-!5N\x1b\\");
+    assert_eq!(parser.screen().cursor_position(), (1, 4));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].position(), (0, 0));
+    assert_eq!(images[0].size(), (2, 3));
+    assert_eq!(images[0].source_rect(), (0, 0, 5, 8));
+
+    let input_mode = parser.screen().input_mode_formatted();
+    assert!(input_mode.windows(6).any(|w| w == b"\x1b[?80h"));
+}
+
+// a 2x2 image, all red
+const KITTY_RGBA: &[u8] = b"/wAA//8AAP//AAD//wAA/w==";
+
+#[test]
+fn kitty_transmit() {
+    let mut parser = vt100::Parser::new_with_callbacks(
+        5,
+        10,
+        0,
+        helpers::Replies::default(),
+    );
+    parser.screen_mut().set_cell_size(2, 4);
+
+    parser.process(b"\x1b_Ga=T,f=32,s=2,v=2,i=1;");
+    parser.process(KITTY_RGBA);
+    parser.process(b"\x1b\\");
+    assert_eq!(parser.callbacks().replies, [b"\x1b_Gi=1;OK\x1b\\"]);
+    assert_eq!(parser.screen().cursor_position(), (0, 1));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].kitty_id(), Some((1, 0)));
+    assert_eq!(images[0].position(), (0, 0));
+    assert_eq!(images[0].size(), (1, 1));
+    assert_eq!(images[0].source_rect(), (0, 0, 2, 2));
+    assert_eq!(images[0].dest_rect(), (0, 0, 2, 2));
+    assert!(images[0]
+        .image()
+        .rgba()
+        .chunks(4)
+        .all(|pixel| pixel == [255, 0, 0, 255]));
+
+    // chunked transmission of rgb data, without displaying it. the rest of
+    // the sequence is still processed normally.
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b_Ga=t,f=24,s=2,v=2,i=2,m=1;AP8AAP8A\x1b\\");
+    parser.process(b"\x1b_Gm=1;AP8A\x1b\\\x1b_Gm=0;AP8A\x1b\\x");
+    assert_eq!(parser.callbacks().replies, [b"\x1b_Gi=2;OK\x1b\\"]);
+    assert_eq!(parser.screen().contents(), " x");
+    assert_eq!(parser.screen().images().len(), 1);
+
+    // placements can be offset within the cell, scaled, and drawn under
+    // the text without moving the cursor. text drawn over kitty images
+    // doesn't affect them.
+    parser.callbacks_mut().replies.clear();
+    parser.process(
+        b"\x1b[2;3H\x1b_Ga=p,i=2,p=7,c=2,r=1,X=1,Y=2,z=-1,C=1\x1b\\",
+    );
+    parser.process(b"ab");
+    assert_eq!(parser.callbacks().replies, [b"\x1b_Gi=2,p=7;OK\x1b\\"]);
+    assert_eq!(parser.screen().cursor_position(), (1, 4));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[1].kitty_id(), Some((2, 7)));
+    assert_eq!(images[1].position(), (1, 2));
+    assert_eq!(images[1].size(), (2, 3));
+    assert_eq!(images[1].source_rect(), (0, 0, 2, 2));
+    assert_eq!(images[1].dest_rect(), (1, 2, 4, 4));
+    assert_eq!(images[1].z_index(), -1);
+    assert!(images[1]
+        .image()
+        .rgba()
+        .chunks(4)
+        .all(|pixel| pixel == [0, 255, 0, 255]));
+
+    // placing it again with the same placement id moves it
+    parser.process(b"\x1b[4;1H\x1b_Ga=p,i=2,p=7,q=1\x1b\\");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[1].position(), (3, 0));
+    assert_eq!(images[1].size(), (1, 1));
+
+    // images can be referred to by number, in which case an id is assigned
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b_Ga=t,I=5,s=1,v=1;AAAA/w==\x1b\\");
+    parser.process(b"\x1b_Ga=p,I=5,q=1\x1b\\");
+    assert_eq!(parser.callbacks().replies, [b"\x1b_Gi=3,I=5;OK\x1b\\"]);
+    assert_eq!(parser.screen().images()[2].kitty_id(), Some((3, 0)));
+
+    // errors are reported unless q=2 is given, and nothing is reported for
+    // images without an id or number
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b_Ga=p,i=99\x1b\\");
+    parser.process(b"\x1b_Ga=t,f=100,i=4;AAAA\x1b\\");
+    parser.process(b"\x1b_Ga=t,s=2,v=2,i=4;AAAA\x1b\\");
+    parser.process(b"\x1b_Ga=t,o=z,i=4;AAAA\x1b\\");
+    parser.process(b"\x1b_Ga=p,i=99,q=2\x1b\\");
+    parser.process(b"\x1b_Ga=p\x1b\\");
+    assert_eq!(
+        parser.callbacks().replies,
+        [
+            &b"\x1b_Gi=99;ENOENT:image not found\x1b\\"[..],
+            b"\x1b_Gi=4;EINVAL:PNG is not supported\x1b\\",
+            b"\x1b_Gi=4;ENODATA:insufficient image data\x1b\\",
+            b"\x1b_Gi=4;EINVAL:compression is not supported\x1b\\",
+        ]
+    );
+    assert_eq!(parser.screen().images().len(), 3);
+}
+
+#[test]
+fn kitty_size_limits() {
+    let mut parser = vt100::Parser::new_with_callbacks(
+        5,
+        10,
+        0,
+        helpers::Replies::default(),
+    );
+
+    // payloads have to be chunked, so a single large command is dropped
+    let mut command = b"\x1b_Ga=t,s=100,v=50,i=1;".to_vec();
+    command.extend(b"AAAA".repeat(6667));
+    command.extend(b"\x1b\\");
+    parser.process(&command);
+    assert!(parser.callbacks().replies.is_empty());
+
+    // chunks can add up to as much data as the image needs
+    parser.process(b"\x1b_Ga=t,s=64,v=64,i=2,m=1;");
+    let data = b"AAAA".repeat(5462);
+    for chunk in data.chunks(4096) {
+        parser.process(chunk);
+        parser.process(b"\x1b\\\x1b_Gm=1;");
+    }
+    parser.process(b"\x1b\\\x1b_Gm=0;\x1b\\");
+    assert_eq!(parser.callbacks().replies, [b"\x1b_Gi=2;OK\x1b\\"]);
+
+    // but no more than that
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b_Ga=t,s=1,v=1,i=3,m=1;AAAA\x1b\\");
+    parser.process(b"\x1b_Gm=1;AAAA\x1b\\\x1b_Gm=1;AAAA\x1b\\");
+    parser.process(b"\x1b_Gm=0;\x1b\\");
+    parser.process(b"\x1b_Ga=t,s=1,v=1,i=4,m=1;AAAAAAAAAAAA\x1b\\");
+    parser.process(b"\x1b_Gm=0;\x1b\\");
+    assert_eq!(
+        parser.callbacks().replies,
+        [
+            &b"\x1b_Gi=3;EFBIG:image too large\x1b\\"[..],
+            b"\x1b_Gi=4;EFBIG:image too large\x1b\\",
+        ]
+    );
+}
+
+#[test]
+fn kitty_scrolling() {
+    let mut parser = vt100::Parser::new(5, 10, 10);
+    parser.screen_mut().set_cell_size(2, 4);
+
+    // images which don't fit scroll the screen, and the cursor ends up
+    // after the last cell of the image. giving only the number of rows
+    // keeps the aspect ratio.
+    parser.process(b"\x1b[5;3H\x1b_Ga=T,i=1,s=2,v=2,r=3;");
+    parser.process(KITTY_RGBA);
+    parser.process(b"\x1b\\");
+    assert_eq!(parser.screen().cursor_position(), (4, 8));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].position(), (2, 2));
+    assert_eq!(images[0].size(), (3, 6));
+    assert_eq!(images[0].dest_rect(), (0, 0, 12, 12));
+
+    // placements scroll along with the text, including into the
+    // scrollback
+    parser.process(b"\r\n\n\n\n");
+    let images = parser.screen().images();
+    assert_eq!(images[0].position(), (0, 2));
+    assert_eq!(images[0].size(), (1, 6));
+    assert_eq!(images[0].source_rect(), (0, 1, 2, 1));
+    assert_eq!(images[0].dest_rect(), (0, 0, 12, 4));
+    parser.screen_mut().set_scrollback(2);
+    let images = parser.screen().images();
+    assert_eq!(images[0].position(), (0, 2));
+    assert_eq!(images[0].size(), (3, 6));
+}
+
+#[test]
+fn kitty_delete() {
+    let mut parser = vt100::Parser::new_with_callbacks(
+        5,
+        10,
+        0,
+        helpers::Replies::default(),
+    );
+    parser.screen_mut().set_cell_size(2, 4);
+    parser.process(b"\x1b_Ga=t,i=1,s=2,v=2,q=1;");
+    parser.process(KITTY_RGBA);
+    parser.process(b"\x1b\\");
+    let place = |parser: &mut vt100::Parser<helpers::Replies>,
+                 pos: &str,
+                 args: &str| {
+        parser.process(
+            format!("\x1b[{pos}H\x1b_Ga=p,i=1,q=1,C=1,{args}\x1b\\")
+                .as_bytes(),
+        );
+    };
+
+    // delete by id and placement id
+    place(&mut parser, "1;1", "p=1");
+    place(&mut parser, "2;1", "p=2");
+    parser.process(b"\x1b_Ga=d,d=i,i=1,p=2\x1b\\");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    assert_eq!(images[0].kitty_id(), Some((1, 1)));
+    parser.process(b"\x1b_Ga=d,d=i,i=1\x1b\\");
+    assert!(parser.screen().images().is_empty());
+
+    // delete at the cursor, at a cell, in a column or row, or by z-index
+    place(&mut parser, "1;1", "p=1");
+    place(&mut parser, "2;3", "p=2,z=3");
+    place(&mut parser, "3;5", "p=3");
+    place(&mut parser, "4;7", "p=4,z=3");
+    place(&mut parser, "5;9", "p=5");
+    parser.process(b"\x1b[1;1H\x1b_Ga=d,d=c\x1b\\");
+    parser.process(b"\x1b_Ga=d,d=p,x=5,y=3\x1b\\");
+    let ids: Vec<_> = parser
+        .screen()
+        .images()
+        .iter()
+        .map(|i| i.kitty_id())
+        .collect();
+    assert_eq!(ids, [Some((1, 2)), Some((1, 4)), Some((1, 5))]);
+    parser.process(b"\x1b_Ga=d,d=z,z=3\x1b\\");
+    let ids: Vec<_> = parser
+        .screen()
+        .images()
+        .iter()
+        .map(|i| i.kitty_id())
+        .collect();
+    assert_eq!(ids, [Some((1, 5))]);
+    place(&mut parser, "1;9", "p=6");
+    parser.process(b"\x1b_Ga=d,d=x,x=9\x1b\\");
+    assert!(parser.screen().images().is_empty());
+    place(&mut parser, "1;1", "p=1");
+    place(&mut parser, "2;1", "p=2");
+    parser.process(b"\x1b_Ga=d,d=y,y=2\x1b\\");
+    assert_eq!(parser.screen().images().len(), 1);
+    parser.process(b"\x1b_Ga=d\x1b\\");
+    assert!(parser.screen().images().is_empty());
+
+    // the image data is kept unless the upper case variant is used
+    place(&mut parser, "1;1", "p=1");
+    parser.process(b"\x1b_Ga=d,d=I,i=1\x1b\\");
+    assert!(parser.screen().images().is_empty());
+    assert!(parser.callbacks().replies.is_empty());
+    parser.process(b"\x1b_Ga=p,i=1\x1b\\");
+    assert_eq!(
+        parser.callbacks().replies,
+        [b"\x1b_Gi=1;ENOENT:image not found\x1b\\"]
+    );
+}
+
+// the header of a 4x8 png file, which is all that is needed to know how
+// large it is
+const ITERM_PNG: &[u8] = b"iVBORw0KGgoAAAANSUhEUgAAAAQAAAAICAYAAAA=";
+
+#[test]
+fn iterm_inline_image() {
+    let mut parser = vt100::Parser::new(5, 10, 0);
+    parser.screen_mut().set_cell_size(2, 4);
+
+    let file = |args: &str, contents: &[u8]| {
+        let mut sequence = format!("\x1b]1337;File={args}:").into_bytes();
+        sequence.extend_from_slice(contents);
+        sequence.push(b'\x07');
+        sequence
+    };
+
+    // images are drawn at their natural size by default, and the cursor
+    // ends up after the last cell of the image
+    parser.process(&file("name=dGVzdA==;size=29;inline=1", ITERM_PNG));
+    assert_eq!(parser.screen().cursor_position(), (1, 2));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 1);
+    let image = images[0].image();
+    assert_eq!((image.width(), image.height()), (4, 8));
+    assert!(image.rgba().is_empty());
+    assert_eq!(image.file().unwrap().len(), 29);
+    assert!(image.file().unwrap().starts_with(b"\x89PNG"));
+    assert_eq!(images[0].position(), (0, 0));
+    assert_eq!(images[0].size(), (2, 2));
+    assert_eq!(images[0].source_rect(), (0, 0, 4, 8));
+    assert_eq!(images[0].dest_rect(), (0, 0, 4, 8));
+    assert_eq!(images[0].kitty_id(), None);
+
+    // sizes can be given in cells, pixels, or percent of the screen size,
+    // keeping the aspect ratio unless preserveAspectRatio=0
+    let mut sizes = vec![];
+    for args in [
+        "inline=1;width=3",
+        "inline=1;width=50%;height=2;preserveAspectRatio=0",
+        "inline=1;width=8px;height=8px",
+        "inline=1;height=auto;width=1px",
+    ] {
+        parser.process(b"\x1b[H\x1b[2J");
+        parser.process(&file(args, ITERM_PNG));
+        let images = parser.screen().images();
+        assert_eq!(images.len(), 1);
+        sizes.push((images[0].size(), images[0].dest_rect()));
+    }
+    assert_eq!(
+        sizes,
+        [
+            ((3, 3), (0, 0, 6, 12)),
+            ((2, 5), (0, 0, 10, 8)),
+            ((2, 2), (0, 0, 4, 8)),
+            ((1, 1), (0, 0, 1, 2)),
+        ]
+    );
+
+    // images which are too large for the screen are scaled down to fit it,
+    // and gif and jpeg files are supported too
+    parser.process(b"\x1b[H\x1b[2J");
+    parser.process(&file("inline=1", b"R0lGODlhKAAKAAAAAA=="));
+    parser.process(b"\r\n");
+    parser.process(&file("inline=1", b"/9j/4AAEYWL/wAALCAAGAAIBAREA"));
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(
+        (images[0].image().width(), images[0].image().height()),
+        (40, 10)
+    );
+    assert_eq!(images[0].size(), (2, 10));
+    assert_eq!(images[0].dest_rect(), (0, 0, 20, 5));
+    assert_eq!(
+        (images[1].image().width(), images[1].image().height()),
+        (2, 6)
+    );
+    assert_eq!(images[1].position(), (2, 0));
+    assert_eq!(images[1].size(), (2, 1));
+
+    // like sixel images, text drawn over them replaces them
+    parser.process(b"\x1b[1;1Hx");
+    let images = parser.screen().images();
+    assert_eq!(images.len(), 3);
+    assert_eq!(images[0].position(), (0, 1));
+
+    // files which aren't inline, are too large, or aren't in a format that
+    // we recognize are ignored
+    parser.process(b"\x1b[H\x1b[2J");
+    parser.process(&file("name=dGVzdA==", ITERM_PNG));
+    parser.process(&file("inline=1;size=100000000", ITERM_PNG));
+    parser.process(&file("inline=1", b"dGVzdA=="));
+    parser.process(&file("inline=1", b"not base64"));
+    parser.process(&file("inline=1;width=big", ITERM_PNG));
+    parser.process(b"\x1b]1337;SetMark\x07");
+    assert!(parser.screen().images().is_empty());
+}
+
+#[test]
+fn iterm_inline_image_stream() {
+    struct State {
+        unhandled: Vec<Vec<Vec<u8>>>,
+    }
+
+    impl vt100::Callbacks for State {
+        fn unhandled_osc(&mut self, _: &mut vt100::Screen, params: &[&[u8]]) {
+            self.unhandled
+                .push(params.iter().map(|param| param.to_vec()).collect());
+        }
+    }
+
+    let mut parser = vt100::Parser::new_with_callbacks(
+        5,
+        10,
+        0,
+        State { unhandled: vec![] },
+    );
+    parser.screen_mut().set_cell_size(2, 4);
+
+    // the command can arrive in any number of pieces, and be terminated by
+    // either BEL or ST
+    let mut sequence = b"a\x1b]1337;File=inline=1:".to_vec();
+    sequence.extend_from_slice(ITERM_PNG);
+    sequence.extend_from_slice(b"\x1b\\b");
+    for byte in &sequence {
+        parser.process(std::slice::from_ref(byte));
+    }
+    assert_eq!(parser.screen().images().len(), 1);
+    assert_eq!(parser.screen().contents(), "a\n   b");
+    assert!(parser.callbacks().unhandled.is_empty());
+
+    // files which are declared to be too large are dropped before their
+    // contents arrive, and cancelled commands are dropped entirely
+    parser.process(b"\x1b[H\x1b[2J");
+    parser.process(b"\x1b]1337;File=inline=1;size=100000000:");
+    parser.process(ITERM_PNG);
+    parser.process(b"\x07c\x1b]1337;File=inline=1:");
+    parser.process(ITERM_PNG);
+    parser.process(b"\x18d");
+    assert!(parser.screen().images().is_empty());
+    assert_eq!(parser.screen().contents(), "cd");
+    assert!(parser.callbacks().unhandled.is_empty());
+
+    // other commands are still reported as unhandled
+    parser.process(b"\x1b]1337;SetMark;x\x07\x1b]1337\x07");
+    assert_eq!(
+        parser.callbacks().unhandled,
+        [
+            vec![b"1337".to_vec(), b"SetMark".to_vec(), b"x".to_vec()],
+            vec![b"1337".to_vec()],
+        ]
+    );
+}
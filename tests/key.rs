@@ -0,0 +1,392 @@
+mod helpers;
+
+use vt100::{Key, KeyEvent, KeyEventKind, Modifiers};
+
+fn encode(parser: &vt100::Parser, key: Key, modifiers: Modifiers) -> Vec<u8> {
+    parser.screen().encode_key(&KeyEvent::new(key, modifiers))
+}
+
+#[test]
+fn text() {
+    let parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    assert_eq!(encode(&parser, Key::Char('a'), none), b"a");
+    assert_eq!(
+        parser.screen().encode_key(
+            &KeyEvent::new(Key::Char('a'), Modifiers::SHIFT).with_text("A")
+        ),
+        b"A"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .encode_key(&KeyEvent::new(Key::Char('e'), none).with_text("é")),
+        "é".as_bytes()
+    );
+    assert_eq!(encode(&parser, Key::Char('c'), Modifiers::CTRL), b"\x03");
+    assert_eq!(encode(&parser, Key::Char('['), Modifiers::CTRL), b"\x1b");
+    assert_eq!(encode(&parser, Key::Char(' '), Modifiers::CTRL), b"\x00");
+    assert_eq!(encode(&parser, Key::Char('x'), Modifiers::ALT), b"\x1bx");
+    assert_eq!(
+        encode(&parser, Key::Char('x'), Modifiers::ALT | Modifiers::CTRL),
+        b"\x1b\x18"
+    );
+    // control combinations without a control character are sent as is
+    assert_eq!(encode(&parser, Key::Char('1'), Modifiers::CTRL), b"1");
+
+    assert_eq!(encode(&parser, Key::Tab, none), b"\t");
+    assert_eq!(encode(&parser, Key::Tab, Modifiers::SHIFT), b"\x1b[Z");
+    assert_eq!(encode(&parser, Key::Escape, none), b"\x1b");
+    assert_eq!(encode(&parser, Key::Escape, Modifiers::ALT), b"\x1b\x1b");
+}
+
+#[test]
+fn enter_and_backspace() {
+    let mut parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    assert_eq!(encode(&parser, Key::Enter, none), b"\r");
+    assert_eq!(encode(&parser, Key::KeypadEnter, none), b"\r");
+    assert_eq!(encode(&parser, Key::Backspace, none), b"\x7f");
+    assert_eq!(encode(&parser, Key::Backspace, Modifiers::CTRL), b"\x08");
+
+    parser.process(b"\x1b[20h\x1b[?67h");
+    assert_eq!(encode(&parser, Key::Enter, none), b"\r\n");
+    assert_eq!(encode(&parser, Key::KeypadEnter, none), b"\r\n");
+    assert_eq!(encode(&parser, Key::Backspace, none), b"\x08");
+    assert_eq!(encode(&parser, Key::Backspace, Modifiers::CTRL), b"\x7f");
+    assert!(parser.screen().backarrow_key_mode());
+
+    let input_mode = parser.screen().input_mode_formatted();
+    assert!(input_mode.windows(6).any(|w| w == b"\x1b[?67h"));
+}
+
+#[test]
+fn cursor_keys() {
+    let mut parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1b[A");
+    assert_eq!(encode(&parser, Key::Left, none), b"\x1b[D");
+    assert_eq!(encode(&parser, Key::Home, none), b"\x1b[H");
+    assert_eq!(encode(&parser, Key::Up, Modifiers::CTRL), b"\x1b[1;5A");
+    assert_eq!(
+        encode(&parser, Key::End, Modifiers::SHIFT | Modifiers::ALT),
+        b"\x1b[1;4F"
+    );
+    assert_eq!(encode(&parser, Key::PageUp, none), b"\x1b[5~");
+    assert_eq!(encode(&parser, Key::Delete, Modifiers::CTRL), b"\x1b[3;5~");
+
+    parser.process(b"\x1b[?1h");
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1bOA");
+    assert_eq!(encode(&parser, Key::End, none), b"\x1bOF");
+    assert_eq!(encode(&parser, Key::Up, Modifiers::CTRL), b"\x1b[1;5A");
+    assert_eq!(encode(&parser, Key::Insert, none), b"\x1b[2~");
+}
+
+#[test]
+fn keypad() {
+    let mut parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    assert_eq!(encode(&parser, Key::Keypad('5'), none), b"5");
+    assert_eq!(encode(&parser, Key::Keypad('+'), none), b"+");
+
+    parser.process(b"\x1b=");
+    assert_eq!(encode(&parser, Key::Keypad('5'), none), b"\x1bOu");
+    assert_eq!(encode(&parser, Key::Keypad('+'), none), b"\x1bOk");
+    assert_eq!(encode(&parser, Key::Keypad('.'), none), b"\x1bOn");
+    assert_eq!(encode(&parser, Key::KeypadEnter, none), b"\x1bOM");
+    assert_eq!(encode(&parser, Key::Enter, none), b"\r");
+}
+
+#[test]
+fn function_keys() {
+    let parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    let keys: Vec<_> = (1..=24)
+        .map(|n| String::from_utf8(encode(&parser, Key::F(n), none)).unwrap())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "\x1bOP",
+            "\x1bOQ",
+            "\x1bOR",
+            "\x1bOS",
+            "\x1b[15~",
+            "\x1b[17~",
+            "\x1b[18~",
+            "\x1b[19~",
+            "\x1b[20~",
+            "\x1b[21~",
+            "\x1b[23~",
+            "\x1b[24~",
+            "\x1b[1;2P",
+            "\x1b[1;2Q",
+            "\x1b[1;2R",
+            "\x1b[1;2S",
+            "\x1b[15;2~",
+            "\x1b[17;2~",
+            "\x1b[18;2~",
+            "\x1b[19;2~",
+            "\x1b[20;2~",
+            "\x1b[21;2~",
+            "\x1b[23;2~",
+            "\x1b[24;2~",
+        ]
+    );
+    assert_eq!(encode(&parser, Key::F(1), Modifiers::CTRL), b"\x1b[1;5P");
+    assert_eq!(encode(&parser, Key::F(5), Modifiers::ALT), b"\x1b[15;3~");
+    assert_eq!(encode(&parser, Key::F(25), none), b"");
+}
+
+#[test]
+fn modify_other_keys() {
+    let mut parser = vt100::Parser::default();
+    assert_eq!(parser.screen().modify_other_keys(), 0);
+    let ctrl = Modifiers::CTRL;
+
+    // level 1 only changes keys which would otherwise lose modifiers
+    parser.process(b"\x1b[>4;1m");
+    assert_eq!(parser.screen().modify_other_keys(), 1);
+    assert_eq!(encode(&parser, Key::Char('a'), ctrl), b"\x01");
+    assert_eq!(encode(&parser, Key::Char('1'), ctrl), b"\x1b[27;5;49~");
+    assert_eq!(encode(&parser, Key::Enter, ctrl), b"\x1b[27;5;13~");
+    assert_eq!(encode(&parser, Key::Enter, Modifiers::ALT), b"\x1b\r");
+    assert_eq!(encode(&parser, Key::Backspace, ctrl), b"\x08");
+
+    // level 2 changes all modified keys other than shifted characters
+    parser.process(b"\x1b[>4;2m");
+    assert_eq!(parser.screen().modify_other_keys(), 2);
+    assert_eq!(encode(&parser, Key::Char('a'), ctrl), b"\x1b[27;5;97~");
+    assert_eq!(
+        parser.screen().encode_key(
+            &KeyEvent::new(Key::Char('a'), ctrl | Modifiers::SHIFT)
+                .with_text("A")
+        ),
+        b"\x1b[27;6;65~"
+    );
+    assert_eq!(
+        parser.screen().encode_key(
+            &KeyEvent::new(Key::Char('a'), Modifiers::SHIFT).with_text("A")
+        ),
+        b"A"
+    );
+    assert_eq!(encode(&parser, Key::Tab, Modifiers::SHIFT), b"\x1b[27;2;9~");
+    assert_eq!(encode(&parser, Key::Up, ctrl), b"\x1b[1;5A");
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[>4m");
+    assert_eq!(parser.screen().modify_other_keys(), 0);
+    assert_eq!(encode(&parser, Key::Char('a'), ctrl), b"\x01");
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[>4;0m");
+    assert_eq!(screen.input_mode_diff(parser.screen()), b"\x1b[>4;2m");
+}
+
+#[test]
+fn kitty_keyboard_flags() {
+    let mut parser = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    assert_eq!(parser.screen().keyboard_flags(), 0);
+    let screen = parser.screen().clone();
+
+    parser.process(b"\x1b[?u\x1b[>1u\x1b[>3u\x1b[?u");
+    assert_eq!(parser.screen().keyboard_flags(), 3);
+    parser.process(b"\x1b[=8;2u\x1b[?u\x1b[=2;3u\x1b[?u\x1b[=4u\x1b[?u");
+    assert_eq!(
+        parser.callbacks().replies,
+        [
+            &b"\x1b[?0u"[..],
+            b"\x1b[?3u",
+            b"\x1b[?11u",
+            b"\x1b[?9u",
+            b"\x1b[?4u",
+        ]
+    );
+    assert_eq!(
+        parser.screen().input_mode_diff(&screen),
+        b"\x1b[>1u\x1b[>4u"
+    );
+    assert!(parser
+        .screen()
+        .input_mode_formatted()
+        .ends_with(b"\x1b[>1u\x1b[>4u"));
+    parser.process(b"\x1b[<u");
+    assert_eq!(parser.screen().keyboard_flags(), 1);
+
+    // the alternate screen has its own stack
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1049h");
+    assert_eq!(parser.screen().keyboard_flags(), 0);
+    parser.process(b"\x1b[>31u");
+    assert_eq!(parser.screen().keyboard_flags(), 31);
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[>31u");
+    parser.process(b"\x1b[?1049l");
+    assert_eq!(parser.screen().keyboard_flags(), 1);
+
+    // popping more entries than there are empties the stack
+    parser.process(b"\x1b[>5u\x1b[>7u\x1b[<10u");
+    assert_eq!(parser.screen().keyboard_flags(), 0);
+    assert_eq!(
+        screen.input_mode_diff(parser.screen()),
+        b"\x1b[>1u\x1b[?47h\x1b[<1u\x1b[?47l"
+    );
+}
+
+#[test]
+fn kitty_keyboard_flags_alternate_screen() {
+    // both stacks are reproduced, whichever screen is active
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[>5u\x1b[?1049h\x1b[>1u");
+    let mut replay = vt100::Parser::default();
+    replay.process(&parser.screen().state_formatted());
+    assert!(replay.screen().alternate_screen());
+    assert_eq!(replay.screen().keyboard_flags(), 1);
+    parser.process(b"\x1b[?1049l");
+    replay.process(b"\x1b[?1049l");
+    assert_eq!(parser.screen().keyboard_flags(), 5);
+    assert_eq!(replay.screen().keyboard_flags(), 5);
+    parser.process(b"\x1b[?1049h");
+    replay.process(b"\x1b[?1049h");
+    assert_eq!(replay.screen().keyboard_flags(), 1);
+
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[>5u\x1b[>7u\x1b[?1049h\x1b[>1u\x1b[?1049l");
+    let mut replay = vt100::Parser::default();
+    replay.process(&parser.screen().state_formatted());
+    replay.process(b"\x1b[?1049h");
+    assert_eq!(replay.screen().keyboard_flags(), 1);
+
+    // the diff leaves the stack of the screen which was left alone
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[>5u\x1b[>7u");
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1049h\x1b[>5u");
+    let mut diffed = vt100::Parser::default();
+    diffed.process(&screen.state_formatted());
+    diffed.process(&parser.screen().state_diff(&screen));
+    assert!(diffed.screen().alternate_screen());
+    assert_eq!(diffed.screen().keyboard_flags(), 5);
+    parser.process(b"\x1b[?1049l");
+    diffed.process(b"\x1b[?1049l");
+    assert_eq!(parser.screen().keyboard_flags(), 7);
+    assert_eq!(diffed.screen().keyboard_flags(), 7);
+
+    // and changes to the inactive screen's stack are included too
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1049h\x1b[<1u\x1b[>3u\x1b[?1049l");
+    diffed.process(&parser.screen().state_diff(&screen));
+    assert_eq!(diffed.screen().keyboard_flags(), 7);
+    parser.process(b"\x1b[?1049h");
+    diffed.process(b"\x1b[?1049h");
+    assert_eq!(parser.screen().keyboard_flags(), 3);
+    assert_eq!(diffed.screen().keyboard_flags(), 3);
+}
+
+#[test]
+fn kitty_keyboard_encoding() {
+    let mut parser = vt100::Parser::default();
+    let none = Modifiers::NONE;
+    let ctrl = Modifiers::CTRL;
+    let shift_a = |kind| {
+        KeyEvent::new(Key::Char('a'), Modifiers::SHIFT)
+            .with_text("A")
+            .with_kind(kind)
+    };
+    let release = |key, modifiers| {
+        KeyEvent::new(key, modifiers).with_kind(KeyEventKind::Release)
+    };
+
+    // releases are never sent without the kitty keyboard protocol
+    assert_eq!(
+        parser.screen().encode_key(&release(Key::Char('a'), none)),
+        b""
+    );
+
+    // disambiguating escape codes only changes keys which would otherwise
+    // be ambiguous
+    parser.process(b"\x1b[>1u");
+    assert_eq!(encode(&parser, Key::Char('a'), none), b"a");
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Press)),
+        b"A"
+    );
+    assert_eq!(encode(&parser, Key::Char('a'), ctrl), b"\x1b[97;5u");
+    assert_eq!(
+        encode(&parser, Key::Char('a'), ctrl | Modifiers::ALT),
+        b"\x1b[97;7u"
+    );
+    assert_eq!(encode(&parser, Key::Escape, none), b"\x1b[27u");
+    assert_eq!(encode(&parser, Key::Enter, none), b"\r");
+    assert_eq!(encode(&parser, Key::Enter, ctrl), b"\x1b[13;5u");
+    assert_eq!(encode(&parser, Key::Backspace, none), b"\x7f");
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1b[A");
+    assert_eq!(encode(&parser, Key::Up, ctrl), b"\x1b[1;5A");
+    assert_eq!(encode(&parser, Key::F(3), ctrl), b"\x1b[13;5~");
+    assert_eq!(encode(&parser, Key::F(13), none), b"\x1b[57376u");
+    assert_eq!(encode(&parser, Key::Keypad('1'), none), b"1");
+    assert_eq!(encode(&parser, Key::Keypad('1'), ctrl), b"\x1b[57400;5u");
+    assert_eq!(encode(&parser, Key::KeypadEnter, ctrl), b"\x1b[57414;5u");
+    assert_eq!(
+        parser.screen().encode_key(&release(Key::Char('a'), ctrl)),
+        b""
+    );
+
+    // event types add releases and repeats, for keys which are sent as
+    // escape codes
+    parser.process(b"\x1b[=3u");
+    assert_eq!(
+        parser.screen().encode_key(&release(Key::Char('a'), ctrl)),
+        b"\x1b[97;5:3u"
+    );
+    assert_eq!(
+        parser.screen().encode_key(&release(Key::Up, none)),
+        b"\x1b[1;1:3A"
+    );
+    assert_eq!(
+        parser.screen().encode_key(
+            &KeyEvent::new(Key::Delete, none).with_kind(KeyEventKind::Repeat)
+        ),
+        b"\x1b[3;1:2~"
+    );
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Repeat)),
+        b"A"
+    );
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Release)),
+        b""
+    );
+    assert_eq!(parser.screen().encode_key(&release(Key::Enter, none)), b"");
+
+    // all keys can be sent as escape codes, optionally with the shifted
+    // key and the text that the key typed
+    parser.process(b"\x1b[=8u");
+    assert_eq!(encode(&parser, Key::Char('a'), none), b"\x1b[97u");
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Press)),
+        b"\x1b[97;2u"
+    );
+    assert_eq!(encode(&parser, Key::Enter, none), b"\x1b[13u");
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1b[A");
+    parser.process(b"\x1b[=31u");
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Press)),
+        b"\x1b[97:65;2;65u"
+    );
+    assert_eq!(encode(&parser, Key::Char('a'), none), b"\x1b[97;;97u");
+    assert_eq!(
+        parser.screen().encode_key(&shift_a(KeyEventKind::Release)),
+        b"\x1b[97:65;2:3u"
+    );
+    assert_eq!(encode(&parser, Key::Enter, none), b"\x1b[13u");
+
+    // application cursor mode still applies to keys which are sent the
+    // same way as without the kitty keyboard protocol
+    parser.process(b"\x1b[?1h\x1b[=1u");
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1bOA");
+    parser.process(b"\x1b[=8u");
+    assert_eq!(encode(&parser, Key::Up, none), b"\x1b[A");
+}
@@ -9,3 +9,156 @@ fn modes() {
 fn alternate_buffer() {
     helpers::fixture("alternate_buffer");
 }
+
+#[test]
+fn insert_mode() {
+    helpers::fixture("insert_mode");
+}
+
+#[test]
+fn newline_mode() {
+    helpers::fixture("newline_mode");
+}
+
+#[test]
+fn send_receive_mode() {
+    struct State {
+        unhandled: usize,
+    }
+
+    impl vt100::Callbacks for State {
+        fn unhandled_csi(
+            &mut self,
+            _: &mut vt100::Screen,
+            _: Option<u8>,
+            _: Option<u8>,
+            _: &[&[u16]],
+            _: char,
+        ) {
+            self.unhandled += 1;
+        }
+    }
+
+    let mut parser =
+        vt100::Parser::new_with_callbacks(24, 80, 0, State { unhandled: 0 });
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[12l\x1b[12h");
+    assert_eq!(parser.callbacks().unhandled, 0);
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"");
+    parser.process(b"\x1b[13h");
+    assert_eq!(parser.callbacks().unhandled, 1);
+}
+
+#[test]
+fn autowrap() {
+    helpers::fixture("autowrap");
+}
+
+#[test]
+fn reverse_wraparound() {
+    helpers::fixture("reverse_wraparound");
+}
+
+#[test]
+fn cursor_style() {
+    helpers::fixture("cursor_style");
+}
+
+#[test]
+fn cursor_style_formatted() {
+    let mut parser = vt100::Parser::default();
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[6 q");
+    assert_eq!(
+        parser.screen().cursor_style(),
+        vt100::CursorStyle::SteadyBar
+    );
+    assert_eq!(parser.screen().contents_diff(&screen), b"\x1b[6 q");
+    assert_eq!(
+        parser.screen().cursor_state_formatted(),
+        b"\x1b[?25h\x1b[6 q\x1b[H"
+    );
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[0 q\x1b[?12h");
+    assert_eq!(parser.screen().contents_diff(&screen), b"\x1b[0 q\x1b[?12h");
+    assert_eq!(
+        parser.screen().cursor_state_formatted(),
+        b"\x1b[?25h\x1b[?12h\x1b[H"
+    );
+}
+
+#[test]
+fn focus_events() {
+    let mut parser = vt100::Parser::default();
+    assert!(!parser.screen().focus_events());
+    assert_eq!(parser.screen().encode_focus(true), b"");
+    assert_eq!(parser.screen().encode_focus(false), b"");
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1004h");
+    assert!(parser.screen().focus_events());
+    assert_eq!(parser.screen().encode_focus(true), b"\x1b[I");
+    assert_eq!(parser.screen().encode_focus(false), b"\x1b[O");
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[?1004h");
+    assert!(parser
+        .screen()
+        .input_mode_formatted()
+        .windows(8)
+        .any(|w| w == b"\x1b[?1004h"));
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b[?1004l");
+    assert!(!parser.screen().focus_events());
+    assert_eq!(parser.screen().encode_focus(true), b"");
+    assert_eq!(parser.screen().input_mode_diff(&screen), b"\x1b[?1004l");
+}
+
+#[test]
+fn synchronized_output() {
+    let mut parser = vt100::Parser::default();
+    parser.process(b"abc");
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(parser.screen().frame().contents(), "abc");
+
+    parser.process(b"\x1b[?2026h\x1b[Hdef");
+    assert!(parser.screen().synchronized_output());
+    assert_eq!(parser.screen().contents(), "def");
+    assert_eq!(parser.screen().frame().contents(), "abc");
+    // starting another update while one is in progress keeps the same frame
+    parser.process(b"\x1b[?2026hghi");
+    assert_eq!(parser.screen().frame().contents(), "abc");
+
+    parser.process(b"\x1b[?2026l");
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(parser.screen().frame().contents(), "defghi");
+
+    // updates are ended automatically once they contain too much output
+    parser.process(b"\x1b[?2026h");
+    parser.process(&vec![0; 1024 * 1024]);
+    assert!(parser.screen().synchronized_output());
+    parser.process(&vec![0; 4 * 1024 * 1024]);
+    assert!(!parser.screen().synchronized_output());
+
+    // or once they take too long
+    parser
+        .screen_mut()
+        .set_synchronized_update_timeout(std::time::Duration::ZERO);
+    parser.process(b"\x1b[?2026h\x1b[2Jjkl");
+    assert!(!parser.screen().synchronized_output());
+    assert_eq!(parser.screen().frame().contents(), "      jkl");
+
+    // and an update which has timed out is replaced by the next one
+    parser
+        .screen_mut()
+        .set_synchronized_update_timeout(std::time::Duration::from_secs(60));
+    parser.process(b"\x1b[?2026hmno");
+    assert!(parser.screen().synchronized_output());
+    assert_eq!(parser.screen().frame().contents(), "      jkl");
+
+    // the frame doesn't include the scrollback
+    let mut parser = vt100::Parser::new(2, 10, 10);
+    parser.process(b"a\r\nb\r\nc\x1b[?2026hd");
+    assert_eq!(parser.screen().contents_full(), "a\nb\ncd");
+    assert_eq!(parser.screen().frame().contents_full(), "b\nc");
+}
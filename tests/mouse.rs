@@ -0,0 +1,273 @@
+mod helpers;
+
+use vt100::{Modifiers, MouseButton, MouseEvent, MouseEventKind};
+
+fn encode<CB: vt100::Callbacks>(
+    parser: &mut vt100::Parser<CB>,
+    kind: MouseEventKind,
+    row: u16,
+    col: u16,
+) -> Vec<u8> {
+    parser
+        .screen_mut()
+        .encode_mouse(&MouseEvent::new(kind, row, col))
+}
+
+#[test]
+fn modes() {
+    let press = MouseEventKind::Press(MouseButton::Left);
+    let release = MouseEventKind::Release(MouseButton::Left);
+    let drag = MouseEventKind::Motion(Some(MouseButton::Left));
+    let motion = MouseEventKind::Motion(None);
+
+    let mut parser = vt100::Parser::default();
+    assert_eq!(encode(&mut parser, press, 0, 0), b"");
+
+    parser.process(b"\x1b[?9h");
+    assert_eq!(encode(&mut parser, press, 0, 0), b"\x1b[M !!");
+    assert_eq!(encode(&mut parser, release, 0, 0), b"");
+    // x10 mode doesn't report modifiers
+    assert_eq!(
+        parser.screen_mut().encode_mouse(
+            &MouseEvent::new(press, 0, 0).with_modifiers(Modifiers::CTRL)
+        ),
+        b"\x1b[M !!"
+    );
+
+    parser.process(b"\x1b[?1000h");
+    assert_eq!(encode(&mut parser, press, 1, 2), b"\x1b[M #\"");
+    assert_eq!(encode(&mut parser, release, 1, 2), b"\x1b[M##\"");
+    assert_eq!(encode(&mut parser, drag, 1, 3), b"");
+    assert_eq!(
+        parser.screen_mut().encode_mouse(
+            &MouseEvent::new(MouseEventKind::Press(MouseButton::Right), 0, 0)
+                .with_modifiers(Modifiers::SHIFT | Modifiers::CTRL)
+        ),
+        b"\x1b[M6!!"
+    );
+    // wheel events are reported as presses only
+    assert_eq!(
+        encode(
+            &mut parser,
+            MouseEventKind::Press(MouseButton::WheelUp),
+            0,
+            0
+        ),
+        b"\x1b[M`!!"
+    );
+    assert_eq!(
+        encode(
+            &mut parser,
+            MouseEventKind::Release(MouseButton::WheelUp),
+            0,
+            0
+        ),
+        b""
+    );
+
+    parser.process(b"\x1b[?1002h");
+    assert_eq!(encode(&mut parser, press, 0, 0), b"\x1b[M !!");
+    assert_eq!(encode(&mut parser, drag, 0, 1), b"\x1b[M@\"!");
+    // motion within the same cell isn't reported
+    assert_eq!(encode(&mut parser, drag, 0, 1), b"");
+    assert_eq!(encode(&mut parser, release, 0, 1), b"\x1b[M#\"!");
+    assert_eq!(encode(&mut parser, motion, 0, 2), b"");
+
+    parser.process(b"\x1b[?1003h");
+    assert_eq!(encode(&mut parser, motion, 0, 2), b"\x1b[MC#!");
+    assert_eq!(encode(&mut parser, motion, 0, 2), b"");
+    assert_eq!(encode(&mut parser, motion, 0, 3), b"\x1b[MC$!");
+
+    parser.process(b"\x1b[?1003l");
+    assert_eq!(encode(&mut parser, press, 0, 0), b"");
+}
+
+#[test]
+fn encodings() {
+    let press = MouseEventKind::Press(MouseButton::Middle);
+    let release = MouseEventKind::Release(MouseButton::Middle);
+
+    let mut parser = vt100::Parser::new(24, 300, 0);
+    parser.process(b"\x1b[?1000h");
+    assert_eq!(encode(&mut parser, press, 0, 222), b"\x1b[M!\xff!");
+    // positions past 223 can't be represented
+    assert_eq!(encode(&mut parser, press, 0, 223), b"");
+
+    parser.process(b"\x1b[?1005h");
+    assert_eq!(
+        encode(&mut parser, press, 0, 222),
+        "\x1b[M!\u{ff}!".as_bytes()
+    );
+    assert_eq!(
+        encode(&mut parser, press, 0, 299),
+        "\x1b[M!\u{14c}!".as_bytes()
+    );
+
+    parser.process(b"\x1b[?1015h");
+    assert_eq!(encode(&mut parser, press, 4, 299), b"\x1b[33;300;5M");
+    assert_eq!(encode(&mut parser, release, 4, 299), b"\x1b[35;300;5M");
+
+    parser.process(b"\x1b[?1006h");
+    assert_eq!(encode(&mut parser, press, 4, 299), b"\x1b[<1;300;5M");
+    assert_eq!(encode(&mut parser, release, 4, 299), b"\x1b[<1;300;5m");
+    assert_eq!(
+        parser.screen_mut().encode_mouse(
+            &MouseEvent::new(MouseEventKind::Press(MouseButton::Back), 0, 0)
+                .with_modifiers(Modifiers::ALT)
+        ),
+        b"\x1b[<136;1;1M"
+    );
+
+    parser.process(b"\x1b[?1016h");
+    assert_eq!(encode(&mut parser, press, 1, 2), b"\x1b[<1;21;21M");
+    assert_eq!(
+        parser.screen_mut().encode_mouse(
+            &MouseEvent::new(release, 1, 2).with_pixel_position(25, 27)
+        ),
+        b"\x1b[<1;26;28m"
+    );
+
+    parser.process(b"\x1b[?1016l");
+    assert_eq!(
+        parser.screen().mouse_protocol_encoding(),
+        vt100::MouseProtocolEncoding::Default
+    );
+}
+
+#[test]
+fn highlight() {
+    let press = MouseEventKind::Press(MouseButton::Left);
+    let release = MouseEventKind::Release(MouseButton::Left);
+
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[?1001h");
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        vt100::MouseProtocolMode::Highlight
+    );
+
+    // without a response from the application, this acts like 1000
+    assert_eq!(encode(&mut parser, press, 0, 0), b"\x1b[M !!");
+    assert_eq!(encode(&mut parser, release, 0, 0), b"\x1b[M#!!");
+
+    // the application starts highlighting at the press position, limited to
+    // rows 1 through 3
+    assert_eq!(encode(&mut parser, press, 1, 4), b"\x1b[M %\"");
+    parser.process(b"\x1b[1;5;2;1;4T");
+    assert_eq!(encode(&mut parser, release, 5, 9), b"\x1b[T%\"*#*&");
+
+    // releasing where the highlight started
+    assert_eq!(encode(&mut parser, press, 1, 4), b"\x1b[M %\"");
+    parser.process(b"\x1b[1;5;2;1;4T");
+    assert_eq!(encode(&mut parser, release, 1, 4), b"\x1b[t%\"");
+
+    // the application can also decline to highlight
+    assert_eq!(encode(&mut parser, press, 1, 4), b"\x1b[M %\"");
+    parser.process(b"\x1b[0;5;2;1;4T");
+    assert_eq!(encode(&mut parser, release, 1, 4), b"\x1b[M#%\"");
+
+    // a single parameter is still a scroll down
+    parser.process(b"abc\x1b[T");
+    assert_eq!(parser.screen().contents(), "\nabc");
+}
+
+#[test]
+fn dec_locator() {
+    let press = MouseEventKind::Press(MouseButton::Left);
+    let release = MouseEventKind::Release(MouseButton::Left);
+    let motion = MouseEventKind::Motion(None);
+
+    let mut parser = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    // requests are ignored until the locator is enabled
+    parser.process(b"\x1b['|");
+    assert!(parser.callbacks().replies.is_empty());
+
+    parser.process(b"\x1b[1'z");
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        vt100::MouseProtocolMode::DecLocator
+    );
+    // the position isn't known until there has been a mouse event
+    parser.process(b"\x1b['|");
+    assert_eq!(parser.callbacks().replies, [b"\x1b[0&w"]);
+
+    // only requests are answered by default
+    assert_eq!(encode(&mut parser, motion, 2, 3), b"");
+    assert_eq!(encode(&mut parser, press, 2, 3), b"");
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b[1'|");
+    assert_eq!(parser.callbacks().replies, [b"\x1b[1;4;3;4;1&w"]);
+    assert_eq!(encode(&mut parser, release, 2, 3), b"");
+
+    parser.process(b"\x1b[1;3'{");
+    assert_eq!(encode(&mut parser, press, 2, 3), b"\x1b[2;4;3;4;1&w");
+    assert_eq!(
+        encode(&mut parser, MouseEventKind::Press(MouseButton::Right), 2, 3),
+        b"\x1b[6;5;3;4;1&w"
+    );
+    assert_eq!(encode(&mut parser, release, 2, 3), b"\x1b[3;1;3;4;1&w");
+    parser.process(b"\x1b[2'{");
+    assert_eq!(
+        encode(
+            &mut parser,
+            MouseEventKind::Release(MouseButton::Right),
+            2,
+            3
+        ),
+        b"\x1b[7;0;3;4;1&w"
+    );
+    // wheel events aren't locator buttons
+    assert_eq!(
+        encode(
+            &mut parser,
+            MouseEventKind::Press(MouseButton::WheelUp),
+            2,
+            3
+        ),
+        b""
+    );
+
+    // the filter rectangle reports once when the mouse leaves it
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b[2;2;5;10'w");
+    assert!(parser.callbacks().replies.is_empty());
+    assert_eq!(encode(&mut parser, motion, 4, 9), b"");
+    assert_eq!(encode(&mut parser, motion, 5, 9), b"\x1b[10;0;6;10;1&w");
+    assert_eq!(encode(&mut parser, motion, 6, 9), b"");
+    // omitted edges default to the current position
+    parser.process(b"\x1b['w");
+    assert_eq!(encode(&mut parser, motion, 6, 9), b"");
+    assert_eq!(encode(&mut parser, motion, 6, 10), b"\x1b[10;0;7;11;1&w");
+    // a rectangle which doesn't contain the mouse reports immediately
+    parser.process(b"\x1b[1;1;2;2'w");
+    assert_eq!(parser.callbacks().replies, [b"\x1b[10;0;7;11;1&w"]);
+
+    // one shot reports in pixels
+    let prev = parser.screen().clone();
+    parser.process(b"\x1b[2;1'z\x1b[0'{");
+    assert_eq!(
+        parser.screen().input_mode_diff(&prev),
+        b"\x1b[2;1'z\x1b[2;4'{"
+    );
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1b['|\x1b['|");
+    assert_eq!(parser.callbacks().replies, [b"\x1b[1;0;121;101;1&w"]);
+    assert_eq!(
+        parser.screen().mouse_protocol_mode(),
+        vt100::MouseProtocolMode::None
+    );
+
+    parser.process(b"\x1b[1'z");
+    let prev = parser.screen().clone();
+    parser.process(b"\x1b[0'z");
+    assert_eq!(parser.screen().input_mode_diff(&prev), b"\x1b['z");
+
+    // other mouse modes replace the locator
+    parser.process(b"\x1b[1'z\x1b[?1000h");
+    assert_eq!(encode(&mut parser, press, 0, 0), b"\x1b[M !!");
+}
@@ -82,3 +82,330 @@ fn clipboard() {
 fn unknown_osc() {
     helpers::fixture("unknown_osc");
 }
+
+#[test]
+fn hyperlink() {
+    helpers::fixture("hyperlink");
+}
+
+#[test]
+fn hyperlink_formatted() {
+    let mut parser = vt100::Parser::default();
+    parser.process(
+        b"\x1b]8;id=x;https://example.com/\x1b\\ab\x1b]8;;\x1b\\c\x1b[m",
+    );
+    let id = parser.screen().cell(0, 0).unwrap().hyperlink().unwrap();
+    assert_eq!(parser.screen().cell(0, 1).unwrap().hyperlink(), Some(id));
+    assert_eq!(parser.screen().cell(0, 2).unwrap().hyperlink(), None);
+    let link = parser.screen().hyperlink(id).unwrap();
+    assert_eq!(link.uri(), "https://example.com/");
+    assert_eq!(link.id(), Some("x"));
+    assert_eq!(
+        parser.screen().contents_formatted(),
+        b"\x1b[?25h\x1b[m\x1b[H\x1b[J\x1b]8;id=x;https://example.com/\x1b\\ab\x1b]8;;\x1b\\c"
+    );
+
+    // sgr 0 doesn't end the link, and the same link gets the same id
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b]8;id=x;https://example.com/\x1b\\\x1b[31md\x1b[me");
+    assert_eq!(parser.screen().active_hyperlink(), Some(id));
+    assert_eq!(parser.screen().cell(0, 4).unwrap().hyperlink(), Some(id));
+    assert_eq!(
+        parser.screen().contents_diff(&screen),
+        b"\x1b]8;id=x;https://example.com/\x1b\\\x1b[31md\x1b[me"
+    );
+
+    // erasing removes the link from the cells
+    parser.process(b"\x1b[H\x1b[K");
+    assert_eq!(parser.screen().cell(0, 0).unwrap().hyperlink(), None);
+    assert_eq!(parser.screen().active_hyperlink(), Some(id));
+}
+
+#[test]
+fn hyperlink_ids() {
+    // ids are reused once nothing refers to their link, so links are
+    // compared by value between screens
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b]8;;https://example.com/\x1b\\a");
+    let mut other = vt100::Parser::default();
+    other.process(
+        b"\x1b]8;;https://example.org/\x1b\\\x1b]8;;https://example.com/\x1b\\a",
+    );
+    assert_ne!(
+        parser.screen().cell(0, 0).unwrap().hyperlink(),
+        other.screen().cell(0, 0).unwrap().hyperlink()
+    );
+    assert_eq!(other.screen().contents_diff(parser.screen()), b"");
+    assert_eq!(
+        parser
+            .screen()
+            .contents_diff(vt100::Parser::default().screen()),
+        b"\x1b]8;;https://example.com/\x1b\\a"
+    );
+
+    // the table is limited to u16 ids, but links which aren't on the
+    // screen any more don't take up space in it
+    parser.process(b"\r\n");
+    let screen = parser.screen().clone();
+    for i in 0..70000 {
+        parser.process(
+            format!("\x1b]8;;https://example.com/{i}\x1b\\\rb").as_bytes(),
+        );
+    }
+    let link = |row, col| {
+        let id = parser.screen().cell(row, col).unwrap().hyperlink()?;
+        Some(parser.screen().hyperlink(id)?.uri().to_string())
+    };
+    assert_eq!(link(0, 0).as_deref(), Some("https://example.com/"));
+    assert_eq!(link(1, 0).as_deref(), Some("https://example.com/69999"));
+    assert_eq!(
+        parser.screen().contents_diff(&screen),
+        b"\x1b]8;;https://example.com/69999\x1b\\b"
+    );
+
+    // overly long uris are ignored
+    parser.process(b"\x1b]8;;\x1b\\");
+    let uri = "a".repeat(2083);
+    parser.process(format!("\x1b]8;;{uri}\x1b\\").as_bytes());
+    assert!(parser.screen().active_hyperlink().is_some());
+    parser.process(b"\x1b]8;;\x1b\\");
+    parser.process(format!("\x1b]8;;{uri}a\x1b\\").as_bytes());
+    assert!(parser.screen().active_hyperlink().is_none());
+}
+
+#[test]
+fn palette() {
+    let mut parser = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        helpers::Replies::default(),
+    );
+    assert_eq!(parser.screen().palette_color(1), (205, 0, 0));
+    assert_eq!(parser.screen().palette_color(16), (0, 0, 0));
+    assert_eq!(parser.screen().palette_color(196), (255, 0, 0));
+    assert_eq!(parser.screen().palette_color(244), (128, 128, 128));
+    assert_eq!(parser.screen().palette_formatted(), b"");
+
+    parser.process(b"\x1b]4;1;rgb:12/34/56;200;#abc\x07");
+    parser.process(b"\x1b]4;3;rgb:f/ffff/80\x1b\\");
+    assert_eq!(parser.screen().palette_color(1), (0x12, 0x34, 0x56));
+    assert_eq!(parser.screen().palette_color(200), (0xa0, 0xb0, 0xc0));
+    assert_eq!(parser.screen().palette_color(3), (0xff, 0xff, 0x80));
+
+    parser.process(b"\x1b]10;#102030\x07\x1b]11;rgb:0/0/0;#ffffff\x07");
+    assert_eq!(parser.screen().foreground_color(), (0x10, 0x20, 0x30));
+    assert_eq!(parser.screen().background_color(), (0, 0, 0));
+    assert_eq!(parser.screen().cursor_color(), (0xff, 0xff, 0xff));
+
+    assert_eq!(
+        parser.screen().palette_formatted(),
+        b"\x1b]4;1;rgb:12/34/56\x1b\\\x1b]4;3;rgb:ff/ff/80\x1b\\\x1b]4;200;rgb:a0/b0/c0\x1b\\\x1b]10;rgb:10/20/30\x1b\\\x1b]11;rgb:00/00/00\x1b\\\x1b]12;rgb:ff/ff/ff\x1b\\"
+    );
+
+    parser.process(b"\x1b]4;1;?;2;?\x07\x1b]10;?;?\x1b\\\x1b]12;?\x07");
+    assert_eq!(
+        parser.callbacks().replies,
+        [
+            &b"\x1b]4;1;rgb:1212/3434/5656\x07\x1b]4;2;rgb:0000/cdcd/0000\x07"[..],
+            b"\x1b]10;rgb:1010/2020/3030\x1b\\\x1b]11;rgb:0000/0000/0000\x1b\\",
+            b"\x1b]12;rgb:ffff/ffff/ffff\x07",
+        ]
+    );
+
+    let screen = parser.screen().clone();
+    parser.process(b"\x1b]104;1;3\x07\x1b]111\x07\x1b]4;5;#000\x07");
+    assert_eq!(parser.screen().palette_color(1), (205, 0, 0));
+    assert_eq!(parser.screen().palette_color(200), (0xa0, 0xb0, 0xc0));
+    assert_eq!(parser.screen().background_color(), (0, 0, 0));
+    assert_eq!(
+        parser.screen().palette_diff(&screen),
+        b"\x1b]104;1\x1b\\\x1b]104;3\x1b\\\x1b]4;5;rgb:00/00/00\x1b\\\x1b]111\x1b\\"
+    );
+    helpers::assert_contents_diff_reproduces_state_from_screens(
+        &screen,
+        parser.screen(),
+    );
+
+    parser.process(b"\x1b]104\x07\x1b]110\x07\x1b]112\x07");
+    assert_eq!(parser.screen().palette_formatted(), b"");
+
+    parser
+        .screen_mut()
+        .set_default_colors(vt100::DefaultColors {
+            background: (0xff, 0xff, 0xff),
+            ..Default::default()
+        });
+    parser.callbacks_mut().replies.clear();
+    parser.process(b"\x1bc\x1b]11;?\x07");
+    assert_eq!(
+        parser.callbacks().replies,
+        [b"\x1b]11;rgb:ffff/ffff/ffff\x07"]
+    );
+}
+
+#[test]
+fn cwd() {
+    let mut parser = vt100::Parser::default();
+    assert_eq!(parser.screen().cwd(), None);
+    parser.process(b"\x1b]7;file://host/home/user\x07");
+    assert_eq!(parser.screen().cwd(), Some("file://host/home/user"));
+    parser.process(b"\x1b]7;file://host/tmp/a;b\x1b\\");
+    assert_eq!(parser.screen().cwd(), Some("file://host/tmp/a;b"));
+}
+
+#[test]
+fn semantic_prompt() {
+    let mut parser = vt100::Parser::new(5, 20, 10);
+    assert_eq!(parser.screen().commands(), vec![]);
+
+    // a finished command with output
+    parser.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+    parser.process(b"a\r\nb\r\n\x1b]133;D;0\x07");
+    // a failed command without output
+    parser.process(b"\x1b]133;A\x07$ \x1b]133;B\x07false\r\n\x1b]133;C\x07");
+    parser.process(b"\x1b]133;D;1\x07");
+    // a command that is still being typed
+    parser.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ec");
+
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 3);
+
+    assert_eq!(commands[0].prompt_rows(), 0..1);
+    assert_eq!(commands[0].input_rows(), Some(0..1));
+    assert_eq!(commands[0].output_rows(), Some(1..3));
+    assert_eq!(commands[0].exit_code(), Some(0));
+    assert!(commands[0].is_finished());
+
+    assert_eq!(commands[1].prompt_rows(), 3..4);
+    assert_eq!(commands[1].input_rows(), Some(3..4));
+    assert_eq!(commands[1].output_rows(), Some(4..5));
+    assert_eq!(commands[1].exit_code(), Some(1));
+    assert!(commands[1].is_finished());
+
+    assert_eq!(commands[2].prompt_rows(), 4..5);
+    assert_eq!(commands[2].input_rows(), Some(4..5));
+    assert_eq!(commands[2].output_rows(), None);
+    assert_eq!(commands[2].exit_code(), None);
+    assert!(!commands[2].is_finished());
+
+    // markers scroll into the scrollback along with their rows
+    parser.process(b"ho\r\n\x1b]133;C\x07x\r\ny\r\n");
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 3);
+    assert_eq!(commands[0].output_rows(), Some(1..3));
+    assert_eq!(commands[2].input_rows(), Some(4..5));
+    assert_eq!(commands[2].output_rows(), Some(5..8));
+    let rows: Vec<_> = parser.screen().rows_full(0, 20).collect();
+    assert_eq!(rows[1..3], ["a", "b"]);
+    assert_eq!(rows[5..7], ["x", "y"]);
+
+    // the markers belong to the rows, so redrawing or clearing the text in
+    // them leaves the markers alone
+    let commands = parser.screen().commands();
+    parser.process(b"\x1b[1;1H\x1b[2K\r\x1b[K\x1b[H\x1b[2J");
+    assert_eq!(parser.screen().commands(), commands);
+
+    let mut parser = vt100::Parser::new(5, 20, 0);
+    parser.process(b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07");
+    parser.process(b"\x1b]133;D;0\x07");
+    parser.process(b"\x1b[H\x1b[2K");
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].prompt_rows(), 0..1);
+    assert_eq!(commands[0].exit_code(), Some(0));
+
+    // markers between the left and right margins scroll with the cells
+    // there, and the ones outside stay put
+    parser.process(b"\x1b[?69h\x1b[3;20s\x1b[5;3H\n");
+    let commands = parser.screen().commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].prompt_rows(), 0..1);
+    assert_eq!(commands[0].input_rows(), None);
+    assert_eq!(commands[0].output_rows(), Some(1..2));
+    assert_eq!(commands[0].exit_code(), Some(0));
+    parser.process(b"\x1b[1;3H\x1b]133;B\x07");
+    assert_eq!(parser.screen().commands()[0].input_rows(), Some(0..1));
+    parser.process(b"\x1b[T");
+    assert_eq!(parser.screen().commands()[0].input_rows(), None);
+    parser.process(b"\x1b[S");
+    assert_eq!(parser.screen().commands()[0].input_rows(), Some(0..1));
+    parser.process(b"\x1b[1;20s\x1b[5;1H\n");
+    assert_eq!(parser.screen().commands(), vec![]);
+}
+
+#[test]
+fn notifications() {
+    #[derive(Default)]
+    struct Notifications {
+        notifications: Vec<vt100::Notification>,
+        unhandled: usize,
+    }
+    impl vt100::Callbacks for Notifications {
+        fn notify(
+            &mut self,
+            _: &mut vt100::Screen,
+            notification: &vt100::Notification,
+        ) {
+            self.notifications.push(notification.clone());
+        }
+
+        fn unhandled_osc(&mut self, _: &mut vt100::Screen, _: &[&[u8]]) {
+            self.unhandled += 1;
+        }
+    }
+
+    let mut parser = vt100::Parser::new_with_callbacks(
+        24,
+        80,
+        0,
+        Notifications::default(),
+    );
+
+    parser.process(b"\x1b]9;build finished; 3 warnings\x07");
+    parser.process(b"\x1b]777;notify;make;done\x1b\\");
+    let notifications = &parser.callbacks().notifications;
+    assert_eq!(notifications.len(), 2);
+    assert_eq!(notifications[0].title(), "");
+    assert_eq!(notifications[0].body(), "build finished; 3 warnings");
+    assert_eq!(notifications[0].id(), None);
+    assert_eq!(notifications[0].urgency(), vt100::Urgency::Normal);
+    assert_eq!(notifications[1].title(), "make");
+    assert_eq!(notifications[1].body(), "done");
+
+    // ConEmu progress reports are not notifications
+    parser.process(b"\x1b]9;4;1;50\x07");
+    assert_eq!(parser.callbacks().notifications.len(), 2);
+    assert_eq!(parser.callbacks().unhandled, 1);
+
+    parser.process(b"\x1b]99;;hello\x07");
+    let notification = parser.callbacks().notifications.last().unwrap();
+    assert_eq!(notification.title(), "hello");
+    assert_eq!(notification.body(), "");
+
+    // chunked, with a base64 body split in the middle of a character
+    parser.process(b"\x1b]99;i=a1:d=0:u=2;Build\x1b\\");
+    parser.process(b"\x1b]99;i=a1:d=0:p=body:e=1;w6k=\x1b\\");
+    parser.process(b"\x1b]99;i=a1:d=0:p=body;\xc3\x1b\\");
+    assert_eq!(parser.callbacks().notifications.len(), 3);
+    parser.process(b"\x1b]99;i=a1:p=body;\xa9\x1b\\");
+    let notification = parser.callbacks().notifications.last().unwrap();
+    assert_eq!(parser.callbacks().notifications.len(), 4);
+    assert_eq!(notification.id(), Some("a1"));
+    assert_eq!(notification.title(), "Build");
+    assert_eq!(notification.body(), "éé");
+    assert_eq!(notification.urgency(), vt100::Urgency::Critical);
+
+    // a chunk for a different notification discards the unfinished one
+    parser.process(b"\x1b]99;i=a:d=0;one\x1b\\");
+    parser.process(b"\x1b]99;i=b;two\x1b\\");
+    let notification = parser.callbacks().notifications.last().unwrap();
+    assert_eq!(notification.id(), Some("b"));
+    assert_eq!(notification.title(), "two");
+
+    // queries and invalid metadata are left to the application
+    parser.process(b"\x1b]99;i=c:p=?;\x1b\\");
+    parser.process(b"\x1b]99;e=1;not base64!\x1b\\");
+    assert_eq!(parser.callbacks().notifications.len(), 5);
+    assert_eq!(parser.callbacks().unhandled, 3);
+}
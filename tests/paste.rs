@@ -0,0 +1,71 @@
+#[test]
+fn encode_paste() {
+    let mut parser = vt100::Parser::default();
+    assert_eq!(parser.screen().encode_paste(b"ls -l"), b"ls -l");
+    assert_eq!(parser.screen().encode_paste(b"a\nb\r\nc\rd"), b"a\rb\rc\rd");
+    assert_eq!(parser.screen().encode_paste(b"a\tb\x03c\x7f"), b"a\tbc");
+    assert_eq!(
+        parser.screen().encode_paste("é\u{9b}31m".as_bytes()),
+        "é31m".as_bytes()
+    );
+
+    parser.process(b"\x1b[?2004h");
+    assert_eq!(
+        parser.screen().encode_paste(b"ls -l\n"),
+        b"\x1b[200~ls -l\r\x1b[201~"
+    );
+    // the pasted text can't end the paste itself
+    assert_eq!(
+        parser.screen().encode_paste(b"a\x1b[201~rm -rf ~\n"),
+        b"\x1b[200~arm -rf ~\r\x1b[201~"
+    );
+}
+
+#[test]
+fn paste_controls() {
+    let mut parser = vt100::Parser::default();
+    parser.process(b"\x1b[?2004h");
+
+    parser
+        .screen_mut()
+        .set_paste_controls(vt100::PasteControls::Replace);
+    assert_eq!(
+        parser
+            .screen()
+            .encode_paste("a\x1b[31m\x00\x7f\u{85}\n".as_bytes()),
+        "\x1b[200~a\u{241b}[31m\u{2400}\u{2421}\u{fffd}\r\x1b[201~"
+            .as_bytes()
+    );
+    assert_eq!(
+        parser.screen().encode_paste(b"\x1b[201~"),
+        b"\x1b[200~\x1b[201~"
+    );
+
+    parser
+        .screen_mut()
+        .set_paste_controls(vt100::PasteControls::Allow);
+    assert_eq!(
+        parser.screen().encode_paste(b"a\x1b[31m\x03\n"),
+        b"\x1b[200~a\x1b[31m\x03\r\x1b[201~"
+    );
+    // markers are removed even when other controls are allowed, including
+    // ones which only appear once another is removed
+    assert_eq!(
+        parser.screen().encode_paste(b"a\x1b[20\x1b[201~1~b"),
+        b"\x1b[200~ab\x1b[201~"
+    );
+    assert_eq!(
+        parser.screen().encode_paste(b"\x1b[200~x"),
+        b"\x1b[200~x\x1b[201~"
+    );
+    assert_eq!(
+        parser
+            .screen()
+            .encode_paste(b"a\x1b[20\x1b[20\x1b[201~0~1~b"),
+        b"\x1b[200~ab\x1b[201~"
+    );
+
+    // the setting is kept across a reset
+    parser.process(b"\x1bc");
+    assert_eq!(parser.screen().encode_paste(b"\x03"), b"\x03");
+}
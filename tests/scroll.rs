@@ -12,6 +12,11 @@ fn origin_mode() {
     helpers::fixture("origin_mode");
 }
 
+#[test]
+fn left_right_margins() {
+    helpers::fixture("left_right_margins");
+}
+
 #[test]
 fn scrollback() {
     let mut parser = vt100::Parser::new(24, 80, 10);
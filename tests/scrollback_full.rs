@@ -69,7 +69,10 @@ fn formatting_preservation() {
     new_parser.process(&formatted);
 
     // Verify the text matches
-    assert_eq!(new_parser.screen().contents(), parser.screen().contents_full());
+    assert_eq!(
+        new_parser.screen().contents(),
+        parser.screen().contents_full()
+    );
 
     // Verify formatting on specific cells
     // Row 0 (red line): should be red (color idx 1)
@@ -234,7 +237,7 @@ fn rows_formatted_full_basic() {
     assert_eq!(rows.len(), 5);
 
     // First row should contain red SGR code and "red"
-    assert!(rows[0].len() > 0);
+    assert!(!rows[0].is_empty());
     let row0_str = String::from_utf8_lossy(&rows[0]);
     assert!(row0_str.contains("red"));
 }
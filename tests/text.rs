@@ -25,6 +25,30 @@ fn combining() {
     helpers::fixture("combining");
 }
 
+#[test]
+fn grapheme_capacity() {
+    let mut parser = vt100::Parser::default();
+
+    // cells can hold 15 bytes of text, and further combining characters are
+    // dropped
+    let full = format!("a{}", "\u{301}".repeat(7));
+    parser.process(format!("{full}\u{301}b").as_bytes());
+    assert_eq!(parser.screen().cell(0, 0).unwrap().contents(), full);
+    assert_eq!(parser.screen().cell(0, 1).unwrap().contents(), "b");
+
+    // truncating never leaves a dangling zero width joiner
+    let prefix = format!("a{}", "\u{301}".repeat(5));
+    parser.process(format!("\r\n{prefix}\u{200d}\u{301}b").as_bytes());
+    assert_eq!(parser.screen().cell(1, 0).unwrap().contents(), prefix);
+    assert_eq!(parser.screen().cell(1, 1).unwrap().contents(), "b");
+
+    // wide characters each get their own cell, even when joined
+    parser.process("\r\n👨\u{200d}👩\u{200d}👧".as_bytes());
+    assert_eq!(parser.screen().cell(2, 0).unwrap().contents(), "👨\u{200d}");
+    assert_eq!(parser.screen().cell(2, 2).unwrap().contents(), "👩\u{200d}");
+    assert_eq!(parser.screen().cell(2, 4).unwrap().contents(), "👧");
+}
+
 #[test]
 fn wrap() {
     helpers::fixture("wrap");